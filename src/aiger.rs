@@ -0,0 +1,759 @@
+use std::collections::{HashMap,HashSet};
+
+use crate::formula::{BooleanFormula, Expression};
+use crate::sat::{CNF, Clause, Literal, Polarity, TruthAssignment, check_sat_cnf_and_find_assignment};
+use crate::symbols::{SymbolTable, is_valid_name};
+use crate::operators::Syntax;
+
+// AIGER literals are twice the variable, plus one when negated: 0 is F and 1 is T
+pub const FALSE_LITERAL:u32=0;
+pub const TRUE_LITERAL:u32=1;
+
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+pub struct AndGate{
+    pub lhs: u32,
+    pub rhs0: u32,
+    pub rhs1: u32
+}
+
+// line is the line of the file where the problem was found
+#[derive(Debug,Clone,PartialEq,Eq)]
+pub struct AigerError{
+    pub message: String,
+    pub line: usize
+}
+
+impl std::fmt::Display for AigerError{
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for AigerError{}
+
+// a combinational and-inverter graph, read from or written to the ASCII (aag)
+// and binary (aig) AIGER formats; latches are not supported
+#[derive(Debug,Clone,Default,PartialEq,Eq)]
+pub struct Aig{
+    max_variable: u32,
+    inputs: Vec<u32>,
+    outputs: Vec<u32>,
+    ands: Vec<AndGate>,
+    input_names: Vec<Option<String>>,
+    output_names: Vec<Option<String>>,
+    comments: Vec<String>
+}
+
+struct Reader<'a>{
+    bytes: &'a [u8],
+    position: usize,
+    line: usize
+}
+
+impl<'a> Reader<'a>{
+    fn error(&self,message:&str)->AigerError{
+        AigerError { message: message.to_string(), line: self.line }
+    }
+
+    fn at_end(&self)->bool{
+        self.position>=self.bytes.len()
+    }
+
+    fn read_line(&mut self)->Result<&'a str,AigerError>{
+        if self.at_end(){
+            return Err(AigerError { message: "unexpected end of file".to_string(), line: self.line+1 });
+        }
+        let start=self.position;
+        while self.position<self.bytes.len() && self.bytes[self.position]!=b'\n'{
+            self.position+=1;
+        }
+        let end=self.position;
+        self.position+=1;
+        self.line+=1;
+        match std::str::from_utf8(&self.bytes[start..end]){
+            Ok(line)=>Ok(line.trim_end_matches('\r')),
+            Err(_)=>Err(self.error("invalid text"))
+        }
+    }
+
+    fn read_numbers(&mut self,count:usize)->Result<Vec<u32>,AigerError>{
+        let line=match self.read_line(){
+            Ok(line)=>line,
+            Err(e)=>{return Err(e);}
+        };
+        let mut numbers:Vec<u32>=vec![];
+        for field in line.split(' '){
+            match field.parse::<u32>(){
+                Ok(n)=>numbers.push(n),
+                Err(_)=>{return Err(self.error(&format!("expected a number, found \"{}\"",field)));}
+            }
+        }
+        if numbers.len()!=count{
+            return Err(self.error(&format!("expected {} numbers, found {}",count,numbers.len())));
+        }
+        Ok(numbers)
+    }
+
+    // the binary and gates store deltas in 7 bit groups, lowest first
+    fn read_delta(&mut self)->Result<u32,AigerError>{
+        let mut res:u32=0;
+        let mut shift=0;
+        loop{
+            if self.at_end(){
+                return Err(self.error("unexpected end of file in the and gates"));
+            }
+            let byte=self.bytes[self.position];
+            self.position+=1;
+            if shift>28 || (shift==28 && byte&0x70!=0){
+                return Err(self.error("delta does not fit 32 bits"));
+            }
+            res|=((byte&0x7f) as u32)<<shift;
+            if byte&0x80==0{
+                return Ok(res);
+            }
+            shift+=7;
+        }
+    }
+}
+
+fn write_delta(bytes:&mut Vec<u8>,mut delta:u32){
+    while delta>=0x80{
+        bytes.push((delta&0x7f) as u8|0x80);
+        delta>>=7;
+    }
+    bytes.push(delta as u8);
+}
+
+impl Aig{
+    pub fn get_max_variable(&self)->u32{
+        self.max_variable
+    }
+
+    pub fn get_inputs(&self)->&Vec<u32>{
+        &self.inputs
+    }
+
+    pub fn get_outputs(&self)->&Vec<u32>{
+        &self.outputs
+    }
+
+    pub fn get_ands(&self)->&Vec<AndGate>{
+        &self.ands
+    }
+
+    pub fn get_input_name(&self,input:usize)->Option<&String>{
+        self.input_names.get(input).and_then(|x|x.as_ref())
+    }
+
+    pub fn get_output_name(&self,output:usize)->Option<&String>{
+        self.output_names.get(output).and_then(|x|x.as_ref())
+    }
+
+    pub fn get_comments(&self)->&Vec<String>{
+        &self.comments
+    }
+
+    pub fn from_aag(text:&str)->Result<Self,AigerError>{
+        Self::from_bytes(text.as_bytes())
+    }
+
+    // reads either format, as told by the header
+    pub fn from_bytes(bytes:&[u8])->Result<Self,AigerError>{
+        let mut reader=Reader { bytes, position: 0, line: 0 };
+        let header=match reader.read_line(){
+            Ok(line)=>line,
+            Err(e)=>{return Err(e);}
+        };
+        let fields:Vec<&str>=header.split(' ').collect();
+        let binary=match fields[0]{
+            "aag"=>false,
+            "aig"=>true,
+            _=>{return Err(reader.error("expected the header aag M I L O A or aig M I L O A"));}
+        };
+        let mut numbers:Vec<u32>=vec![];
+        for field in fields[1..].iter(){
+            match field.parse::<u32>(){
+                Ok(n)=>numbers.push(n),
+                Err(_)=>{return Err(reader.error(&format!("expected a number, found \"{}\"",field)));}
+            }
+        }
+        if numbers.len()<5 || numbers.len()>9{
+            return Err(reader.error("expected the header aag M I L O A or aig M I L O A"));
+        }
+        let (max_variable,input_count,latch_count,output_count,and_count)=(numbers[0],numbers[1],numbers[2],numbers[3],numbers[4]);
+        if latch_count>0{
+            return Err(reader.error("latches are not supported, only combinational circuits"));
+        }
+        // the AIGER 1.9 sections for bad states, constraints, justice and fairness
+        if numbers[5..].iter().any(|n|*n>0){
+            return Err(reader.error("bad state, constraint, justice and fairness properties are not supported"));
+        }
+        if (max_variable as u64)>=(1u64<<31) || (input_count as u64)+(and_count as u64)>(max_variable as u64){
+            return Err(reader.error("M must be at least I+L+A and below 2^31"));
+        }
+        if binary && input_count+and_count!=max_variable{
+            return Err(reader.error("in the binary format M must be I+L+A"));
+        }
+        let mut aig=Aig { max_variable, ..Aig::default() };
+        for i in 0..input_count{
+            if binary{
+                aig.inputs.push(2*(i+1));
+                continue;
+            }
+            match reader.read_numbers(1){
+                Ok(numbers)=>aig.inputs.push(numbers[0]),
+                Err(e)=>{return Err(e);}
+            }
+        }
+        for _ in 0..output_count{
+            match reader.read_numbers(1){
+                Ok(numbers)=>aig.outputs.push(numbers[0]),
+                Err(e)=>{return Err(e);}
+            }
+        }
+        for i in 0..and_count{
+            if !binary{
+                match reader.read_numbers(3){
+                    Ok(numbers)=>aig.ands.push(AndGate { lhs: numbers[0], rhs0: numbers[1], rhs1: numbers[2] }),
+                    Err(e)=>{return Err(e);}
+                }
+                continue;
+            }
+            let lhs=2*(input_count+i+1);
+            let first=match reader.read_delta(){
+                Ok(delta)=>delta,
+                Err(e)=>{return Err(e);}
+            };
+            let second=match reader.read_delta(){
+                Ok(delta)=>delta,
+                Err(e)=>{return Err(e);}
+            };
+            if first==0 || first>lhs || second>lhs-first{
+                return Err(reader.error(&format!("invalid deltas for the and gate {}",lhs)));
+            }
+            aig.ands.push(AndGate { lhs, rhs0: lhs-first, rhs1: lhs-first-second });
+        }
+        aig.input_names=vec![None;aig.inputs.len()];
+        aig.output_names=vec![None;aig.outputs.len()];
+        while !reader.at_end(){
+            let line=match reader.read_line(){
+                Ok(line)=>line,
+                Err(e)=>{return Err(e);}
+            };
+            if line=="c"{
+                while !reader.at_end(){
+                    match reader.read_line(){
+                        Ok(comment)=>aig.comments.push(comment.to_string()),
+                        Err(e)=>{return Err(e);}
+                    }
+                }
+                break;
+            }
+            if line.is_empty(){
+                continue;
+            }
+            let names=match line.as_bytes()[0]{
+                b'i'=>&mut aig.input_names,
+                b'o'=>&mut aig.output_names,
+                _=>{return Err(reader.error("expected a symbol such as i0 name"));}
+            };
+            let (position,name)=match line[1..].split_once(' '){
+                Some(pair)=>pair,
+                None=>{return Err(reader.error("expected a symbol such as i0 name"));}
+            };
+            match position.parse::<usize>(){
+                Ok(i) if i<names.len()=>{names[i]=Some(name.to_string());},
+                _=>{return Err(reader.error(&format!("no {} to name",&line[..position.len()+1])));}
+            }
+        }
+        match aig.check(){
+            Ok(())=>Ok(aig),
+            Err(message)=>Err(AigerError { message, line: 0 })
+        }
+    }
+
+    // every literal must refer to F, an input or an and gate defined once, without cycles
+    fn check(&self)->Result<(),String>{
+        let mut defined:HashSet<u32>=HashSet::from([0]);
+        for lhs in self.inputs.iter().chain(self.ands.iter().map(|gate|&gate.lhs)){
+            if lhs%2==1 || *lhs<2 || lhs/2>self.max_variable{
+                return Err(format!("{} can not be defined",lhs));
+            }
+            if !defined.insert(lhs/2){
+                return Err(format!("{} is defined twice",lhs));
+            }
+        }
+        let uses=self.outputs.iter().chain(self.ands.iter().flat_map(|gate|[&gate.rhs0,&gate.rhs1]));
+        for literal in uses{
+            if !defined.contains(&(literal/2)){
+                return Err(format!("{} is used but not defined",literal));
+            }
+        }
+        match self.topological_order(){
+            Some(_)=>Ok(()),
+            None=>Err("the and gates form a cycle".to_string())
+        }
+    }
+
+    // the indices of the and gates with each gate after those it reads, None for a cycle
+    pub(crate) fn topological_order(&self)->Option<Vec<usize>>{
+        let gate_of:HashMap<u32,usize>=self.ands.iter().enumerate().map(|(i,gate)|(gate.lhs/2,i)).collect();
+        // 0 not visited, 1 on the stack, 2 done
+        let mut state:Vec<u8>=vec![0;self.ands.len()];
+        let mut order:Vec<usize>=vec![];
+        for root in 0..self.ands.len(){
+            if state[root]!=0{
+                continue;
+            }
+            let mut stack:Vec<(usize,bool)>=vec![(root,false)];
+            while let Some((gate,expanded))=stack.pop(){
+                if expanded{
+                    state[gate]=2;
+                    order.push(gate);
+                    continue;
+                }
+                if state[gate]==2{
+                    continue;
+                }
+                state[gate]=1;
+                stack.push((gate,true));
+                for literal in [self.ands[gate].rhs0,self.ands[gate].rhs1]{
+                    if let Some(&child)=gate_of.get(&(literal/2)){
+                        match state[child]{
+                            0=>stack.push((child,false)),
+                            1=>{return None;},
+                            _=>{}
+                        }
+                    }
+                }
+            }
+        }
+        Some(order)
+    }
+
+    pub fn to_aag(&self)->String{
+        let mut res=format!("aag {} {} 0 {} {}\n",self.max_variable,self.inputs.len(),self.outputs.len(),self.ands.len());
+        for input in self.inputs.iter(){
+            res.push_str(&format!("{}\n",input));
+        }
+        for output in self.outputs.iter(){
+            res.push_str(&format!("{}\n",output));
+        }
+        for gate in self.ands.iter(){
+            res.push_str(&format!("{} {} {}\n",gate.lhs,gate.rhs0,gate.rhs1));
+        }
+        res.push_str(&self.symbols_and_comments());
+        res
+    }
+
+    // the binary format wants the inputs first and every gate after the gates it
+    // reads, so the variables are numbered again in that order
+    pub fn to_aig(&self)->Vec<u8>{
+        let mut renumbered:HashMap<u32,u32>=HashMap::from([(0,0)]);
+        for (i,input) in self.inputs.iter().enumerate(){
+            renumbered.insert(input/2,i as u32+1);
+        }
+        let order=self.topological_order().unwrap();
+        for (i,gate) in order.iter().enumerate(){
+            renumbered.insert(self.ands[*gate].lhs/2,(self.inputs.len()+i) as u32+1);
+        }
+        let literal=|x:u32|2*renumbered[&(x/2)]+(x&1);
+        let mut bytes:Vec<u8>=format!("aig {} {} 0 {} {}\n",self.inputs.len()+self.ands.len(),self.inputs.len(),self.outputs.len(),self.ands.len()).into_bytes();
+        for output in self.outputs.iter(){
+            bytes.extend(format!("{}\n",literal(*output)).into_bytes());
+        }
+        for gate in order.iter(){
+            let gate=&self.ands[*gate];
+            let lhs=literal(gate.lhs);
+            let (rhs0,rhs1)=(literal(gate.rhs0).max(literal(gate.rhs1)),literal(gate.rhs0).min(literal(gate.rhs1)));
+            write_delta(&mut bytes,lhs-rhs0);
+            write_delta(&mut bytes,rhs0-rhs1);
+        }
+        bytes.extend(self.symbols_and_comments().into_bytes());
+        bytes
+    }
+
+    fn symbols_and_comments(&self)->String{
+        let mut res="".to_string();
+        for (i,name) in self.input_names.iter().enumerate(){
+            if let Some(name)=name{
+                res.push_str(&format!("i{} {}\n",i,name));
+            }
+        }
+        for (i,name) in self.output_names.iter().enumerate(){
+            if let Some(name)=name{
+                res.push_str(&format!("o{} {}\n",i,name));
+            }
+        }
+        if !self.comments.is_empty(){
+            res.push_str("c\n");
+            for comment in self.comments.iter(){
+                res.push_str(&format!("{}\n",comment));
+            }
+        }
+        res
+    }
+
+    // input i is the variable i+1, named after the symbol of the input when it
+    // is a valid name used by no other input
+    pub fn get_symbols(&self)->SymbolTable{
+        let mut symbols=SymbolTable::new();
+        let syntax=Syntax::default();
+        for (i,name) in self.input_names.iter().enumerate(){
+            if let Some(name)=name{
+                if is_valid_name(name,&syntax) && symbols.get_id(name).is_none(){
+                    symbols.insert(name,i as i32+1);
+                }
+            }
+        }
+        symbols
+    }
+
+    // the outputs as formulas over the inputs, gates read in several places are
+    // shared rather than copied
+    pub fn output_formulas(&self)->Vec<BooleanFormula>{
+        BooleanFormula::from_aig(self)
+    }
+
+    // one input per variable of the formulas, in increasing order of id, and one
+    // output per formula; the formulas are expected to share their symbol table
+    pub fn from_formulas(formulas:&[BooleanFormula])->Self{
+        let mut variables:Vec<i32>=formulas.iter().flat_map(|f|f.get_variables().iter().copied()).collect();
+        variables.sort();
+        variables.dedup();
+        let mut builder=AigBuilder::new(variables.len());
+        for (i,x) in variables.iter().enumerate(){
+            builder.aig.input_names[i]=formulas.iter().find_map(|f|f.get_symbols().get_name(*x).cloned());
+        }
+        let inputs:HashMap<i32,u32>=variables.iter().enumerate().map(|(i,x)|(*x,2*(i as u32+1))).collect();
+        let outputs:Vec<u32>=formulas.iter().map(|f|builder.expression(&f.to_expression(),&inputs)).collect();
+        builder.finish(outputs)
+    }
+
+    pub fn from_formula(formula:&BooleanFormula)->Self{
+        Self::from_formulas(std::slice::from_ref(formula))
+    }
+
+    // Tseitin encoding of the cone of the output, asserted true: input i is the
+    // variable i+1 as in output_formulas, each and gate gets a variable above them
+    pub fn to_cnf(&self,output:usize)->CNF{
+        let input_count=self.inputs.len() as i32;
+        let mut variable:HashMap<u32,i32>=HashMap::new();
+        for (i,input) in self.inputs.iter().enumerate(){
+            variable.insert(input/2,i as i32+1);
+        }
+        let gate_of:HashMap<u32,&AndGate>=self.ands.iter().map(|gate|(gate.lhs/2,gate)).collect();
+        let mut cnf=CNF::new().with_symbols(self.get_symbols());
+        let mut stack:Vec<u32>=vec![self.outputs[output]/2];
+        let mut next=input_count+1;
+        while let Some(x)=stack.pop(){
+            if x==0 || variable.contains_key(&x){
+                continue;
+            }
+            variable.insert(x,next);
+            next+=1;
+            let gate=gate_of[&x];
+            stack.push(gate.rhs0/2);
+            stack.push(gate.rhs1/2);
+        }
+        let mut add_clause=|literals:&[u32]|{
+            if literals.contains(&TRUE_LITERAL){
+                return;
+            }
+            let mut clause=Clause::new();
+            for literal in literals.iter().filter(|x|**x!=FALSE_LITERAL){
+                let polarity=if literal&1==1{Polarity::Negative}else{Polarity::Positive};
+                clause.add_literal(Literal::new(polarity,variable[&(literal/2)]));
+            }
+            cnf.add_clause(clause);
+        };
+        for gate in self.ands.iter(){
+            if !variable.contains_key(&(gate.lhs/2)){
+                continue;
+            }
+            add_clause(&[gate.lhs^1,gate.rhs0]);
+            add_clause(&[gate.lhs^1,gate.rhs1]);
+            add_clause(&[gate.lhs,gate.rhs0^1,gate.rhs1^1]);
+        }
+        add_clause(&[self.outputs[output]]);
+        cnf
+    }
+
+    // values of the inputs, as variables 1 to I, that make the output true
+    pub fn check_output(&self,output:usize)->Option<TruthAssignment>{
+        let found=match check_sat_cnf_and_find_assignment(self.to_cnf(output)){
+            Some(assignment)=>assignment,
+            None=>{return None;}
+        };
+        let mut assignment=TruthAssignment::new();
+        for i in 0..self.inputs.len(){
+            assignment.add_assignment(i as i32+1,found.get_assignment_or_default(i as i32+1));
+        }
+        Some(assignment)
+    }
+
+    // the circuits are matched input by input and output by output
+    pub fn equivalent(&self,other:&Aig)->Result<bool,String>{
+        if self.inputs.len()!=other.inputs.len() || self.outputs.len()!=other.outputs.len(){
+            return Err(format!("the circuits have {} and {} inputs, {} and {} outputs",
+                self.inputs.len(),other.inputs.len(),self.outputs.len(),other.outputs.len()));
+        }
+        let mut builder=AigBuilder::new(self.inputs.len());
+        let left=builder.copy(self);
+        let right=builder.copy(other);
+        let mut difference=FALSE_LITERAL;
+        for (a,b) in left.into_iter().zip(right){
+            let different=builder.xor(a,b);
+            difference=builder.or(difference,different);
+        }
+        let miter=builder.finish(vec![difference]);
+        Ok(miter.check_output(0).is_none())
+    }
+}
+
+// adds and gates with constant folding and structural hashing, so that
+// the same gate is never built twice
+struct AigBuilder{
+    aig: Aig,
+    gates: HashMap<(u32,u32),u32>
+}
+
+impl AigBuilder{
+    fn new(inputs:usize)->Self{
+        let aig=Aig {
+            max_variable: inputs as u32,
+            inputs: (1..=inputs as u32).map(|i|2*i).collect(),
+            input_names: vec![None;inputs],
+            ..Aig::default()
+        };
+        AigBuilder { aig, gates: HashMap::new() }
+    }
+
+    fn and(&mut self,a:u32,b:u32)->u32{
+        if a==FALSE_LITERAL || b==FALSE_LITERAL || a==b^1{
+            return FALSE_LITERAL;
+        }
+        if a==TRUE_LITERAL || a==b{
+            return b;
+        }
+        if b==TRUE_LITERAL{
+            return a;
+        }
+        let key=(a.max(b),a.min(b));
+        if let Some(&lhs)=self.gates.get(&key){
+            return lhs;
+        }
+        self.aig.max_variable+=1;
+        let lhs=2*self.aig.max_variable;
+        self.aig.ands.push(AndGate { lhs, rhs0: key.0, rhs1: key.1 });
+        self.gates.insert(key,lhs);
+        lhs
+    }
+
+    fn or(&mut self,a:u32,b:u32)->u32{
+        self.and(a^1,b^1)^1
+    }
+
+    fn xor(&mut self,a:u32,b:u32)->u32{
+        let left=self.and(a,b^1);
+        let right=self.and(a^1,b);
+        self.or(left,right)
+    }
+
+    fn expression(&mut self,expression:&Expression,inputs:&HashMap<i32,u32>)->u32{
+        match expression{
+            Expression::Variable(x)=>inputs[x],
+            Expression::Constant(true)=>TRUE_LITERAL,
+            Expression::Constant(false)=>FALSE_LITERAL,
+            Expression::Not(a)=>self.expression(a,inputs)^1,
+            Expression::And(vec)=>{
+                let mut res=TRUE_LITERAL;
+                for e in vec.iter(){
+                    let x=self.expression(e,inputs);
+                    res=self.and(res,x);
+                }
+                res
+            },
+            Expression::Or(vec)=>{
+                let mut res=FALSE_LITERAL;
+                for e in vec.iter(){
+                    let x=self.expression(e,inputs);
+                    res=self.or(res,x);
+                }
+                res
+            },
+            Expression::Xor(a,b)=>{
+                let (a,b)=(self.expression(a,inputs),self.expression(b,inputs));
+                self.xor(a,b)
+            },
+            Expression::Implies(a,b)=>{
+                let (a,b)=(self.expression(a,inputs),self.expression(b,inputs));
+                self.or(a^1,b)
+            },
+            Expression::Iff(a,b)=>{
+                let (a,b)=(self.expression(a,inputs),self.expression(b,inputs));
+                self.xor(a,b)^1
+            },
+            Expression::Ite(c,a,b)=>{
+                let (c,a,b)=(self.expression(c,inputs),self.expression(a,inputs),self.expression(b,inputs));
+                let left=self.and(c,a);
+                let right=self.and(c^1,b);
+                self.or(left,right)
+            },
+            Expression::AtMost(k,vec)|
+            Expression::AtLeast(k,vec)|
+            Expression::Exactly(k,vec)=>{
+                // counts[j] holds when at least j of the inputs hold
+                let limit=k.saturating_add(1).min(vec.len());
+                let mut counts=vec![FALSE_LITERAL;limit+1];
+                counts[0]=TRUE_LITERAL;
+                for (i,e) in vec.iter().enumerate(){
+                    let x=self.expression(e,inputs);
+                    for j in (1..=limit.min(i+1)).rev(){
+                        let with_x=self.and(x,counts[j-1]);
+                        counts[j]=self.or(counts[j],with_x);
+                    }
+                }
+                let at_least=|j:usize|counts.get(j).copied().unwrap_or(FALSE_LITERAL);
+                match expression{
+                    Expression::AtLeast(_,_)=>at_least(*k),
+                    Expression::AtMost(_,_)=>at_least(k.saturating_add(1))^1,
+                    _=>self.and(at_least(*k),at_least(k.saturating_add(1))^1)
+                }
+            },
+            Expression::Parity(vec)=>{
+                let mut res=FALSE_LITERAL;
+                for e in vec.iter(){
+                    let x=self.expression(e,inputs);
+                    res=self.xor(res,x);
+                }
+                res
+            }
+        }
+    }
+
+    // the gates of aig over the inputs of the builder, returns its outputs
+    fn copy(&mut self,aig:&Aig)->Vec<u32>{
+        let mut literals:HashMap<u32,u32>=HashMap::new();
+        literals.insert(0,FALSE_LITERAL);
+        for (i,input) in aig.inputs.iter().enumerate(){
+            literals.insert(input/2,self.aig.inputs[i]);
+        }
+        let literal=|literals:&HashMap<u32,u32>,x:u32|literals[&(x/2)]^(x&1);
+        for gate in aig.topological_order().unwrap(){
+            let gate=&aig.ands[gate];
+            let (a,b)=(literal(&literals,gate.rhs0),literal(&literals,gate.rhs1));
+            let lhs=self.and(a,b);
+            literals.insert(gate.lhs/2,lhs);
+        }
+        aig.outputs.iter().map(|x|literal(&literals,*x)).collect()
+    }
+
+    fn finish(mut self,outputs:Vec<u32>)->Aig{
+        self.aig.output_names=vec![None;outputs.len()];
+        self.aig.outputs=outputs;
+        self.aig
+    }
+}
+
+#[cfg(test)]
+mod test_aiger{
+    use super::*;
+    use crate::operators::*;
+
+    // the and gate example of the AIGER documentation
+    const AND_GATE:&str="aag 3 2 0 1 1\n2\n4\n6\n6 2 4\ni0 x\ni1 y\no0 both\nc\nan and gate\n";
+
+    #[test]
+    fn read_ascii(){
+        let aig=Aig::from_aag(AND_GATE).unwrap();
+        assert_eq!(aig.get_inputs(),&vec![2,4]);
+        assert_eq!(aig.get_output_name(0).unwrap(),"both");
+        assert_eq!(aig.get_comments(),&vec!["an and gate".to_string()]);
+        assert_eq!(aig.output_formulas()[0].to_string(),format!("x{}y",AND_OPERATOR_SYMBOL));
+        assert_eq!(aig.to_aag(),AND_GATE);
+    }
+
+    #[test]
+    fn binary_round_trip(){
+        let aig=Aig::from_aag(AND_GATE).unwrap();
+        let bytes=aig.to_aig();
+        assert!(bytes.starts_with(b"aig 3 2 0 1 1\n6\n\x02\x02i0 x\n"));
+        let read=Aig::from_bytes(&bytes).unwrap();
+        assert_eq!(read.get_ands(),&vec![AndGate { lhs: 6, rhs0: 4, rhs1: 2 }]);
+        assert_eq!(read.get_output_name(0).unwrap(),"both");
+        assert_eq!(read.get_comments(),aig.get_comments());
+        assert_eq!(read.to_aig(),bytes);
+    }
+
+    #[test]
+    fn binary_renumbers_the_gates(){
+        // gate 4 reads gate 6, which the binary format does not allow
+        let text="aag 3 1 0 1 2\n2\n5\n4 6 2\n6 2 3\n";
+        let aig=Aig::from_aag(text).unwrap();
+        let read=Aig::from_bytes(&aig.to_aig()).unwrap();
+        assert_eq!(read.get_max_variable(),3);
+        assert!(aig.equivalent(&read).unwrap());
+        assert!(aig.check_output(0).is_some());
+    }
+
+    #[test]
+    fn formulas_to_circuits(){
+        let formula=BooleanFormula::from_string(format!("(a{o}b){a}{n}(a{a}b)",o=OR_OPERATOR_SYMBOL,a=AND_OPERATOR_SYMBOL,n=NEGATION_OPERATOR_SYMBOL)).unwrap();
+        let aig=Aig::from_formula(&formula);
+        assert_eq!(aig.get_inputs().len(),2);
+        assert_eq!(aig.get_input_name(0).unwrap(),"a");
+        let xor=Aig::from_formula(&BooleanFormula::from_string(format!("a{}b",XOR_OPERATOR_SYMBOL)).unwrap());
+        assert!(aig.equivalent(&xor).unwrap());
+        let iff=Aig::from_formula(&BooleanFormula::from_string(format!("a{}b",IFF_OPERATOR_SYMBOL)).unwrap());
+        assert!(!aig.equivalent(&iff).unwrap());
+        let back=Aig::from_formula(&aig.output_formulas()[0]);
+        assert!(back.equivalent(&xor).unwrap());
+    }
+
+    #[test]
+    fn shared_gates_stay_shared(){
+        // x1^x2^...^x41, each xor is three gates reading the previous one twice
+        let n:u32=40;
+        let mut gates:Vec<String>=vec![];
+        let mut xor=2;
+        let mut next=n+2;
+        for input in 2..=n+1{
+            let b=2*input;
+            let (left,right,both)=(2*next,2*next+2,2*next+4);
+            gates.push(format!("{} {} {}",left,xor,b+1));
+            gates.push(format!("{} {} {}",right,xor+1,b));
+            gates.push(format!("{} {} {}",both,left+1,right+1));
+            xor=both+1;
+            next+=3;
+        }
+        let inputs:Vec<String>=(1..=n+1).map(|x|(2*x).to_string()).collect();
+        let text=format!("aag {} {} 0 1 {}\n{}\n{}\n{}\n",next-1,n+1,3*n,inputs.join("\n"),xor,gates.join("\n"));
+        let aig=Aig::from_aag(&text).unwrap();
+        let formula=&aig.output_formulas()[0];
+        assert_eq!(formula.get_variables().len(),n as usize+1);
+        // copying the gates would take about 2^n nodes
+        assert!(formula.shared_size()<10*n as usize);
+    }
+
+    #[test]
+    fn satisfiability_of_outputs(){
+        let contradiction=BooleanFormula::from_string(format!("a{}{}a",AND_OPERATOR_SYMBOL,NEGATION_OPERATOR_SYMBOL)).unwrap();
+        let formula=BooleanFormula::from_string(format!("a{}{}b",AND_OPERATOR_SYMBOL,NEGATION_OPERATOR_SYMBOL)).unwrap();
+        let aig=Aig::from_formulas(&[contradiction,formula]);
+        assert_eq!(aig.get_outputs()[0],FALSE_LITERAL);
+        assert!(aig.check_output(0).is_none());
+        let assignment=aig.check_output(1).unwrap();
+        assert_eq!(assignment.get_assignment(1),Some(true));
+        assert_eq!(assignment.get_assignment(2),Some(false));
+    }
+
+    #[test]
+    fn invalid_files(){
+        assert_eq!(Aig::from_aag("aag 1 0 1 0 0\n2 3\n").unwrap_err().line,1);
+        assert!(Aig::from_aag("aag 2 1 0 1 0\n2\n4\n").is_err());
+        assert!(Aig::from_aag("aag 3 1 0 1 2\n2\n4\n4 6 2\n6 4 2\n").is_err());
+        assert!(Aig::from_aag("aag 1 1 0 0 0\n2\ni3 x\n").is_err());
+        assert!(Aig::from_bytes(b"aig 2 1 0 1 1\n4\n\x05").is_err());
+    }
+}
@@ -0,0 +1,212 @@
+use std::collections::BTreeSet;
+
+use crate::formula::BooleanFormula;
+
+pub const DEFAULT_ANF_MAX_MONOMIALS:usize=4096;
+
+// a conjunction of variables, the empty monomial is the constant T
+pub type Monomial=BTreeSet<i32>;
+
+// a xor of monomials (a polynomial over GF(2)), two formulas
+// are equivalent exactly when their ANFs are identical
+#[derive(Debug,Clone,PartialEq,Eq,Hash)]
+pub struct AlgebraicNormalForm{
+    monomials: BTreeSet<Monomial>
+}
+
+impl AlgebraicNormalForm{
+    pub fn zero()->Self{
+        AlgebraicNormalForm { monomials: BTreeSet::new() }
+    }
+
+    pub fn one()->Self{
+        AlgebraicNormalForm { monomials: BTreeSet::from([Monomial::new()]) }
+    }
+
+    pub fn variable(variable:i32)->Self{
+        AlgebraicNormalForm { monomials: BTreeSet::from([Monomial::from([variable])]) }
+    }
+
+    pub fn is_zero(&self)->bool{
+        self.monomials.is_empty()
+    }
+
+    pub fn is_one(&self)->bool{
+        self.monomials.len()==1 && self.has_constant_term()
+    }
+
+    pub fn has_constant_term(&self)->bool{
+        self.monomials.contains(&Monomial::new())
+    }
+
+    pub fn len(&self)->usize{
+        self.monomials.len()
+    }
+
+    pub fn is_empty(&self)->bool{
+        self.monomials.is_empty()
+    }
+
+    pub fn degree(&self)->usize{
+        self.monomials.iter().map(|m|m.len()).max().unwrap_or(0)
+    }
+
+    // monomials by increasing degree, ties broken by their variables
+    pub fn get_monomials(&self)->Vec<&Monomial>{
+        let mut res:Vec<&Monomial>=self.monomials.iter().collect();
+        res.sort_by(|a,b|a.len().cmp(&b.len()).then(a.cmp(b)));
+        res
+    }
+
+    pub fn get_variables(&self)->BTreeSet<i32>{
+        self.monomials.iter().flatten().copied().collect()
+    }
+
+    pub fn xor(&self,other:&Self)->Self{
+        let monomials=self.monomials.symmetric_difference(&other.monomials).cloned().collect();
+        AlgebraicNormalForm { monomials }
+    }
+
+    pub fn not(&self)->Self{
+        self.xor(&Self::one())
+    }
+
+    pub fn and(&self,other:&Self,max_monomials:usize)->Result<Self,String>{
+        let mut monomials:BTreeSet<Monomial>=BTreeSet::new();
+        for left in self.monomials.iter(){
+            for right in other.monomials.iter(){
+                let product:Monomial=left.union(right).copied().collect();
+                // x%x |= F
+                if !monomials.remove(&product){
+                    monomials.insert(product);
+                }
+            }
+            if monomials.len()>max_monomials{
+                return Err(anf_limit_error(max_monomials));
+            }
+        }
+        Ok(AlgebraicNormalForm { monomials })
+    }
+
+    // a*b |= a%b%(a+b)
+    pub fn or(&self,other:&Self,max_monomials:usize)->Result<Self,String>{
+        let product=match self.and(other,max_monomials){
+            Ok(anf)=>anf,
+            Err(s)=>{return Err(s);}
+        };
+        let res=self.xor(other).xor(&product);
+        if res.len()>max_monomials{
+            return Err(anf_limit_error(max_monomials));
+        }
+        Ok(res)
+    }
+
+    pub fn restrict(&self,variable:i32,value:bool)->Self{
+        let mut res=Self::zero();
+        for monomial in self.monomials.iter(){
+            if !monomial.contains(&variable){
+                res.toggle(monomial.clone());
+            }else if value{
+                let mut reduced=monomial.clone();
+                reduced.remove(&variable);
+                res.toggle(reduced);
+            }
+        }
+        res
+    }
+
+    fn toggle(&mut self,monomial:Monomial){
+        if !self.monomials.remove(&monomial){
+            self.monomials.insert(monomial);
+        }
+    }
+}
+
+pub(crate) fn anf_limit_error(max_monomials:usize)->String{
+    format!("ANF conversion aborted: the polynomial exceeds the limit of {} monomials",max_monomials)
+}
+
+impl std::fmt::Display for AlgebraicNormalForm{
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", BooleanFormula::from_anf(self))
+    }
+}
+
+#[cfg(test)]
+mod test_anf{
+    use super::*;
+    use crate::operators::*;
+
+    #[test]
+    fn xor_cancels_itself(){
+        let string=format!("1{}1",XOR_OPERATOR_SYMBOL);
+        let anf=BooleanFormula::from_string(string).unwrap().to_anf().unwrap();
+        assert!(anf.is_zero());
+    }
+
+    #[test]
+    fn or_expansion(){
+        // 1*2 |= 1%2%(1+2)
+        let string=format!("1{}2",OR_OPERATOR_SYMBOL);
+        let anf=BooleanFormula::from_string(string).unwrap().to_anf().unwrap();
+        let monomials:Vec<Vec<i32>>=anf.get_monomials().into_iter().map(|m|m.iter().copied().collect()).collect();
+        assert_eq!(monomials,vec![vec![1],vec![2],vec![1,2]]);
+        assert_eq!(anf.to_string(),format!("(1{}2){}(1{}2)",XOR_OPERATOR_SYMBOL,XOR_OPERATOR_SYMBOL,AND_OPERATOR_SYMBOL));
+    }
+
+    #[test]
+    fn constant_term_becomes_negation(){
+        let string=format!("1{}2",IFF_OPERATOR_SYMBOL);
+        let formula=BooleanFormula::from_string(string).unwrap();
+        let anf=formula.to_anf().unwrap();
+        assert!(anf.has_constant_term());
+        assert_eq!(formula.get_anf().unwrap().to_string(),format!("{}(1{}2)",NEGATION_OPERATOR_SYMBOL,XOR_OPERATOR_SYMBOL));
+    }
+
+    #[test]
+    fn equivalence_by_identity(){
+        // 1>2 and -2>-1
+        let left=BooleanFormula::from_string(format!("1{}2",IMPL_OPERATOR_SYMBOL)).unwrap();
+        let right=BooleanFormula::from_string(format!("{}2{}{}1",NEGATION_OPERATOR_SYMBOL,IMPL_OPERATOR_SYMBOL,NEGATION_OPERATOR_SYMBOL)).unwrap();
+        assert!(left.anf_equivalent(&right).unwrap());
+        let other=BooleanFormula::from_string(format!("2{}1",IMPL_OPERATOR_SYMBOL)).unwrap();
+        assert!(!left.anf_equivalent(&other).unwrap());
+    }
+
+    #[test]
+    fn equivalence_goes_by_names(){
+        let a=BooleanFormula::from_conventional_str("a").unwrap();
+        let b=BooleanFormula::from_conventional_str("b").unwrap();
+        assert!(!a.anf_equivalent(&b).unwrap());
+        assert!(!a.anf_equivalent(&BooleanFormula::var(1)).unwrap());
+        let left=BooleanFormula::from_conventional_str("a & !b").unwrap();
+        let right=BooleanFormula::from_conventional_str("!(b | !a)").unwrap();
+        assert!(left.anf_equivalent(&right).unwrap());
+    }
+
+    #[test]
+    fn quantifiers(){
+        // E1.(1+2) |= 2 and A1.(1*2) |= 2
+        let exists=BooleanFormula::from_string(format!("{}1{}(1{}2)",EXISTENTIAL_QUANTIFIER_SYMBOL,QUANTIFIER_SEPARATOR_SYMBOL,AND_OPERATOR_SYMBOL)).unwrap();
+        assert_eq!(exists.to_anf().unwrap(),AlgebraicNormalForm::variable(2));
+        let for_each=BooleanFormula::from_string(format!("{}1{}(1{}2)",UNIVERSAL_QUANTIFIER_SYMBOL,QUANTIFIER_SEPARATOR_SYMBOL,OR_OPERATOR_SYMBOL)).unwrap();
+        assert_eq!(for_each.to_anf().unwrap(),AlgebraicNormalForm::variable(2));
+    }
+
+    #[test]
+    fn round_trip_preserves_polynomial(){
+        let string=format!("(1{}2){}(3{}{}4)",IMPL_OPERATOR_SYMBOL,XOR_OPERATOR_SYMBOL,OR_OPERATOR_SYMBOL,NEGATION_OPERATOR_SYMBOL);
+        let anf=BooleanFormula::from_string(string).unwrap().to_anf().unwrap();
+        let back=BooleanFormula::from_anf(&anf);
+        assert_eq!(back.to_anf().unwrap(),anf);
+    }
+
+    #[test]
+    fn monomial_limit(){
+        // the disjunction of 5 variables has 31 monomials
+        let string=format!("1{o}2{o}3{o}4{o}5",o=OR_OPERATOR_SYMBOL);
+        let formula=BooleanFormula::from_string(string).unwrap();
+        assert!(formula.to_anf_with_limit(30).is_err());
+        assert_eq!(formula.to_anf_with_limit(31).unwrap().len(),31);
+    }
+}
@@ -0,0 +1,508 @@
+use std::collections::HashMap;
+
+use crate::formula::BooleanFormula;
+use crate::sat::TruthAssignment;
+use crate::symbols::SymbolTable;
+
+// index of a node inside its BddManager
+pub type BddRef=usize;
+
+pub const BDD_FALSE:BddRef=0;
+pub const BDD_TRUE:BddRef=1;
+
+#[derive(Debug,Copy,Clone,PartialEq,Eq,Hash)]
+pub enum BddOperator{And, Or, Xor, Iff}
+
+#[derive(Debug,Copy,Clone)]
+struct BddNode{
+    level: usize,
+    low: BddRef,
+    high: BddRef
+}
+
+// owns every node, so that equivalent functions built by the
+// same manager always get the same BddRef
+#[derive(Debug,Clone)]
+pub struct BddManager{
+    nodes: Vec<BddNode>,
+    unique_table: HashMap<(usize,BddRef,BddRef),BddRef>,
+    computed_cache: HashMap<(BddOperator,BddRef,BddRef),BddRef>,
+    order: Vec<i32>,
+    levels: HashMap<i32,usize>,
+    // the names of the variables of the formulas compiled so far
+    symbols: SymbolTable
+}
+
+impl BddOperator{
+    fn evaluate(&self,a:bool,b:bool)->bool{
+        match self{
+            BddOperator::And=>a&&b,
+            BddOperator::Or=>a||b,
+            BddOperator::Xor=>a!=b,
+            BddOperator::Iff=>a==b
+        }
+    }
+}
+
+impl BddManager{
+    pub fn new()->Self{
+        Self::with_order(vec![])
+    }
+
+    // variables missing from the order are appended the first time they are used
+    pub fn with_order(order:Vec<i32>)->Self{
+        let terminal=BddNode { level: usize::MAX, low: BDD_FALSE, high: BDD_FALSE };
+        let mut manager=BddManager {
+            nodes: vec![terminal,terminal],
+            unique_table: HashMap::new(),
+            computed_cache: HashMap::new(),
+            order: vec![],
+            levels: HashMap::new(),
+            symbols: SymbolTable::new()
+        };
+        for variable in order{
+            manager.level_of(variable);
+        }
+        manager
+    }
+
+    pub fn get_order(&self)->&Vec<i32>{
+        &self.order
+    }
+
+    pub fn get_symbols(&self)->&SymbolTable{
+        &self.symbols
+    }
+
+    // the first name given to a variable is kept
+    pub fn name_variable(&mut self,variable:i32,name:&str){
+        if self.symbols.get_name(variable).is_none() && self.symbols.get_id(name).is_none(){
+            self.symbols.insert(name,variable);
+        }
+    }
+
+    // the variable, low and high child of an inner node, None for the terminals
+    pub(crate) fn decision(&self,f:BddRef)->Option<(i32,BddRef,BddRef)>{
+        if f<=BDD_TRUE{
+            return None;
+        }
+        let node=self.nodes[f];
+        Some((self.order[node.level],node.low,node.high))
+    }
+
+    fn level_of(&mut self,variable:i32)->usize{
+        match self.levels.get(&variable){
+            Some(level)=>*level,
+            None=>{
+                self.order.push(variable);
+                self.levels.insert(variable,self.order.len()-1);
+                self.order.len()-1
+            }
+        }
+    }
+
+    // terminals are placed below every variable
+    fn level(&self,node:BddRef)->usize{
+        if node<=BDD_TRUE{
+            self.order.len()
+        }else{
+            self.nodes[node].level
+        }
+    }
+
+    fn make_node(&mut self,level:usize,low:BddRef,high:BddRef)->BddRef{
+        if low==high{
+            return low;
+        }
+        if let Some(node)=self.unique_table.get(&(level,low,high)){
+            return *node;
+        }
+        self.nodes.push(BddNode { level, low, high });
+        let node=self.nodes.len()-1;
+        self.unique_table.insert((level,low,high),node);
+        node
+    }
+
+    pub fn zero(&self)->BddRef{
+        BDD_FALSE
+    }
+
+    pub fn one(&self)->BddRef{
+        BDD_TRUE
+    }
+
+    pub fn variable(&mut self,variable:i32)->BddRef{
+        let level=self.level_of(variable);
+        self.make_node(level,BDD_FALSE,BDD_TRUE)
+    }
+
+    pub fn not(&mut self,f:BddRef)->BddRef{
+        self.apply(BddOperator::Xor,f,BDD_TRUE)
+    }
+
+    pub fn apply(&mut self,operator:BddOperator,f:BddRef,g:BddRef)->BddRef{
+        if f<=BDD_TRUE && g<=BDD_TRUE{
+            return if operator.evaluate(f==BDD_TRUE,g==BDD_TRUE){BDD_TRUE}else{BDD_FALSE};
+        }
+        let shortcut=match operator{
+            BddOperator::And=>{
+                if f==BDD_FALSE || g==BDD_FALSE{Some(BDD_FALSE)}
+                else if f==BDD_TRUE || f==g{Some(g)}
+                else if g==BDD_TRUE{Some(f)}
+                else{None}
+            },
+            BddOperator::Or=>{
+                if f==BDD_TRUE || g==BDD_TRUE{Some(BDD_TRUE)}
+                else if f==BDD_FALSE || f==g{Some(g)}
+                else if g==BDD_FALSE{Some(f)}
+                else{None}
+            },
+            BddOperator::Xor=>{
+                if f==g{Some(BDD_FALSE)}
+                else if f==BDD_FALSE{Some(g)}
+                else if g==BDD_FALSE{Some(f)}
+                else{None}
+            },
+            BddOperator::Iff=>{
+                if f==g{Some(BDD_TRUE)}
+                else if f==BDD_TRUE{Some(g)}
+                else if g==BDD_TRUE{Some(f)}
+                else{None}
+            }
+        };
+        if let Some(node)=shortcut{
+            return node;
+        }
+        // every operator is commutative
+        let key=(operator,f.min(g),f.max(g));
+        if let Some(node)=self.computed_cache.get(&key){
+            return *node;
+        }
+        let level=self.level(f).min(self.level(g));
+        let (f_low,f_high)=self.cofactors(f,level);
+        let (g_low,g_high)=self.cofactors(g,level);
+        let low=self.apply(operator,f_low,g_low);
+        let high=self.apply(operator,f_high,g_high);
+        let res=self.make_node(level,low,high);
+        self.computed_cache.insert(key,res);
+        res
+    }
+
+    fn cofactors(&self,f:BddRef,level:usize)->(BddRef,BddRef){
+        if self.level(f)==level{
+            (self.nodes[f].low,self.nodes[f].high)
+        }else{
+            (f,f)
+        }
+    }
+
+    pub fn and(&mut self,f:BddRef,g:BddRef)->BddRef{
+        self.apply(BddOperator::And,f,g)
+    }
+
+    pub fn or(&mut self,f:BddRef,g:BddRef)->BddRef{
+        self.apply(BddOperator::Or,f,g)
+    }
+
+    pub fn xor(&mut self,f:BddRef,g:BddRef)->BddRef{
+        self.apply(BddOperator::Xor,f,g)
+    }
+
+    pub fn iff(&mut self,f:BddRef,g:BddRef)->BddRef{
+        self.apply(BddOperator::Iff,f,g)
+    }
+
+    pub fn restrict(&mut self,f:BddRef,variable:i32,value:bool)->BddRef{
+        let level=match self.levels.get(&variable){
+            Some(level)=>*level,
+            None=>{return f;}
+        };
+        let mut memo:HashMap<BddRef,BddRef>=HashMap::new();
+        self.restrict_recursive(f,level,value,&mut memo)
+    }
+
+    fn restrict_recursive(&mut self,f:BddRef,level:usize,value:bool,memo:&mut HashMap<BddRef,BddRef>)->BddRef{
+        let node_level=self.level(f);
+        if node_level>level{
+            return f;
+        }
+        if let Some(node)=memo.get(&f){
+            return *node;
+        }
+        let node=self.nodes[f];
+        let res=if node_level==level{
+            if value{node.high}else{node.low}
+        }else{
+            let low=self.restrict_recursive(node.low,level,value,memo);
+            let high=self.restrict_recursive(node.high,level,value,memo);
+            self.make_node(node_level,low,high)
+        };
+        memo.insert(f,res);
+        res
+    }
+
+    pub fn exists(&mut self,f:BddRef,variable:i32)->BddRef{
+        let when_false=self.restrict(f,variable,false);
+        let when_true=self.restrict(f,variable,true);
+        self.or(when_false,when_true)
+    }
+
+    pub fn for_all(&mut self,f:BddRef,variable:i32)->BddRef{
+        let when_false=self.restrict(f,variable,false);
+        let when_true=self.restrict(f,variable,true);
+        self.and(when_false,when_true)
+    }
+
+    // number of models over all the variables known to the manager,
+    // None when it does not fit in a u128
+    pub fn sat_count(&self,f:BddRef)->Option<u128>{
+        let mut memo:HashMap<BddRef,Option<u128>>=HashMap::new();
+        let count=self.sat_count_recursive(f,&mut memo);
+        count.and_then(|count|scale(count,self.level(f)))
+    }
+
+    fn sat_count_recursive(&self,f:BddRef,memo:&mut HashMap<BddRef,Option<u128>>)->Option<u128>{
+        if f==BDD_FALSE{
+            return Some(0);
+        }
+        if f==BDD_TRUE{
+            return Some(1);
+        }
+        if let Some(count)=memo.get(&f){
+            return *count;
+        }
+        let node=self.nodes[f];
+        let low=self.sat_count_recursive(node.low,memo).and_then(|count|scale(count,self.level(node.low)-node.level-1));
+        let high=self.sat_count_recursive(node.high,memo).and_then(|count|scale(count,self.level(node.high)-node.level-1));
+        let count=match (low,high){
+            (Some(low),Some(high))=>low.checked_add(high),
+            _=>None
+        };
+        memo.insert(f,count);
+        count
+    }
+
+    // every total assignment over the variables known to the manager, in order
+    pub fn models(&self,f:BddRef)->Vec<TruthAssignment>{
+        let mut res:Vec<TruthAssignment>=vec![];
+        let mut current=TruthAssignment::new();
+        self.models_recursive(f,0,&mut current,&mut res);
+        res
+    }
+
+    fn models_recursive(&self,f:BddRef,level:usize,current:&mut TruthAssignment,res:&mut Vec<TruthAssignment>){
+        if f==BDD_FALSE{
+            return;
+        }
+        if level==self.order.len(){
+            res.push(current.clone());
+            return;
+        }
+        let (low,high)=self.cofactors(f,level);
+        let variable=self.order[level];
+        current.add_assignment(variable,false);
+        self.models_recursive(low,level+1,current,res);
+        current.add_assignment(variable,true);
+        self.models_recursive(high,level+1,current,res);
+        current.remove_assignment(variable);
+    }
+
+    // a single path to the true terminal, variables off the path are left unassigned
+    pub fn any_model(&self,f:BddRef)->Option<TruthAssignment>{
+        if f==BDD_FALSE{
+            return None;
+        }
+        let mut res=TruthAssignment::new();
+        let mut current=f;
+        while current!=BDD_TRUE{
+            let node=self.nodes[current];
+            let variable=self.order[node.level];
+            if node.low!=BDD_FALSE{
+                res.add_assignment(variable,false);
+                current=node.low;
+            }else{
+                res.add_assignment(variable,true);
+                current=node.high;
+            }
+        }
+        Some(res)
+    }
+
+    pub fn node_count(&self,f:BddRef)->usize{
+        let mut visited:Vec<BddRef>=vec![];
+        let mut stack=vec![f];
+        while let Some(node)=stack.pop(){
+            if visited.contains(&node){
+                continue;
+            }
+            visited.push(node);
+            if node>BDD_TRUE{
+                stack.push(self.nodes[node].low);
+                stack.push(self.nodes[node].high);
+            }
+        }
+        visited.len()
+    }
+
+    // each node v?h:l becomes (v*h)+(-v*l), shared nodes stay shared
+    pub fn to_boolean_formula(&self,f:BddRef)->BooleanFormula{
+        BooleanFormula::from_bdd(self,f)
+    }
+}
+
+// count*2^bits, None on overflow
+pub(crate) fn scale(count:u128,bits:usize)->Option<u128>{
+    if count==0{
+        return Some(0);
+    }
+    if bits>=128 || count.leading_zeros()<bits as u32{
+        return None;
+    }
+    Some(count<<bits)
+}
+
+impl Default for BddManager{
+    fn default()->Self{
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod test_bdd{
+    use super::*;
+    use crate::operators::*;
+
+    fn compile(string:String,manager:&mut BddManager)->BddRef{
+        BooleanFormula::from_string(string).unwrap().to_bdd(manager)
+    }
+
+    #[test]
+    fn equivalent_formulas_share_node(){
+        let mut manager=BddManager::new();
+        let left=compile(format!("1{}2",IMPL_OPERATOR_SYMBOL),&mut manager);
+        let right=compile(format!("{}2{}{}1",NEGATION_OPERATOR_SYMBOL,IMPL_OPERATOR_SYMBOL,NEGATION_OPERATOR_SYMBOL),&mut manager);
+        let other=compile(format!("2{}1",IMPL_OPERATOR_SYMBOL),&mut manager);
+        assert_eq!(left,right);
+        assert_ne!(left,other);
+    }
+
+    #[test]
+    fn tautology_and_contradiction(){
+        let mut manager=BddManager::new();
+        assert_eq!(compile(format!("1{}{}1",OR_OPERATOR_SYMBOL,NEGATION_OPERATOR_SYMBOL),&mut manager),BDD_TRUE);
+        assert_eq!(compile(format!("1{}{}1",AND_OPERATOR_SYMBOL,NEGATION_OPERATOR_SYMBOL),&mut manager),BDD_FALSE);
+    }
+
+    #[test]
+    fn sat_count_and_models(){
+        let mut manager=BddManager::with_order(vec![1,2,3]);
+        let f=compile(format!("(1{}2){}3",XOR_OPERATOR_SYMBOL,OR_OPERATOR_SYMBOL),&mut manager);
+        assert_eq!(manager.sat_count(f),Some(6));
+        let models=manager.models(f);
+        assert_eq!(models.len(),6);
+        for model in models.iter(){
+            let value=(model.get_assignment(1).unwrap()!=model.get_assignment(2).unwrap())||model.get_assignment(3).unwrap();
+            assert!(value);
+        }
+        assert_eq!(manager.sat_count(BDD_TRUE),Some(8));
+        assert_eq!(manager.sat_count(BDD_FALSE),Some(0));
+    }
+
+    #[test]
+    fn sat_count_overflow(){
+        let manager=BddManager::with_order((1..=128).collect());
+        assert_eq!(manager.sat_count(BDD_TRUE),None);
+        let mut manager=BddManager::with_order((1..=130).collect());
+        let first=manager.variable(1);
+        let last=manager.variable(130);
+        assert_eq!(manager.sat_count(last),None);
+        let both=manager.apply(BddOperator::And,first,last);
+        assert_eq!(manager.sat_count(both),None);
+        let manager=BddManager::with_order((1..=127).collect());
+        assert_eq!(manager.sat_count(BDD_TRUE),Some(1<<127));
+    }
+
+    #[test]
+    fn restrict_and_quantify(){
+        let mut manager=BddManager::new();
+        let f=compile(format!("1{}2",AND_OPERATOR_SYMBOL),&mut manager);
+        let two=manager.variable(2);
+        assert_eq!(manager.restrict(f,1,true),two);
+        assert_eq!(manager.restrict(f,1,false),BDD_FALSE);
+        assert_eq!(manager.exists(f,1),two);
+        assert_eq!(manager.for_all(f,1),BDD_FALSE);
+        let quantified=compile(format!("{}1{}(1{}2)",EXISTENTIAL_QUANTIFIER_SYMBOL,QUANTIFIER_SEPARATOR_SYMBOL,AND_OPERATOR_SYMBOL),&mut manager);
+        assert_eq!(quantified,two);
+    }
+
+    #[test]
+    fn variable_order_matters(){
+        let string=format!("(1{}4){}(2{}5){}(3{}6)",IFF_OPERATOR_SYMBOL,AND_OPERATOR_SYMBOL,IFF_OPERATOR_SYMBOL,AND_OPERATOR_SYMBOL,IFF_OPERATOR_SYMBOL);
+        let mut interleaved=BddManager::with_order(vec![1,4,2,5,3,6]);
+        let mut separated=BddManager::with_order(vec![1,2,3,4,5,6]);
+        let small=compile(string.clone(),&mut interleaved);
+        let big=compile(string,&mut separated);
+        assert!(interleaved.node_count(small)<separated.node_count(big));
+        assert_eq!(interleaved.sat_count(small),separated.sat_count(big));
+    }
+
+    #[test]
+    fn back_to_formula(){
+        let formula=BooleanFormula::from_string(format!("(1{}2){}3",IFF_OPERATOR_SYMBOL,IMPL_OPERATOR_SYMBOL)).unwrap();
+        let mut manager=BddManager::new();
+        let f=formula.to_bdd(&mut manager);
+        let back=manager.to_boolean_formula(f);
+        assert!(formula.bdd_equivalent(&back));
+        assert_eq!(back.to_bdd(&mut manager),f);
+    }
+
+    #[test]
+    fn equivalence_goes_by_names(){
+        let a=BooleanFormula::from_conventional_str("a").unwrap();
+        let b=BooleanFormula::from_conventional_str("b").unwrap();
+        assert!(!a.bdd_equivalent(&b));
+        assert!(!a.bdd_equivalent(&BooleanFormula::var(1)));
+        assert!(crate::sat::equivalent(&a,&b).is_err());
+        let left=BooleanFormula::from_conventional_str("a & !b").unwrap();
+        let right=BooleanFormula::from_conventional_str("!(b | !a)").unwrap();
+        assert!(left.bdd_equivalent(&right));
+        assert!(crate::sat::equivalent(&left,&right).is_ok());
+    }
+
+    #[test]
+    fn back_to_formula_keeps_names(){
+        let formula=BooleanFormula::from_conventional_str("(door | window) & !alarm").unwrap();
+        let mut manager=BddManager::new();
+        let f=formula.to_bdd(&mut manager);
+        let back=manager.to_boolean_formula(f);
+        assert!(formula.bdd_equivalent(&back));
+        let text=back.to_conventional_string();
+        assert!(text.contains("door") && text.contains("window") && text.contains("alarm"),"{}",text);
+        assert_eq!(manager.to_boolean_formula(BDD_TRUE).to_conventional_string(),"true");
+        assert_eq!(manager.to_boolean_formula(BDD_FALSE).to_conventional_string(),"false");
+    }
+
+    #[test]
+    fn back_to_formula_of_a_parity(){
+        // 15 inner nodes but 2^8 paths, the formula follows the nodes
+        let mut manager=BddManager::with_order((1..=8).collect());
+        let mut f=BDD_FALSE;
+        for x in 1..=8{
+            let variable=manager.variable(x);
+            f=manager.apply(BddOperator::Xor,f,variable);
+        }
+        let back=manager.to_boolean_formula(f);
+        assert_eq!(back.to_bdd(&mut manager),f);
+        assert_eq!(manager.sat_count(f),Some(128));
+    }
+
+    #[test]
+    fn any_model_satisfies(){
+        let formula=BooleanFormula::from_string(format!("{}1{}(2{}3)",NEGATION_OPERATOR_SYMBOL,AND_OPERATOR_SYMBOL,XOR_OPERATOR_SYMBOL)).unwrap();
+        let mut manager=BddManager::new();
+        let f=formula.to_bdd(&mut manager);
+        let model=manager.any_model(f).unwrap();
+        assert!(formula.evaluate(&model));
+        assert!(manager.any_model(BDD_FALSE).is_none());
+    }
+}
@@ -0,0 +1,149 @@
+use crate::formula::BooleanFormula;
+use crate::sat::check_sat_dpll_and_find_assignment;
+use crate::script::Script;
+use crate::smtlib::SmtSession;
+use crate::aiger::Aig;
+use crate::differential::{differential_check, DEFAULT_DIFFERENTIAL_VARIABLES, DEFAULT_DIFFERENTIAL_DEPTH};
+
+// the commands behind the binary, each one returns the lines to print or the
+// message to fail with, so that main only reads the arguments
+
+pub const DEFAULT_DIFFERENTIAL_RUNS:u64=1000;
+
+// runs a problem file, one line per response, files ending in .smt2 are read
+// as SMT-LIB, .aag and .aig as AIGER circuits and anything else as a script
+pub fn run_file(path:&str)->Result<Vec<String>,String>{
+    if path.ends_with(".aag") || path.ends_with(".aig"){
+        return run_circuit(path);
+    }
+    let source=match std::fs::read_to_string(path){
+        Ok(source)=>source,
+        Err(e)=>{return Err(format!("error: cannot read {}: {}",path,e));}
+    };
+    if path.ends_with(".smt2"){
+        return match SmtSession::new().run(&source){
+            Ok(responses)=>Ok(responses),
+            Err(e)=>Err(e.render(&source))
+        };
+    }
+    let script=match source.parse::<Script>(){
+        Ok(script)=>script,
+        Err(e)=>{return Err(e.render(&source));}
+    };
+    Ok(script.run().iter().map(|response|response.to_string()).collect())
+}
+
+// tells for each output of an AIGER circuit whether it can be true
+pub fn run_circuit(path:&str)->Result<Vec<String>,String>{
+    let aig=match std::fs::read(path).map_err(|e|e.to_string()).and_then(|bytes|Aig::from_bytes(&bytes).map_err(|e|e.to_string())){
+        Ok(aig)=>aig,
+        Err(e)=>{return Err(format!("error: cannot read {}: {}",path,e));}
+    };
+    let mut lines:Vec<String>=vec![];
+    for i in 0..aig.get_outputs().len(){
+        let name=aig.get_output_name(i).cloned().unwrap_or(format!("o{}",i));
+        match aig.check_output(i){
+            Some(_)=>lines.push(format!("{}: sat",name)),
+            None=>lines.push(format!("{}: unsat",name))
+        }
+    }
+    Ok(lines)
+}
+
+// differential [runs] [seed]: compares the solvers with the brute force oracle on random formulas
+pub fn run_differential(arguments:&[String])->Result<Vec<String>,String>{
+    let mut numbers:Vec<u64>=vec![DEFAULT_DIFFERENTIAL_RUNS,0];
+    for (i,argument) in arguments.iter().enumerate().take(numbers.len()){
+        numbers[i]=match argument.parse::<u64>(){
+            Ok(n)=>n,
+            Err(_)=>{return Err(format!("error: {} is not a number",argument));}
+        };
+    }
+    let (runs,seed)=(numbers[0] as usize,numbers[1]);
+    match differential_check(seed,runs,DEFAULT_DIFFERENTIAL_VARIABLES,DEFAULT_DIFFERENTIAL_DEPTH){
+        Ok(checked)=>Ok(vec![format!("{} random formulas checked, no disagreement",checked)]),
+        Err(disagreement)=>Err(format!("disagreement: {}",disagreement))
+    }
+}
+
+fn parse_demo_formula(input:&str, conventional:bool)->Result<BooleanFormula,String>{
+    let parsed=if conventional{BooleanFormula::from_conventional_str(input)}else{input.parse::<BooleanFormula>()};
+    parsed.map_err(|e|e.render(input))
+}
+
+// what the binary shows without arguments: a few formulas parsed, converted and solved
+pub fn demo()->Result<Vec<String>,String>{
+    let mut lines:Vec<String>=vec![];
+    let parsed=match parse_demo_formula("A1.2+-f1",false){
+        Ok(formula)=>formula,
+        Err(e)=>{return Err(e);}
+    };
+    lines.push(format!("Parsed from string: {}",parsed));
+    let parsed_from_str=match parse_demo_formula("T*(T+((-10=E10.20)=(0=4))+1+-(-2*3*-1)+(1=2)+F+0)*12",false){
+        Ok(formula)=>formula,
+        Err(e)=>{return Err(e);}
+    };
+    lines.push(format!("Parsed from str: {}",parsed_from_str));
+    let parsed_conventional=match parse_demo_formula("forall x. (door_open & !locked -> x | alarm)",true){
+        Ok(formula)=>formula,
+        Err(e)=>{return Err(e);}
+    };
+    lines.push(format!("Parsed from conventional syntax: {} ({})",parsed_conventional,parsed_conventional.to_conventional_string()));
+    lines.push(format!("NNF: {}",parsed.get_nnf()));
+    let parsed_cnf=parsed.get_cnf();
+    lines.push(format!("CNF: {}",parsed_cnf));
+    lines.push(format!("Cloned: {}",parsed.clone()));
+    lines.push(format!("Without Quantifiers: {}",parsed.without_quantifiers()));
+    match check_sat_dpll_and_find_assignment(&parsed){
+        Some(assignment)=>{
+            lines.push(format!("{} is satisfiable",parsed));
+            lines.push("Assignment:".to_string());
+            for var in parsed_cnf.get_variables(){
+                let value=if assignment.get_assignment_or_default(*var){"true"}else{"false"};
+                lines.push(format!("{}: {}",parsed_cnf.get_variable_name(*var),value));
+            }
+        },
+        None=>lines.push(format!("{} is unsatisfiable",parsed))
+    }
+    Ok(lines)
+}
+
+#[cfg(test)]
+mod test_cli{
+    use super::*;
+    use crate::operators::*;
+
+    fn write_file(name:&str, contents:&str)->String{
+        let path=std::env::temp_dir().join(format!("cnf_test_cli_{}_{}",std::process::id(),name));
+        std::fs::write(&path,contents).unwrap();
+        path.to_string_lossy().to_string()
+    }
+
+    #[test]
+    fn files_by_extension(){
+        let script=write_file("problem.txt",&format!("assert a{}b\ncheck-sat\n",AND_OPERATOR_SYMBOL));
+        assert_eq!(run_file(&script).unwrap(),vec!["sat".to_string()]);
+        let smt=write_file("problem.smt2","(declare-const a Bool)\n(assert (and a (not a)))\n(check-sat)\n");
+        assert_eq!(run_file(&smt).unwrap(),vec!["unsat".to_string()]);
+        let circuit=write_file("circuit.aag","aag 3 2 0 1 1\n2\n4\n6\n6 2 4\n");
+        assert_eq!(run_file(&circuit).unwrap(),vec!["o0: sat".to_string()]);
+        for path in [script,smt,circuit]{
+            std::fs::remove_file(path).unwrap();
+        }
+        assert!(run_file("/nonexistent/problem.txt").unwrap_err().starts_with("error: cannot read"));
+    }
+
+    #[test]
+    fn differential_arguments(){
+        assert_eq!(run_differential(&["5".to_string(),"3".to_string()]).unwrap(),
+            vec!["5 random formulas checked, no disagreement".to_string()]);
+        assert_eq!(run_differential(&["five".to_string()]).unwrap_err(),"error: five is not a number");
+    }
+
+    #[test]
+    fn demo_runs(){
+        let lines=demo().unwrap();
+        assert!(lines[0].starts_with("Parsed from string: "));
+        assert!(lines.iter().any(|line|line.ends_with("is satisfiable") || line.ends_with("is unsatisfiable")));
+    }
+}
@@ -0,0 +1,192 @@
+use crate::formula::BooleanFormula;
+use crate::random::{Rng, random_formula};
+use crate::sat::{TruthAssignment, check_sat_dpll_and_find_assignment, check_sat_cnf_and_find_assignment};
+use crate::truth_table::TruthTable;
+
+pub const DEFAULT_DIFFERENTIAL_VARIABLES:usize=5;
+pub const DEFAULT_DIFFERENTIAL_DEPTH:usize=5;
+
+// a formula on which the solver, the CNF conversion and the oracle do not agree,
+// shrunk as far as it goes while they still disagree
+#[derive(Debug,Clone)]
+pub struct Disagreement{
+    pub formula: BooleanFormula,
+    pub original: BooleanFormula,
+    pub reason: String
+}
+
+impl std::fmt::Display for Disagreement{
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{} on {} (shrunk from {})", self.reason, self.formula.to_conventional_string(), self.original.to_conventional_string())
+    }
+}
+
+// the reference answer: a model found by trying every assignment, in truth table order
+pub fn brute_force_sat(formula:&BooleanFormula)->Result<Option<TruthAssignment>,String>{
+    let table=match formula.truth_table(){
+        Ok(table)=>table,
+        Err(e)=>{return Err(e);}
+    };
+    Ok(table.find_row(true).map(|row|table.get_assignment(row)))
+}
+
+fn check_model(name:&str, formula:&BooleanFormula, table:&TruthTable, model:Option<TruthAssignment>)->Result<(),String>{
+    match model{
+        Some(model)=>{
+            if !table.is_satisfiable(){
+                return Err(format!("{} finds a model of an unsatisfiable formula",name));
+            }
+            if !formula.evaluate(&model){
+                return Err(format!("the model found by {} does not satisfy the formula",name));
+            }
+        },
+        None=>{
+            if table.is_satisfiable(){
+                return Err(format!("{} misses the models of a satisfiable formula",name));
+            }
+        }
+    }
+    Ok(())
+}
+
+// compares DPLL, DPLL on the clauses of the CNF conversion and the NNF against
+// the truth table
+pub fn check_formula(formula:&BooleanFormula)->Result<(),String>{
+    match formula.truth_table(){
+        Ok(table)=>check_against_table(formula,&table),
+        Err(e)=>Err(e)
+    }
+}
+
+// like check_formula, but a formula with too many variables for the truth table
+// passes, the oracle cannot tell anything about it
+fn check_if_tabulated(formula:&BooleanFormula)->Result<(),String>{
+    match formula.truth_table(){
+        Ok(table)=>check_against_table(formula,&table),
+        Err(_)=>Ok(())
+    }
+}
+
+fn check_against_table(formula:&BooleanFormula, table:&TruthTable)->Result<(),String>{
+    match check_model("DPLL",formula,table,check_sat_dpll_and_find_assignment(formula)){
+        Ok(())=>{},
+        Err(e)=>{return Err(e);}
+    }
+    match check_model("DPLL on the CNF",formula,table,check_sat_cnf_and_find_assignment(formula.to_cnf_representation())){
+        Ok(())=>{},
+        Err(e)=>{return Err(e);}
+    }
+    let nnf=formula.get_nnf();
+    for row in 0..table.row_count(){
+        if nnf.evaluate(&table.get_assignment(row))!=table.get_value(row){
+            return Err("the NNF is not equivalent to the formula".to_string());
+        }
+    }
+    Ok(())
+}
+
+// replaces the formula by a smaller failing one for as long as there is one
+pub fn shrink(formula:&BooleanFormula, reason:String, check:impl Fn(&BooleanFormula)->Result<(),String>)->(BooleanFormula,String){
+    let mut smallest=formula.clone();
+    let mut reason=reason;
+    'search: loop{
+        let mut candidates=smallest.variants(usize::MAX);
+        candidates.sort_by_key(|candidate|candidate.size());
+        for candidate in candidates{
+            if candidate.size()>=smallest.size(){
+                continue;
+            }
+            if let Err(e)=check(&candidate){
+                smallest=candidate;
+                reason=e;
+                continue 'search;
+            }
+        }
+        return (smallest,reason);
+    }
+}
+
+// checks runs random formulas and returns how many of them the truth table could
+// hold, the others are skipped; the first disagreement found is shrunk and returned
+pub fn differential_check(seed:u64, runs:usize, variables:usize, depth:usize)->Result<usize,Box<Disagreement>>{
+    let mut rng=Rng::new(seed);
+    let mut checked=0;
+    for _ in 0..runs{
+        let formula=random_formula(&mut rng,variables,depth);
+        let table=match formula.truth_table(){
+            Ok(table)=>table,
+            Err(_)=>{continue;}
+        };
+        if let Err(reason)=check_against_table(&formula,&table){
+            let (smallest,reason)=shrink(&formula,reason,check_if_tabulated);
+            return Err(Box::new(Disagreement { formula: smallest, original: formula, reason }));
+        }
+        checked+=1;
+    }
+    Ok(checked)
+}
+
+#[cfg(test)]
+mod test_differential{
+    use super::*;
+    use crate::sat::check_sat_dpll;
+
+    #[test]
+    fn oracle(){
+        let formula=BooleanFormula::from_conventional_str("(a | b) & !a").unwrap();
+        let model=brute_force_sat(&formula).unwrap().unwrap();
+        assert!(formula.evaluate(&model));
+        let formula=BooleanFormula::from_conventional_str("(a <-> b) & (a ^ b)").unwrap();
+        assert!(brute_force_sat(&formula).unwrap().is_none());
+    }
+
+    #[test]
+    fn random_formulas_are_reproducible(){
+        let mut first=Rng::new(7);
+        let mut second=Rng::new(7);
+        for _ in 0..20{
+            let a=random_formula(&mut first,4,4);
+            let b=random_formula(&mut second,4,4);
+            assert_eq!(a.to_string(),b.to_string());
+        }
+    }
+
+    #[test]
+    fn solvers_agree_with_the_oracle(){
+        for seed in 0..4{
+            if let Err(disagreement)=differential_check(seed,100,DEFAULT_DIFFERENTIAL_VARIABLES,DEFAULT_DIFFERENTIAL_DEPTH){
+                panic!("{}",disagreement);
+            }
+        }
+    }
+
+    #[test]
+    fn formulas_too_wide_for_the_oracle_are_skipped(){
+        let wide=(2..=20).fold(BooleanFormula::var(1),|f,x|f&BooleanFormula::var(x));
+        assert!(check_formula(&wide).is_err());
+        assert!(check_if_tabulated(&wide).is_ok());
+        let checked=differential_check(1,20,40,8).unwrap();
+        assert!(checked<20);
+    }
+
+    #[test]
+    fn dpll_on_hard_instances(){
+        // 3 pigeons do not fit in 2 holes, p_i_j: pigeon i in hole j
+        let formula=BooleanFormula::from_conventional_str("(p11 | p12) & (p21 | p22) & (p31 | p32) & \
+            !(p11 & p21) & !(p11 & p31) & !(p21 & p31) & !(p12 & p22) & !(p12 & p32) & !(p22 & p32)").unwrap();
+        assert!(!check_sat_dpll(&formula));
+        assert!(brute_force_sat(&formula).unwrap().is_none());
+    }
+
+    #[test]
+    fn shrinking(){
+        // a check that fails as soon as variable 3 occurs under a negation
+        let check=|formula:&BooleanFormula|{
+            if formula.to_string().contains("-3"){Err("negated 3".to_string())}else{Ok(())}
+        };
+        let formula=BooleanFormula::var(1)&(BooleanFormula::var(2)|!BooleanFormula::var(3))&BooleanFormula::var(4);
+        let (smallest,reason)=shrink(&formula,"negated 3".to_string(),check);
+        assert_eq!(smallest.to_string(),"-3");
+        assert_eq!(reason,"negated 3");
+    }
+}
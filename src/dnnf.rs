@@ -0,0 +1,506 @@
+use std::collections::{BTreeSet,HashMap};
+
+use crate::bdd::scale;
+use crate::sat::{CNF, Clause, Polarity, TruthAssignment};
+
+pub type DnnfRef=usize;
+
+const DNNF_FALSE:DnnfRef=0;
+const DNNF_TRUE:DnnfRef=1;
+
+// a clause as a sorted list of (variable, polarity) pairs
+type CompiledClause=Vec<(i32,bool)>;
+// a conjunction of literals in the same form
+type PartialModel=Vec<(i32,bool)>;
+
+#[derive(Debug,Clone,PartialEq,Eq,Hash)]
+pub enum DnnfNode{
+    True,
+    False,
+    Literal(i32,bool),
+    // children never share a variable
+    And(Vec<DnnfRef>),
+    // (variable+high)*(-variable+low)
+    Decision(i32,DnnfRef,DnnfRef)
+}
+
+// a decision-DNNF circuit, nodes are stored in topological order
+#[derive(Debug,Clone)]
+pub struct DecisionDnnf{
+    nodes: Vec<DnnfNode>,
+    node_variables: Vec<BTreeSet<i32>>,
+    root: DnnfRef,
+    variables: BTreeSet<i32>
+}
+
+struct DnnfBuilder{
+    nodes: Vec<DnnfNode>,
+    unique_table: HashMap<DnnfNode,DnnfRef>,
+    component_cache: HashMap<Vec<CompiledClause>,DnnfRef>
+}
+
+impl DnnfBuilder{
+    fn new()->Self{
+        let mut builder=DnnfBuilder {
+            nodes: vec![],
+            unique_table: HashMap::new(),
+            component_cache: HashMap::new()
+        };
+        builder.add_node(DnnfNode::False);
+        builder.add_node(DnnfNode::True);
+        builder
+    }
+
+    fn add_node(&mut self,node:DnnfNode)->DnnfRef{
+        if let Some(index)=self.unique_table.get(&node){
+            return *index;
+        }
+        self.nodes.push(node.clone());
+        self.unique_table.insert(node,self.nodes.len()-1);
+        self.nodes.len()-1
+    }
+
+    fn make_and(&mut self,children:Vec<DnnfRef>)->DnnfRef{
+        if children.contains(&DNNF_FALSE){
+            return DNNF_FALSE;
+        }
+        let mut children:Vec<DnnfRef>=children.into_iter().filter(|c|*c!=DNNF_TRUE).collect();
+        children.sort();
+        children.dedup();
+        match children.len(){
+            0=>DNNF_TRUE,
+            1=>children[0],
+            _=>self.add_node(DnnfNode::And(children))
+        }
+    }
+
+    fn make_decision(&mut self,variable:i32,high:DnnfRef,low:DnnfRef)->DnnfRef{
+        if high==DNNF_FALSE && low==DNNF_FALSE{
+            return DNNF_FALSE;
+        }
+        if low==DNNF_FALSE{
+            let literal=self.add_node(DnnfNode::Literal(variable,true));
+            return self.make_and(vec![literal,high]);
+        }
+        if high==DNNF_FALSE{
+            let literal=self.add_node(DnnfNode::Literal(variable,false));
+            return self.make_and(vec![literal,low]);
+        }
+        self.add_node(DnnfNode::Decision(variable,high,low))
+    }
+
+    // unit propagation followed by a decomposition into independent components
+    fn compile_clauses(&mut self,clauses:Vec<CompiledClause>)->DnnfRef{
+        let (clauses,units)=match propagate(clauses){
+            Some(res)=>res,
+            None=>{return DNNF_FALSE;}
+        };
+        let mut children:Vec<DnnfRef>=vec![];
+        for (variable,polarity) in units{
+            children.push(self.add_node(DnnfNode::Literal(variable,polarity)));
+        }
+        for component in split_components(clauses){
+            let child=self.compile_component(component);
+            if child==DNNF_FALSE{
+                return DNNF_FALSE;
+            }
+            children.push(child);
+        }
+        self.make_and(children)
+    }
+
+    fn compile_component(&mut self,mut component:Vec<CompiledClause>)->DnnfRef{
+        component.sort();
+        if let Some(node)=self.component_cache.get(&component){
+            return *node;
+        }
+        let variable=choose_variable(&component);
+        let high=self.compile_clauses(condition(&component,variable,true));
+        let low=self.compile_clauses(condition(&component,variable,false));
+        let res=self.make_decision(variable,high,low);
+        self.component_cache.insert(component,res);
+        res
+    }
+
+    fn finish(self,root:DnnfRef,variables:BTreeSet<i32>)->DecisionDnnf{
+        let mut node_variables:Vec<BTreeSet<i32>>=vec![];
+        for node in self.nodes.iter(){
+            let set=match node{
+                DnnfNode::True|DnnfNode::False=>BTreeSet::new(),
+                DnnfNode::Literal(variable,_)=>BTreeSet::from([*variable]),
+                DnnfNode::And(children)=>{
+                    children.iter().flat_map(|c|node_variables[*c].iter().copied()).collect()
+                },
+                DnnfNode::Decision(variable,high,low)=>{
+                    let mut set:BTreeSet<i32>=node_variables[*high].union(&node_variables[*low]).copied().collect();
+                    set.insert(*variable);
+                    set
+                }
+            };
+            node_variables.push(set);
+        }
+        DecisionDnnf { nodes: self.nodes, node_variables, root, variables }
+    }
+}
+
+fn propagate(mut clauses:Vec<CompiledClause>)->Option<(Vec<CompiledClause>,PartialModel)>{
+    let mut units:PartialModel=vec![];
+    loop{
+        if clauses.iter().any(|c|c.is_empty()){
+            return None;
+        }
+        let unit=match clauses.iter().find(|c|c.len()==1){
+            Some(clause)=>clause[0],
+            None=>{break;}
+        };
+        units.push(unit);
+        clauses=condition(&clauses,unit.0,unit.1);
+    }
+    Some((clauses,units))
+}
+
+fn condition(clauses:&[CompiledClause],variable:i32,value:bool)->Vec<CompiledClause>{
+    let mut res:Vec<CompiledClause>=vec![];
+    for clause in clauses.iter(){
+        if clause.contains(&(variable,value)){
+            continue;
+        }
+        res.push(clause.iter().copied().filter(|l|l.0!=variable).collect());
+    }
+    res
+}
+
+// the variable occurring in most clauses
+fn choose_variable(clauses:&[CompiledClause])->i32{
+    let mut occurrences:HashMap<i32,usize>=HashMap::new();
+    for clause in clauses.iter(){
+        for (variable,_) in clause.iter(){
+            *occurrences.entry(*variable).or_insert(0)+=1;
+        }
+    }
+    occurrences.into_iter().max_by_key(|(variable,count)|(*count,-*variable)).unwrap().0
+}
+
+fn split_components(clauses:Vec<CompiledClause>)->Vec<Vec<CompiledClause>>{
+    let mut parent:HashMap<i32,i32>=HashMap::new();
+    fn find(parent:&mut HashMap<i32,i32>,x:i32)->i32{
+        let p=*parent.entry(x).or_insert(x);
+        if p==x{
+            return x;
+        }
+        let root=find(parent,p);
+        parent.insert(x,root);
+        root
+    }
+    for clause in clauses.iter(){
+        let first=find(&mut parent,clause[0].0);
+        for (variable,_) in clause.iter().skip(1){
+            let other=find(&mut parent,*variable);
+            parent.insert(other,first);
+        }
+    }
+    let mut components:Vec<Vec<CompiledClause>>=vec![];
+    let mut component_of:HashMap<i32,usize>=HashMap::new();
+    for clause in clauses.into_iter(){
+        let root=find(&mut parent,clause[0].0);
+        let index=*component_of.entry(root).or_insert_with(||{
+            components.push(vec![]);
+            components.len()-1
+        });
+        components[index].push(clause);
+    }
+    components
+}
+
+fn to_compiled_clause(clause:&Clause)->Option<CompiledClause>{
+    let mut res:CompiledClause=clause.literals.iter()
+        .map(|l|(l.variable,l.polarity==Polarity::Positive))
+        .collect();
+    res.sort();
+    // tautological clauses are dropped
+    if res.windows(2).any(|w|w[0].0==w[1].0){
+        return None;
+    }
+    Some(res)
+}
+
+impl DecisionDnnf{
+    // trace of an exhaustive DPLL search with component caching
+    pub fn compile(cnf:&CNF)->Self{
+        let clauses:Vec<CompiledClause>=cnf.get_clauses().iter().filter_map(to_compiled_clause).collect();
+        let variables:BTreeSet<i32>=cnf.get_variables().into_iter().collect();
+        let mut builder=DnnfBuilder::new();
+        let root=builder.compile_clauses(clauses);
+        builder.finish(root,variables)
+    }
+
+    pub fn get_variables(&self)->&BTreeSet<i32>{
+        &self.variables
+    }
+
+    pub fn get_root(&self)->&DnnfNode{
+        &self.nodes[self.root]
+    }
+
+    pub fn node_count(&self)->usize{
+        self.nodes.len()
+    }
+
+    pub fn is_satisfiable(&self)->bool{
+        self.root!=DNNF_FALSE
+    }
+
+    // number of models over the variables of the compiled CNF, None when it
+    // does not fit in a u128
+    pub fn model_count(&self)->Option<u128>{
+        let mut counts:Vec<Option<u128>>=vec![];
+        for (i,node) in self.nodes.iter().enumerate(){
+            let count=match node{
+                DnnfNode::False=>Some(0),
+                DnnfNode::True|DnnfNode::Literal(_,_)=>Some(1),
+                DnnfNode::And(children)=>{
+                    // a child without models wins over one that overflows
+                    if children.iter().any(|c|counts[*c]==Some(0)){
+                        Some(0)
+                    }else{
+                        children.iter().try_fold(1u128,|product,c|counts[*c].and_then(|count|product.checked_mul(count)))
+                    }
+                },
+                DnnfNode::Decision(_,high,low)=>{
+                    let size=self.node_variables[i].len();
+                    let high_count=counts[*high].and_then(|count|scale(count,size-1-self.node_variables[*high].len()));
+                    let low_count=counts[*low].and_then(|count|scale(count,size-1-self.node_variables[*low].len()));
+                    match (high_count,low_count){
+                        (Some(high_count),Some(low_count))=>high_count.checked_add(low_count),
+                        _=>None
+                    }
+                }
+            };
+            counts.push(count);
+        }
+        counts[self.root].and_then(|count|scale(count,self.variables.len()-self.node_variables[self.root].len()))
+    }
+
+    // the circuit of the formula with every assigned variable replaced by its value
+    pub fn condition(&self,assignment:&TruthAssignment)->Self{
+        let mut builder=DnnfBuilder::new();
+        let mut mapping:Vec<DnnfRef>=vec![];
+        for node in self.nodes.iter(){
+            let mapped=match node{
+                DnnfNode::False=>DNNF_FALSE,
+                DnnfNode::True=>DNNF_TRUE,
+                DnnfNode::Literal(variable,polarity)=>{
+                    match assignment.get_assignment(*variable){
+                        Some(value)=>if value==*polarity{DNNF_TRUE}else{DNNF_FALSE},
+                        None=>builder.add_node(DnnfNode::Literal(*variable,*polarity))
+                    }
+                },
+                DnnfNode::And(children)=>{
+                    let children=children.iter().map(|c|mapping[*c]).collect();
+                    builder.make_and(children)
+                },
+                DnnfNode::Decision(variable,high,low)=>{
+                    match assignment.get_assignment(*variable){
+                        Some(true)=>mapping[*high],
+                        Some(false)=>mapping[*low],
+                        None=>builder.make_decision(*variable,mapping[*high],mapping[*low])
+                    }
+                }
+            };
+            mapping.push(mapped);
+        }
+        let variables=self.variables.iter().copied().filter(|v|assignment.get_assignment(*v).is_none()).collect();
+        builder.finish(mapping[self.root],variables)
+    }
+
+    // the circuit entails a clause when conditioning on its negation is unsatisfiable
+    pub fn entails_clause(&self,clause:&Clause)->bool{
+        let mut assignment=TruthAssignment::new();
+        for literal in clause.literals.iter(){
+            let value=literal.polarity==Polarity::Negative;
+            if assignment.get_assignment(literal.variable)==Some(!value){
+                return true;
+            }
+            assignment.add_assignment(literal.variable,value);
+        }
+        !self.condition(&assignment).is_satisfiable()
+    }
+
+    // every total assignment over the variables of the compiled CNF
+    pub fn models(&self)->Vec<TruthAssignment>{
+        let mut partials:Vec<Vec<PartialModel>>=vec![];
+        for (i,node) in self.nodes.iter().enumerate(){
+            let models=match node{
+                DnnfNode::False=>vec![],
+                DnnfNode::True=>vec![vec![]],
+                DnnfNode::Literal(variable,polarity)=>vec![vec![(*variable,*polarity)]],
+                DnnfNode::And(children)=>{
+                    let mut res:Vec<PartialModel>=vec![vec![]];
+                    for child in children.iter(){
+                        let mut product:Vec<PartialModel>=vec![];
+                        for left in res.iter(){
+                            for right in partials[*child].iter(){
+                                product.push(left.iter().chain(right.iter()).copied().collect());
+                            }
+                        }
+                        res=product;
+                    }
+                    res
+                },
+                DnnfNode::Decision(variable,high,low)=>{
+                    let mut res:Vec<PartialModel>=vec![];
+                    for (branch,value) in [(*high,true),(*low,false)]{
+                        let free:Vec<i32>=self.node_variables[i].iter().copied()
+                            .filter(|v|v!=variable && !self.node_variables[branch].contains(v))
+                            .collect();
+                        for model in partials[branch].iter(){
+                            let mut model=model.clone();
+                            model.push((*variable,value));
+                            res.extend(expand_free_variables(model,&free));
+                        }
+                    }
+                    res
+                }
+            };
+            partials.push(models);
+        }
+        let free:Vec<i32>=self.variables.iter().copied()
+            .filter(|v|!self.node_variables[self.root].contains(v))
+            .collect();
+        let mut res:Vec<TruthAssignment>=vec![];
+        for model in partials[self.root].iter(){
+            for total in expand_free_variables(model.clone(),&free){
+                let mut assignment=TruthAssignment::new();
+                for (variable,value) in total{
+                    assignment.add_assignment(variable,value);
+                }
+                res.push(assignment);
+            }
+        }
+        res
+    }
+}
+
+fn expand_free_variables(model:PartialModel,free:&[i32])->Vec<PartialModel>{
+    let mut res:Vec<PartialModel>=vec![model];
+    for variable in free.iter(){
+        let mut expanded:Vec<PartialModel>=vec![];
+        for model in res.into_iter(){
+            let mut negative=model.clone();
+            negative.push((*variable,false));
+            let mut positive=model;
+            positive.push((*variable,true));
+            expanded.push(negative);
+            expanded.push(positive);
+        }
+        res=expanded;
+    }
+    res
+}
+
+#[cfg(test)]
+mod test_dnnf{
+    use super::*;
+    use crate::sat::Literal;
+
+    // clauses in DIMACS style, a negative number is a negated variable
+    fn cnf_from(clauses:&[Vec<i32>])->CNF{
+        let mut cnf=CNF::new();
+        for c in clauses.iter(){
+            let mut clause=Clause::new();
+            for l in c.iter(){
+                let polarity=if *l>0{Polarity::Positive}else{Polarity::Negative};
+                clause.add_literal(Literal::new(polarity,l.abs()));
+            }
+            cnf.add_clause(clause);
+        }
+        cnf
+    }
+
+    fn satisfies(clauses:&[Vec<i32>],assignment:&TruthAssignment)->bool{
+        clauses.iter().all(|c|c.iter().any(|l|assignment.get_assignment_or_default(l.abs())==(*l>0)))
+    }
+
+    fn brute_force_count(clauses:&[Vec<i32>],variables:i32)->u128{
+        let mut count=0;
+        for bits in 0..(1u32<<variables){
+            let mut assignment=TruthAssignment::new();
+            for v in 1..=variables{
+                assignment.add_assignment(v,bits&(1<<(v-1))!=0);
+            }
+            if satisfies(clauses,&assignment){
+                count+=1;
+            }
+        }
+        count
+    }
+
+    #[test]
+    fn counts_match_brute_force(){
+        let clauses=vec![vec![1,2],vec![-1,3],vec![-2,-3,4],vec![5,6],vec![-5,-6],vec![4,-6,7]];
+        let dnnf=DecisionDnnf::compile(&cnf_from(&clauses));
+        assert_eq!(dnnf.model_count(),Some(brute_force_count(&clauses,7)));
+    }
+
+    #[test]
+    fn independent_components(){
+        let clauses=vec![vec![1,2],vec![3,4],vec![5,6]];
+        let dnnf=DecisionDnnf::compile(&cnf_from(&clauses));
+        assert_eq!(dnnf.model_count(),Some(27));
+        assert!(matches!(dnnf.get_root(),DnnfNode::And(children) if children.len()==3));
+    }
+
+    #[test]
+    fn unsatisfiable(){
+        let clauses=vec![vec![1,2],vec![-1,2],vec![1,-2],vec![-1,-2]];
+        let dnnf=DecisionDnnf::compile(&cnf_from(&clauses));
+        assert!(!dnnf.is_satisfiable());
+        assert_eq!(dnnf.model_count(),Some(0));
+        assert!(dnnf.models().is_empty());
+    }
+
+    #[test]
+    fn counts_past_u128(){
+        // one wide clause is false on a single assignment
+        let fits=DecisionDnnf::compile(&cnf_from(&[(1..=128).collect()]));
+        assert_eq!(fits.model_count(),Some(u128::MAX));
+        let wide=DecisionDnnf::compile(&cnf_from(&[(1..=130).collect()]));
+        assert_eq!(wide.model_count(),None);
+        assert!(wide.is_satisfiable());
+    }
+
+    #[test]
+    fn conditioning(){
+        let clauses=vec![vec![1,2,3],vec![-1,-2]];
+        let dnnf=DecisionDnnf::compile(&cnf_from(&clauses));
+        let mut assignment=TruthAssignment::new();
+        assignment.add_assignment(1,true);
+        let conditioned=dnnf.condition(&assignment);
+        // 2 must be false and 3 is free
+        assert_eq!(conditioned.model_count(),Some(2));
+        assignment.add_assignment(2,true);
+        assert!(!dnnf.condition(&assignment).is_satisfiable());
+    }
+
+    #[test]
+    fn clausal_entailment(){
+        let clauses=vec![vec![1,2],vec![-1,3]];
+        let dnnf=DecisionDnnf::compile(&cnf_from(&clauses));
+        let entailed=cnf_from(&[vec![2,3]]);
+        let not_entailed=cnf_from(&[vec![1,3]]);
+        assert!(dnnf.entails_clause(&entailed.get_clauses()[0]));
+        assert!(!dnnf.entails_clause(&not_entailed.get_clauses()[0]));
+    }
+
+    #[test]
+    fn models_are_distinct_and_satisfying(){
+        let clauses=vec![vec![1,-2],vec![2,3,-4],vec![-1,4]];
+        let dnnf=DecisionDnnf::compile(&cnf_from(&clauses));
+        let models=dnnf.models();
+        assert_eq!(Some(models.len() as u128),dnnf.model_count());
+        assert_eq!(models.len() as u128,brute_force_count(&clauses,4));
+        for model in models.iter(){
+            assert!(satisfies(&clauses,model));
+        }
+    }
+}
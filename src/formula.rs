@@ -1,24 +1,133 @@
 use std::cell::RefCell;
 use std::rc::Rc;
-use std::collections::HashSet;
+use std::collections::{HashSet,HashMap,BTreeMap};
 
-use crate::sat::{Literal, CNF, Clause};
+use crate::sat::{Literal, CNF, Clause, TruthAssignment, check_sat_dpll, is_valid};
+use crate::implicants;
+use crate::anf::{AlgebraicNormalForm, DEFAULT_ANF_MAX_MONOMIALS};
+use crate::truth_table::{TruthTable, DEFAULT_TRUTH_TABLE_MAX_VARIABLES};
+use crate::bdd::{BddManager, BddOperator, BddRef};
+use crate::aiger::Aig;
+use crate::symbols::{SymbolTable, is_identifier_start, is_identifier_char, is_valid_name, is_fresh_variable_name};
 use crate::operators::*;
+use crate::parse_error::{ParseError, Span};
+
+pub const DEFAULT_DNF_MAX_TERMS:usize=4096;
+
+// a conjunction of literals, mapping each variable to its polarity
+pub(crate) type Term=BTreeMap<i32,bool>;
 
 #[derive(Debug)]
 pub struct BooleanFormula{
     root: Formula,
     variables: HashSet<i32>,
+    symbols: SymbolTable,
 }
 
-#[derive(Debug)]
+#[derive(Debug,PartialEq,Eq)]
 struct Formula{
     root: Node
 }
 
 type Link=Rc<RefCell<Formula>>;
 
-#[derive(Debug,Clone)]
+// names met while parsing get ids above every id of the input and of the table,
+// those are only known once the whole input has been read, so an input with new
+// names is read twice: first to find them and the ids in use, then with their ids,
+// a name of the table whose id the input uses as a number moves to a new id as well
+struct ParsingSymbols<'a>{
+    table: &'a SymbolTable,
+    syntax: &'a Syntax,
+    // the ids of the new and the moved names, known in the second reading
+    names: HashMap<String,i32>,
+    pending: Vec<String>,
+    used: HashSet<i32>,
+    errors: Vec<ParseError>
+}
+
+impl<'a> ParsingSymbols<'a>{
+    fn new(table:&'a SymbolTable,syntax:&'a Syntax,names:HashMap<String,i32>)->Self{
+        ParsingSymbols { table, syntax, names, pending: vec![], used: HashSet::new(), errors: vec![] }
+    }
+
+    // an unclosed bracket is noticed once per level it encloses, it is reported once
+    fn report(&mut self,error:ParseError){
+        if self.errors.last()!=Some(&error){
+            self.errors.push(error);
+        }
+    }
+
+    fn id_of(&mut self,name:&str)->i32{
+        if let Some(id)=self.names.get(name){
+            return *id;
+        }
+        if let Some(id)=self.table.get_id(name){
+            return id;
+        }
+        if !self.pending.iter().any(|x|x==name){
+            self.pending.push(name.to_string());
+        }
+        // a placeholder, the first reading is thrown away
+        0
+    }
+
+    // the names of the table whose ids the input uses as numbers, by id
+    fn displaced(&self)->Vec<String>{
+        let mut ids:Vec<i32>=self.used.iter().copied().filter(|id|self.table.get_name(*id).is_some()).collect();
+        ids.sort();
+        ids.into_iter().map(|id|self.table.get_name(id).unwrap().clone()).collect()
+    }
+
+    // the ids for the given names, above the highest id in use when there is room
+    fn assign(&self,names:&[String])->HashMap<String,i32>{
+        let highest=self.used.iter().copied().max().unwrap_or(0).max(self.table.max_id()).max(0);
+        let start=highest.saturating_add(1);
+        let free=(start..=i32::MAX).chain(1..start).filter(|id|!self.used.contains(id) && self.table.get_name(*id).is_none());
+        names.iter().cloned().zip(free).collect()
+    }
+}
+
+// reads the input with read, twice when it has new or moved names, which are
+// then written to the table
+fn read_with_names<T>(symbols:&mut SymbolTable, syntax:&Syntax, read:impl Fn(&mut ParsingSymbols)->T)->(T,Vec<ParseError>){
+    let mut parsing=ParsingSymbols::new(symbols,syntax,HashMap::new());
+    let result=read(&mut parsing);
+    let mut moved=parsing.displaced();
+    if parsing.pending.is_empty() && moved.is_empty(){
+        return (result,parsing.errors);
+    }
+    moved.extend(parsing.pending.iter().cloned());
+    let names=parsing.assign(&moved);
+    let mut parsing=ParsingSymbols::new(symbols,syntax,names.clone());
+    let result=read(&mut parsing);
+    let errors=parsing.errors;
+    for name in moved.iter(){
+        symbols.insert(name,names[name]);
+    }
+    (result,errors)
+}
+
+// a formula as built bottom up by the readers of other formats,
+// the variables are ids of the symbol table the reader keeps
+#[derive(Debug,Clone,PartialEq,Eq)]
+pub(crate) enum Expression{
+    Variable(i32),
+    Constant(bool),
+    Not(Box<Expression>),
+    And(Vec<Expression>),
+    Or(Vec<Expression>),
+    Xor(Box<Expression>,Box<Expression>),
+    Implies(Box<Expression>,Box<Expression>),
+    Iff(Box<Expression>,Box<Expression>),
+    Ite(Box<Expression>,Box<Expression>,Box<Expression>),
+    AtMost(usize,Vec<Expression>),
+    AtLeast(usize,Vec<Expression>),
+    Exactly(usize,Vec<Expression>),
+    Parity(Vec<Expression>)
+}
+
+// equality is structural, shared links compare equal without a walk
+#[derive(Debug,Clone,PartialEq,Eq)]
 enum Node{
     Variable(i32),
     Or(Vec<Link>), 
@@ -28,10 +137,193 @@ enum Node{
     Iff(Link,Link),
     Implies(Link,Link),
     IsImpliedBy(Link,Link),
+    // if the first then the second else the third
+    Ite(Link,Link,Link),
+    // at most, at least or exactly k of the formulas hold
+    AtMost(usize,Vec<Link>),
+    AtLeast(usize,Vec<Link>),
+    Exactly(usize,Vec<Link>),
+    // an odd number of the formulas hold
+    Parity(Vec<Link>),
     Exists(i32,Link),
     ForEach(i32,Link),
     True,
-    False
+    False,
+    Error
+}
+
+impl Node{
+    fn children(&self)->Vec<Link>{
+        match self{
+            Node::And(vec)|
+            Node::Or(vec)|
+            Node::AtMost(_,vec)|
+            Node::AtLeast(_,vec)|
+            Node::Exactly(_,vec)|
+            Node::Parity(vec)=>vec.clone(),
+            Node::Iff(a,b)|
+            Node::Implies(a,b)|
+            Node::IsImpliedBy(a,b)|
+            Node::Xor(a,b)=>vec![Rc::clone(a),Rc::clone(b)],
+            Node::Ite(c,a,b)=>vec![Rc::clone(c),Rc::clone(a),Rc::clone(b)],
+            Node::Not(a)|
+            Node::Exists(_,a)|
+            Node::ForEach(_,a)=>vec![Rc::clone(a)],
+            Node::Variable(_)|Node::True|Node::False|Node::Error=>vec![]
+        }
+    }
+}
+
+// tokens of the conventional syntax: ! & ^ | -> <- <-> ? : forall exists
+#[derive(Debug,Clone,PartialEq)]
+enum ConventionalToken{
+    Name(String),
+    Not,
+    And,
+    Xor,
+    Or,
+    Implies,
+    IsImpliedBy,
+    Iff,
+    Then,
+    Else,
+    Open,
+    Close,
+    ForAll,
+    Exists,
+    Comma,
+    Separator,
+    // input that could not be read, its error is already reported
+    Invalid,
+    End
+}
+
+const NOT_BINDING_POWER:u8=13;
+
+impl ConventionalToken{
+    // left and right binding powers of the binary operators,
+    // a higher right power makes the operator left associative,
+    // the right power of ? is the one of the else branch
+    fn binding_power(&self)->Option<(u8,u8)>{
+        match self{
+            ConventionalToken::Then=>Some((2,1)),
+            ConventionalToken::Iff=>Some((3,4)),
+            ConventionalToken::Implies=>Some((6,5)),
+            ConventionalToken::IsImpliedBy=>Some((5,6)),
+            ConventionalToken::Or=>Some((7,8)),
+            ConventionalToken::Xor=>Some((9,10)),
+            ConventionalToken::And=>Some((11,12)),
+            _=>None
+        }
+    }
+}
+
+impl std::fmt::Display for ConventionalToken{
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let text=match self{
+            ConventionalToken::Name(name)=>name.as_str(),
+            ConventionalToken::Not=>"!",
+            ConventionalToken::And=>"&",
+            ConventionalToken::Xor=>"^",
+            ConventionalToken::Or=>"|",
+            ConventionalToken::Implies=>"->",
+            ConventionalToken::IsImpliedBy=>"<-",
+            ConventionalToken::Iff=>"<->",
+            ConventionalToken::Then=>"?",
+            ConventionalToken::Else=>":",
+            ConventionalToken::Open=>"(",
+            ConventionalToken::Close=>")",
+            ConventionalToken::ForAll=>"forall",
+            ConventionalToken::Exists=>"exists",
+            ConventionalToken::Comma=>",",
+            ConventionalToken::Separator=>".",
+            ConventionalToken::Invalid=>"@",
+            ConventionalToken::End=>"",
+        };
+        write!(f, "{}", text)
+    }
+}
+
+fn unexpected_token(token:&(ConventionalToken,Span), expected:&str)->ParseError{
+    match token.1{
+        span if token.0==ConventionalToken::End=>ParseError::UnexpectedEnd { expected: expected.to_string(), span },
+        span=>ParseError::UnexpectedCharacter { found: token.0.to_string().chars().next().unwrap(), expected: expected.to_string(), span }
+    }
+}
+
+// splits the input into tokens with their spans, the last token is always End
+fn tokenize_conventional(input:&str, errors:&mut Vec<ParseError>)->Vec<(ConventionalToken,Span)>{
+    let chars:Vec<(usize,char)>=input.char_indices().collect();
+    let offset=|i:usize|if i<chars.len(){chars[i].0}else{input.len()};
+    let mut tokens:Vec<(ConventionalToken,Span)>=vec![];
+    let mut index:usize=0;
+    while index<chars.len(){
+        let start=index;
+        let rest:String=chars[index..chars.len().min(index+3)].iter().map(|(_,c)|*c).collect();
+        let (token,length)=match chars[index].1{
+            c if c.is_whitespace()=>{
+                index+=1;
+                continue;
+            },
+            '!'|'~'=>(ConventionalToken::Not,1),
+            '&'=>(ConventionalToken::And,1),
+            '^'=>(ConventionalToken::Xor,1),
+            '|'=>(ConventionalToken::Or,1),
+            '('=>(ConventionalToken::Open,1),
+            ')'=>(ConventionalToken::Close,1),
+            ','=>(ConventionalToken::Comma,1),
+            '?'=>(ConventionalToken::Then,1),
+            ':'=>(ConventionalToken::Else,1),
+            '.'=>(ConventionalToken::Separator,1),
+            _ if rest.starts_with("<->")=>(ConventionalToken::Iff,3),
+            _ if rest.starts_with("<-")=>(ConventionalToken::IsImpliedBy,2),
+            _ if rest.starts_with("->")=>(ConventionalToken::Implies,2),
+            c if is_identifier_char(c)=>{
+                let mut name="".to_string();
+                while index<chars.len() && is_identifier_char(chars[index].1){
+                    name.push(chars[index].1);
+                    index+=1;
+                }
+                while index<chars.len() && chars[index].1=='['{
+                    name.push('[');
+                    index+=1;
+                    while index<chars.len() && chars[index].1.is_ascii_digit(){
+                        name.push(chars[index].1);
+                        index+=1;
+                    }
+                    if index>=chars.len() || chars[index].1!=']' || name.ends_with('['){
+                        break;
+                    }
+                    name.push(']');
+                    index+=1;
+                }
+                if name.ends_with('[') || name.ends_with(|c:char|c.is_ascii_digit()) && name.contains('['){
+                    errors.push(ParseError::InvalidVariableName { name, span: Span::new(offset(start),offset(index)) });
+                    tokens.push((ConventionalToken::Invalid,Span::new(offset(start),offset(index))));
+                    continue;
+                }
+                let token=match name.as_str(){
+                    "forall"=>ConventionalToken::ForAll,
+                    "exists"=>ConventionalToken::Exists,
+                    _=>ConventionalToken::Name(name)
+                };
+                tokens.push((token,Span::new(offset(start),offset(index))));
+                continue;
+            },
+            c=>{
+                errors.push(ParseError::UnexpectedCharacter {
+                    found: c,
+                    expected: "a variable, an operator or a bracket".to_string(),
+                    span: Span::new(offset(index),offset(index+1))
+                });
+                (ConventionalToken::Invalid,1)
+            }
+        };
+        index+=length;
+        tokens.push((token,Span::new(offset(start),offset(index))));
+    }
+    tokens.push((ConventionalToken::End,Span::point(input.len())));
+    tokens
 }
 
 impl BooleanFormula{
@@ -40,7 +332,11 @@ impl BooleanFormula{
     }
 
     pub fn to_string(&self)->String{
-        self.root.to_string()
+        self.root.to_string_with_symbols(&self.symbols)
+    }
+
+    pub fn to_string_with_syntax(&self,syntax:&Syntax)->String{
+        self.root.to_string_with_syntax(&self.symbols,syntax)
     }
 
     pub fn make_nnf(&mut self){
@@ -57,10 +353,22 @@ impl BooleanFormula{
         return &self.variables
     }
 
+    pub fn get_symbols(&self)->&SymbolTable{
+        &self.symbols
+    }
+
+    pub fn get_variable_name(&self,variable:i32)->String{
+        self.symbols.variable_to_string(variable)
+    }
+
+    pub fn get_variable_id(&self,name:&str)->Option<i32>{
+        self.symbols.get_id(name)
+    }
+
     pub fn not(&self)->Self{
         let root_neg=self.root.clone();
         let res =Formula::new(Node::Not(Rc::new(RefCell::new(root_neg))));
-        Self::from_formula(res)
+        self.derive(res)
     }
 
     pub fn update(&mut self){
@@ -68,40 +376,420 @@ impl BooleanFormula{
     }
 
     pub fn entail(&self, formula: &BooleanFormula)->Self{
-        let self_cloned=Rc::new(RefCell::new(self.root.clone()));
-        let formula_cloned=Rc::new(RefCell::new(formula.root.clone()));
-        let result=Formula::new(Node::Implies(self_cloned,formula_cloned));
-        Self::from_formula(result)
+        self.combine(formula,|a,b|Node::Implies(Rc::new(RefCell::new(a)),Rc::new(RefCell::new(b))))
+    }
+
+    // builders, for constructing formulas without going through a string
+    pub fn var(variable:i32)->Self{
+        Self::from_formula_with_symbols(Formula::new(Node::Variable(variable)),SymbolTable::new())
+    }
+
+    pub fn constant(value:bool)->Self{
+        let node=if value{Node::True}else{Node::False};
+        Self::from_formula_with_symbols(Formula::new(node),SymbolTable::new())
+    }
+
+    pub fn implies(&self, formula: &BooleanFormula)->Self{
+        self.entail(formula)
+    }
+
+    pub fn iff(&self, formula: &BooleanFormula)->Self{
+        self.combine(formula,|a,b|Node::Iff(Rc::new(RefCell::new(a)),Rc::new(RefCell::new(b))))
+    }
+
+    pub fn exists(&self, variable:i32)->Self{
+        self.derive(Formula::new(Node::Exists(variable,Rc::new(RefCell::new(self.root.clone())))))
+    }
+
+    pub fn forall(&self, variable:i32)->Self{
+        self.derive(Formula::new(Node::ForEach(variable,Rc::new(RefCell::new(self.root.clone())))))
+    }
+
+    pub fn is_implied_by(&self, formula: &BooleanFormula)->Self{
+        self.combine(formula,|a,b|Node::IsImpliedBy(Rc::new(RefCell::new(a)),Rc::new(RefCell::new(b))))
+    }
+
+    // self?then_branch:else_branch
+    pub fn ite(&self, then_branch: &BooleanFormula, else_branch: &BooleanFormula)->Self{
+        Self::combine_all(&[self.clone(),then_branch.clone(),else_branch.clone()],|links|{
+            Node::Ite(Rc::clone(&links[0]),Rc::clone(&links[1]),Rc::clone(&links[2]))
+        })
+    }
+
+    pub fn at_most(k:usize, formulas:&[BooleanFormula])->Self{
+        Self::combine_all(formulas,|links|Node::AtMost(k,links))
+    }
+
+    pub fn at_least(k:usize, formulas:&[BooleanFormula])->Self{
+        Self::combine_all(formulas,|links|Node::AtLeast(k,links))
+    }
+
+    pub fn exactly(k:usize, formulas:&[BooleanFormula])->Self{
+        Self::combine_all(formulas,|links|Node::Exactly(k,links))
+    }
+
+    pub fn parity(formulas:&[BooleanFormula])->Self{
+        Self::combine_all(formulas,Node::Parity)
+    }
+
+    // the variables of formula are matched to the ones of self by name
+    fn combine(&self, formula: &BooleanFormula, build: impl Fn(Formula,Formula)->Node)->Self{
+        self.combine_with_renamings(formula,build).0
+    }
+
+    // like combine for any number of formulas, they are gathered one at a time
+    // under a conjunction that build then replaces
+    fn combine_all(formulas:&[BooleanFormula], build: impl Fn(Vec<Link>)->Node)->Self{
+        let mut gathered=Self::from_formula(Formula::new(Node::And(vec![])));
+        for formula in formulas{
+            gathered=gathered.combine(formula,|a,b|{
+                let mut links=a.root.children();
+                links.push(Rc::new(RefCell::new(b)));
+                Node::And(links)
+            });
+        }
+        gathered.derive(Formula::new(build(gathered.root.root.children())))
+    }
+
+    // also returns where each variable of self and of formula ended up
+    fn combine_with_renamings(&self, formula: &BooleanFormula, build: impl Fn(Formula,Formula)->Node)->(Self,HashMap<i32,i32>,HashMap<i32,i32>){
+        let (symbols,own,other)=self.merge_symbols(formula);
+        let mut left=self.root.clone();
+        left.rename_variables(&own);
+        let mut right=formula.root.clone();
+        right.rename_variables(&other);
+        let result=Self::from_formula_with_symbols(Formula::new(build(left,right)),symbols);
+        let complete=|f:&BooleanFormula,renaming:&HashMap<i32,i32>|->HashMap<i32,i32>{
+            f.variables.iter().map(|x|(*x,*renaming.get(x).unwrap_or(x))).collect()
+        };
+        (result,complete(self,&own),complete(formula,&other))
+    }
+
+    // both formulas over their merged symbols, equal names on equal ids
+    fn aligned(&self, formula: &BooleanFormula)->(Self,Self){
+        let (symbols,own,other)=self.merge_symbols(formula);
+        (self.renumber(&own,&symbols),formula.renumber(&other,&symbols))
+    }
+
+    pub(crate) fn entail_with_renamings(&self, formula: &BooleanFormula)->(Self,HashMap<i32,i32>,HashMap<i32,i32>){
+        self.combine_with_renamings(formula,|a,b|Node::Implies(Rc::new(RefCell::new(a)),Rc::new(RefCell::new(b))))
+    }
+
+    pub(crate) fn iff_with_renamings(&self, formula: &BooleanFormula)->(Self,HashMap<i32,i32>,HashMap<i32,i32>){
+        self.combine_with_renamings(formula,|a,b|Node::Iff(Rc::new(RefCell::new(a)),Rc::new(RefCell::new(b))))
+    }
+
+    // the conjunction of formulas parsed with one shared symbol table, T when there are none
+    pub fn conjunction(formulas:&[BooleanFormula], symbols:&SymbolTable)->Self{
+        let root=if formulas.is_empty(){
+            Formula::new(Node::True)
+        }else if formulas.len()==1{
+            formulas[0].root.clone()
+        }else{
+            Formula::new(Node::And(formulas.iter().map(|f|Rc::new(RefCell::new(f.root.clone()))).collect()))
+        };
+        Self::from_formula_with_symbols(root,symbols.clone())
+    }
+
+    // the same formula with the ids moved as in mapping, over the given symbols
+    pub(crate) fn renumber(&self, mapping:&HashMap<i32,i32>, symbols:&SymbolTable)->Self{
+        let mut root=self.root.clone();
+        root.rename_variables(mapping);
+        Self::from_formula_with_symbols(root,symbols.clone())
+    }
+
+    // replaces each free variable that has a definition by a copy of it,
+    // the definitions are expected to be expanded already
+    pub fn expand_definitions(&self, definitions:&HashMap<i32,BooleanFormula>)->Self{
+        let mut root=self.root.clone();
+        let roots:HashMap<i32,&Formula>=definitions.iter().map(|(x,f)|(*x,&f.root)).collect();
+        root.expand_definitions(&roots);
+        self.derive(root)
+    }
+
+    pub fn substitute(&self, variable:i32, formula:&BooleanFormula)->Self{
+        let mut substitutions=HashMap::new();
+        substitutions.insert(variable,formula.clone());
+        self.substitute_all(&substitutions)
+    }
+
+    // replaces all the variables at once, so x by y and y by x swaps them,
+    // the variables of the replacements are matched to the ones of self by name
+    pub fn substitute_all(&self, substitutions:&HashMap<i32,BooleanFormula>)->Self{
+        // merged holds self and, in its variables, the replacements merged so far,
+        // which are renamed along with it when a later replacement moves a name
+        let mut merged=self.clone();
+        let mut roots:Vec<(i32,Formula)>=vec![];
+        for (x,formula) in substitutions.iter(){
+            let (symbols,own,other)=merged.merge_symbols(formula);
+            merged.root.rename_variables(&own);
+            for (_,root) in roots.iter_mut(){
+                root.rename_variables(&own);
+            }
+            let mut root=formula.root.clone();
+            root.rename_variables(&other);
+            merged.symbols=symbols;
+            roots.push((*x,root));
+            merged.variables=merged.root.find_variables();
+            for (_,root) in roots.iter(){
+                merged.variables.extend(root.find_variables());
+            }
+        }
+        let mut occurring:HashSet<i32>=HashSet::new();
+        for (_,root) in roots.iter(){
+            occurring.extend(root.find_variables());
+        }
+        // a named variable of self may have moved, an unnamed one keeps its id
+        let key=|x:i32|match self.symbols.get_name(x){
+            Some(name)=>merged.symbols.get_id(name).unwrap_or(x),
+            None=>x
+        };
+        let used=merged.variables.iter().copied().max().unwrap_or(0);
+        let mut next=used.max(merged.symbols.max_id()).max(0)+1;
+        let references:HashMap<i32,&Formula>=roots.iter().map(|(x,f)|(key(*x),f)).collect();
+        let mut root=merged.root.clone();
+        root.substitute(&references,&occurring,&mut next);
+        Self::from_formula_with_symbols(root,merged.symbols)
+    }
+
+    // f with the free occurrences of variable fixed to value
+    pub fn cofactor(&self, variable:i32, value:bool)->Self{
+        let mut result=self.substitute(variable,&Self::constant(value));
+        result.root.simplify_truth();
+        result.update();
+        result
+    }
+
+    // f = (x ? f|x=T : f|x=F)
+    pub fn shannon_expand(&self, variable:i32)->Self{
+        let positive=Rc::new(RefCell::new(self.cofactor(variable,true).root));
+        let negative=Rc::new(RefCell::new(self.cofactor(variable,false).root));
+        let condition=Rc::new(RefCell::new(Formula::new(Node::Variable(variable))));
+        self.derive(Formula::new(Node::Ite(condition,positive,negative)))
+    }
+
+    // true exactly for the assignments where flipping variable flips f
+    pub fn boolean_difference(&self, variable:i32)->Self{
+        self.cofactor(variable,true)^self.cofactor(variable,false)
+    }
+
+    // unlike get_variables, a variable that occurs but cannot change the value does not count
+    pub fn depends_on(&self, variable:i32)->bool{
+        check_sat_dpll(&self.boolean_difference(variable))
+    }
+
+    // renames the free variables, a permutation is fine since it is done at once
+    pub fn rename(&self, mapping:&HashMap<i32,i32>)->Self{
+        // the targets share the symbols of self, so a named target keeps its name
+        let substitutions:HashMap<i32,BooleanFormula>=mapping.iter().map(|(x,y)|(*x,self.derive(Formula::new(Node::Variable(*y))))).collect();
+        self.substitute_all(&substitutions)
+    }
+
+    // the union of both symbol tables and the renamings that make the two formulas
+    // agree on it: equal names get equal ids, an unnamed (numeric) variable keeps
+    // its id on both sides and a named one that clashes with it moves to a fresh id
+    fn merge_symbols(&self, formula: &BooleanFormula)->(SymbolTable,HashMap<i32,i32>,HashMap<i32,i32>){
+        let unnamed=|f:&BooleanFormula|->HashSet<i32>{
+            f.variables.iter().copied().filter(|x|f.symbols.get_name(*x).is_none()).collect()
+        };
+        let (own_numbers,other_numbers)=(unnamed(self),unnamed(formula));
+        let highest=self.variables.iter().chain(formula.variables.iter()).copied().max().unwrap_or(0);
+        let mut next=highest.max(self.symbols.max_id()).max(formula.symbols.max_id()).max(0)+1;
+        let mut symbols=SymbolTable::new();
+        let mut own:HashMap<i32,i32>=HashMap::new();
+        let mut other:HashMap<i32,i32>=HashMap::new();
+        for (name,id) in self.symbols.iter(){
+            if other_numbers.contains(id){
+                own.insert(*id,next);
+                symbols.insert(name,next);
+                next+=1;
+            }else{
+                symbols.insert(name,*id);
+            }
+        }
+        for (name,id) in formula.symbols.iter(){
+            let target=match symbols.get_id(name){
+                Some(existing)=>existing,
+                None=>{
+                    let target=if symbols.get_name(*id).is_some() || own_numbers.contains(id){
+                        next+=1;
+                        next-1
+                    }else{
+                        *id
+                    };
+                    symbols.insert(name,target);
+                    target
+                }
+            };
+            if target!=*id{
+                other.insert(*id,target);
+            }
+        }
+        (symbols,own,other)
+    }
+
+    pub fn from_string(input:String)->Result<Self,ParseError>{
+        Self::from_string_with_symbols(input,&mut SymbolTable::new())
     }
 
-    pub fn from_string(input:String)->Result<Self,String>{
-        let formula=match Formula::from_string(input.clone()){
+    // parses with a table shared among formulas, so that the same name gets the same id
+    pub fn from_string_with_symbols(input:String, symbols:&mut SymbolTable)->Result<Self,ParseError>{
+        let formula=match Formula::from_string_with_symbols(input,symbols){
             Ok(f)=>f,
             Err(s)=>{return Err(s);}
         };
-        Ok(Self::from_formula(formula))
+        Ok(Self::from_formula_with_symbols(formula,symbols.clone()))
     }
 
-    pub fn from_str(input:&str)->Result<Self,String>{
-        Self::from_string(input.to_string())
+    pub fn from_string_with_syntax(input:String, syntax:&Syntax)->Result<Self,ParseError>{
+        Self::from_string_with_symbols_and_syntax(input,&mut SymbolTable::new(),syntax)
+    }
+
+    pub fn from_string_with_symbols_and_syntax(input:String, symbols:&mut SymbolTable, syntax:&Syntax)->Result<Self,ParseError>{
+        let formula=match Formula::from_string_with_syntax(input,symbols,syntax){
+            Ok(f)=>f,
+            Err(s)=>{return Err(s);}
+        };
+        Ok(Self::from_formula_with_symbols(formula,symbols.clone()))
+    }
+
+    // never fails: unreadable parts of the input become error nodes, printed as @,
+    // and all the errors found are returned with the formula
+    pub fn from_string_recovering(input:String)->(Self,Vec<ParseError>){
+        Self::from_string_recovering_with_symbols_and_syntax(input,&mut SymbolTable::new(),&Syntax::default())
+    }
+
+    pub fn from_string_recovering_with_symbols_and_syntax(input:String, symbols:&mut SymbolTable, syntax:&Syntax)->(Self,Vec<ParseError>){
+        let (formula,errors)=Formula::from_string_recovering(input,symbols,syntax);
+        (Self::from_formula_with_symbols(formula,symbols.clone()),errors)
+    }
+
+    // parses the conventional syntax, e.g. forall x. (a & !b -> x | c)
+    pub fn from_conventional_string(input:String)->Result<Self,ParseError>{
+        Self::from_conventional_string_with_symbols(input,&mut SymbolTable::new())
+    }
+
+    pub fn from_conventional_string_with_symbols(input:String, symbols:&mut SymbolTable)->Result<Self,ParseError>{
+        let formula=match Formula::from_conventional_string_with_symbols(input,symbols){
+            Ok(f)=>f,
+            Err(s)=>{return Err(s);}
+        };
+        Ok(Self::from_formula_with_symbols(formula,symbols.clone()))
+    }
+
+    pub fn from_conventional_str(input:&str)->Result<Self,ParseError>{
+        Self::from_conventional_string(input.to_string())
+    }
+
+    pub fn from_conventional_string_recovering(input:String)->(Self,Vec<ParseError>){
+        Self::from_conventional_string_recovering_with_symbols(input,&mut SymbolTable::new())
+    }
+
+    pub fn from_conventional_string_recovering_with_symbols(input:String, symbols:&mut SymbolTable)->(Self,Vec<ParseError>){
+        let (formula,errors)=Formula::from_conventional_string_recovering(input,symbols);
+        (Self::from_formula_with_symbols(formula,symbols.clone()),errors)
+    }
+
+    // true for formulas from a recovering parse that met errors
+    pub fn has_error_nodes(&self)->bool{
+        self.root.has_error_nodes()
+    }
+
+    pub fn to_conventional_string(&self)->String{
+        self.root.to_conventional_string_with_symbols(&self.symbols)
     }
 
     fn from_formula(formula:Formula)->Self{
+        Self::from_formula_with_symbols(formula,SymbolTable::new())
+    }
+
+    // for front ends that read other formats and keep their own symbol table
+    pub(crate) fn from_expression(expression:&Expression, symbols:SymbolTable)->Self{
+        Self::from_formula_with_symbols(Formula::from_expression(expression),symbols)
+    }
+
+    // the quantifiers are expanded, nodes left by a recovering parse count as F
+    pub(crate) fn to_expression(&self)->Expression{
+        self.without_quantifiers().root.to_expression()
+    }
+
+    fn from_formula_with_symbols(formula:Formula, symbols:SymbolTable)->Self{
         let variables=HashSet::new();
         let mut res=BooleanFormula { 
             root: formula,
             variables,
+            symbols,
         };
         res.update();
         res
     }
 
+    // a new formula over the same variable names
+    fn derive(&self, formula:Formula)->Self{
+        Self::from_formula_with_symbols(formula,self.symbols.clone())
+    }
+
     pub fn is_cnf(&self)->bool{
         self.root.is_cnf()
     }
 
     pub fn to_cnf_representation(&self)->CNF{
-        self.root.to_cnf_representation()
+        self.root.to_cnf_representation().with_symbols(self.symbols.clone())
+    }
+
+    // returns the size before and after
+    pub fn simplify(&mut self)->(usize,usize){
+        let before=self.root.size();
+        self.root.simplify();
+        self.update();
+        (before,self.root.size())
+    }
+
+    pub fn size(&self)->usize{
+        self.root.size()
+    }
+
+    pub fn shared_size(&self)->usize{
+        self.root.shared_size()
+    }
+
+    // the formulas one step smaller than self, for shrinking test cases
+    pub(crate) fn variants(&self, limit:usize)->Vec<Self>{
+        self.root.variants(limit).into_iter().map(|root|self.derive(root)).collect()
+    }
+
+    pub fn minimize(&self, budget:usize)->Self{
+        self.minimize_with_care_set(&Self::constant(true),budget)
+    }
+
+    // the smallest formula found that agrees with self wherever care holds, trying
+    // to replace subformulas by constants or by their own parts, the budget is the
+    // number of candidates built and simplified plus the number of solver calls
+    pub fn minimize_with_care_set(&self, care:&BooleanFormula, budget:usize)->Self{
+        let mut best=self.clone();
+        best.simplify();
+        let mut spent=0;
+        'search: loop{
+            let mut candidates:Vec<BooleanFormula>=best.variants(budget-spent);
+            spent+=candidates.len();
+            for candidate in candidates.iter_mut(){
+                candidate.simplify();
+            }
+            candidates.retain(|candidate|candidate.size()<best.size());
+            candidates.sort_by_key(|candidate|candidate.size());
+            for candidate in candidates{
+                if spent==budget{
+                    break 'search;
+                }
+                spent+=1;
+                if is_valid(&care.implies(&self.iff(&candidate))).is_ok(){
+                    best=candidate;
+                    continue 'search;
+                }
+            }
+            break;
+        }
+        best
     }
 
     pub fn get_nnf(&self)->Self{
@@ -144,421 +832,1103 @@ impl BooleanFormula{
         cloned.remove_quantifiers();
         cloned
     }
-}
 
-impl Clone for BooleanFormula{
-    fn clone(&self) -> Self{
-        let mut res=BooleanFormula { root: self.root.clone(), variables: HashSet::new() };
-        res.update();
-        res
+    pub fn is_dnf(&self)->bool{
+        self.root.is_dnf()
     }
-}
 
-impl std::fmt::Display for BooleanFormula{
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        write!(f, "{}", self.to_string())
+    // no term is absorbed by another and no literal can be dropped by self-subsumption,
+    // but a term covered by several others stays, get_minimal_dnf gives the fewest terms
+    pub fn get_dnf(&self)->Result<Self,String>{
+        self.get_dnf_with_limit(DEFAULT_DNF_MAX_TERMS)
     }
-}
 
-impl Default for BooleanFormula {
-    fn default() -> Self { 
-        let string=FALSE_ATOM_SYMBOL.to_string();
-        BooleanFormula::from_string(string).unwrap()
+    pub fn get_dnf_with_limit(&self,max_terms:usize)->Result<Self,String>{
+        let terms=match self.root.to_dnf_terms(max_terms){
+            Ok(terms)=>terms,
+            Err(s)=>{return Err(s);}
+        };
+        Ok(self.derive(Formula::from_dnf_terms(&terms)))
     }
-}
 
-impl Formula{
-    pub fn new(node:Node) -> Self{
-        Formula{
-            root: node
-        }
+    pub fn evaluate(&self,assignment:&TruthAssignment)->bool{
+        self.root.evaluate(assignment)
     }
 
-    pub fn new_default() -> Self{
-        Self::default()
+    pub fn prime_implicants(&self)->Result<Vec<Self>,String>{
+        let primes=match self.prime_implicant_terms(){
+            Ok(primes)=>primes,
+            Err(s)=>{return Err(s);}
+        };
+        let mut res:Vec<Self>=vec![];
+        for prime in primes.iter(){
+            res.push(self.derive(Formula::from_dnf_terms(std::slice::from_ref(prime))));
+        }
+        Ok(res)
     }
 
-    pub fn from_string(s:String) -> Result<Self,String>{
-        let mut open="(".to_string();
-        open.push_str(&s);
-        open.push_str(")");
-        let vec=open.chars().collect();
-        let mut index: usize=0;
-        match Self::from_string_at_char(&vec,&mut index){
-            Ok(node)=>{
-                if index==open.len(){
-                    let res=Formula::new(node);
-                    Ok(res)
-                }else{
-                    let res=Formula::new(node);
-                    let str=format!("Formula not well formatted: found extra characters at the end of parsed formula \"{}\"",res.to_string());
-                    Err(str.to_string())
-                }
-            },
-            Err(s)=>{
-                println!("{} on {}",index, open.len());
-                Err(s)
-            }
+    pub fn prime_implicates(&self)->Result<Vec<Self>,String>{
+        // the prime implicates of f are the negated prime implicants of -f
+        let primes=match self.not().prime_implicant_terms(){
+            Ok(primes)=>primes,
+            Err(s)=>{return Err(s);}
+        };
+        let mut res:Vec<Self>=vec![];
+        for prime in primes.iter(){
+            let literals:Vec<Term>=prime.iter().map(|(variable,polarity)|Term::from([(*variable,!*polarity)])).collect();
+            res.push(self.derive(Formula::from_dnf_terms(&literals)));
         }
+        Ok(res)
     }
 
-    fn from_string_at_char(string: &Vec<char>, index: &mut usize) -> Result<Node,String>{
-        if *string.get(*index).unwrap()!='(' {
-            let error=format!("Formula not well formatted: missing opening brackets!
-                At position {} of {}
-                Expected \"(\"
-                Found \"{}\" instead",*index,string.into_iter().collect::<String>(),*string.get(*index).unwrap());
-            return Err(error);
-        }
-        let left_formula=match Self::read_atom(string,index){
-            Ok(node)=>node,
+    pub fn get_minimal_dnf(&self)->Result<Self,String>{
+        let primes=match self.prime_implicant_terms(){
+            Ok(primes)=>primes,
             Err(s)=>{return Err(s);}
         };
-        if *index>=string.len(){
-            return Err("Formula not well formatted: opened brackets need to be closed or filled with opeartors!".to_string());
-        }   
-        while *string.get(*index).unwrap()==' '{
-            *index+=1;
-            if *index>=string.len(){
-                return Err("Formula not well formatted: opened brackets need to be closed or filled with opeartors!".to_string());
-            }    
+        let cover=match implicants::minimal_cover(&primes,implicants::DEFAULT_MAX_COVER_VARIABLES){
+            Ok(cover)=>cover,
+            Err(s)=>{return Err(s);}
+        };
+        Ok(self.derive(Formula::from_dnf_terms(&cover)))
+    }
+
+    pub fn truth_table(&self)->Result<TruthTable,String>{
+        self.truth_table_with_limit(DEFAULT_TRUTH_TABLE_MAX_VARIABLES)
+    }
+
+    pub fn truth_table_with_limit(&self,max_variables:usize)->Result<TruthTable,String>{
+        TruthTable::from_formula(self,max_variables)
+    }
+
+    // the disjunction of the minterms of the true rows
+    pub fn canonical_dnf(table:&TruthTable)->Self{
+        Self::from_formula_with_symbols(Formula::from_dnf_terms(&table.minterms()),table.get_symbols().clone())
+    }
+
+    pub fn from_cnf(cnf:&CNF)->Self{
+        let mut clauses:Vec<Link>=vec![];
+        for clause in cnf.get_clauses().iter(){
+            let mut literals:Vec<&Literal>=clause.literals.iter().collect();
+            literals.sort_by_key(|l|(l.variable,l.polarity==crate::sat::Polarity::Positive));
+            let literals:Vec<Term>=literals.iter().map(|l|Term::from([(l.variable,l.polarity==crate::sat::Polarity::Positive)])).collect();
+            clauses.push(Rc::new(RefCell::new(Formula::from_dnf_terms(&literals))));
         }
-        let formula= match *string.get(*index).unwrap(){
-            ')'=>{
-                *index+=1;
-                return Ok(left_formula);
-            },
-            AND_OPERATOR_SYMBOL=>{ // and
-                let mut atom_vec:Vec<Link>=vec![];
-                atom_vec.push(Rc::new(RefCell::new(Formula::new(left_formula))));
-                loop{
-                    let next_atom=match Self::read_atom(string, index){
-                        Ok(node)=>node,
-                        Err(s)=>{return Err(s);}
-                    };
-                    atom_vec.push(Rc::new(RefCell::new(Formula::new(next_atom))));
-                    while *string.get(*index).unwrap()==' '{
-                        *index+=1;
-                        if *index>=string.len(){
-                            return Err("Formula not well formatted: and operator needs to be closed or continued!".to_string());
-                        }    
-                    }
-                    if *string.get(*index).unwrap()==AND_OPERATOR_SYMBOL{
+        Self::from_formula_with_symbols(Formula::conjunction_of(clauses),cnf.get_symbols().clone())
+    }
+
+    // the conjunction of the clauses ruling out the false rows
+    pub fn canonical_cnf(table:&TruthTable)->Self{
+        let mut clauses:Vec<Link>=vec![];
+        for maxterm in table.maxterms(){
+            let literals:Vec<Term>=maxterm.iter().map(|(variable,polarity)|Term::from([(*variable,*polarity)])).collect();
+            clauses.push(Rc::new(RefCell::new(Formula::from_dnf_terms(&literals))));
+        }
+        Self::from_formula_with_symbols(Formula::conjunction_of(clauses),table.get_symbols().clone())
+    }
+
+    pub fn to_anf(&self)->Result<AlgebraicNormalForm,String>{
+        self.to_anf_with_limit(DEFAULT_ANF_MAX_MONOMIALS)
+    }
+
+    pub fn to_anf_with_limit(&self,max_monomials:usize)->Result<AlgebraicNormalForm,String>{
+        self.root.to_anf(max_monomials)
+    }
+
+    pub fn from_anf(anf:&AlgebraicNormalForm)->Self{
+        Self::from_formula(Formula::from_anf(anf))
+    }
+
+    pub fn get_anf(&self)->Result<Self,String>{
+        match self.to_anf(){
+            Ok(anf)=>Ok(self.derive(Formula::from_anf(&anf))),
+            Err(s)=>Err(s)
+        }
+    }
+
+    pub fn anf_equivalent(&self,formula:&BooleanFormula)->Result<bool,String>{
+        let (left,right)=self.aligned(formula);
+        let left=match left.to_anf(){
+            Ok(anf)=>anf,
+            Err(s)=>{return Err(s);}
+        };
+        let right=match right.to_anf(){
+            Ok(anf)=>anf,
+            Err(s)=>{return Err(s);}
+        };
+        Ok(left==right)
+    }
+
+    pub fn to_bdd(&self,manager:&mut BddManager)->BddRef{
+        for (name,id) in self.symbols.iter(){
+            if self.variables.contains(id){
+                manager.name_variable(*id,name);
+            }
+        }
+        self.root.to_bdd(manager)
+    }
+
+    pub(crate) fn from_bdd(manager:&BddManager,f:BddRef)->Self{
+        let mut memo:HashMap<BddRef,Link>=HashMap::new();
+        let root=Formula::from_bdd(manager,f,&mut memo);
+        drop(memo);
+        let root=match Rc::try_unwrap(root){
+            Ok(root)=>root.into_inner(),
+            Err(root)=>root.borrow().clone()
+        };
+        Self::from_formula_with_symbols(root,manager.get_symbols().clone())
+    }
+
+    // one formula per output of the circuit
+    pub(crate) fn from_aig(aig:&Aig)->Vec<Self>{
+        let symbols=aig.get_symbols();
+        Formula::from_aig(aig).into_iter().map(|root|{
+            let root=match Rc::try_unwrap(root){
+                Ok(root)=>root.into_inner(),
+                Err(root)=>root.borrow().clone()
+            };
+            Self::from_formula_with_symbols(root,symbols.clone())
+        }).collect()
+    }
+
+    pub fn bdd_equivalent(&self,formula:&BooleanFormula)->bool{
+        let (left,right)=self.aligned(formula);
+        let mut order:Vec<i32>=left.variables.union(&right.variables).copied().collect();
+        order.sort();
+        let mut manager=BddManager::with_order(order);
+        left.to_bdd(&mut manager)==right.to_bdd(&mut manager)
+    }
+
+    fn prime_implicant_terms(&self)->Result<Vec<Term>,String>{
+        let terms=match self.root.to_dnf_terms(DEFAULT_DNF_MAX_TERMS){
+            Ok(terms)=>terms,
+            Err(s)=>{return Err(s);}
+        };
+        implicants::prime_implicants(terms,DEFAULT_DNF_MAX_TERMS)
+    }
+}
+
+impl Clone for BooleanFormula{
+    fn clone(&self) -> Self{
+        let mut res=BooleanFormula { root: self.root.clone(), variables: HashSet::new(), symbols: self.symbols.clone() };
+        res.update();
+        res
+    }
+}
+
+impl std::fmt::Display for BooleanFormula{
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.to_string())
+    }
+}
+
+impl std::str::FromStr for BooleanFormula{
+    type Err=ParseError;
+
+    fn from_str(input:&str)->Result<Self,ParseError>{
+        Self::from_string(input.to_string())
+    }
+}
+
+// a chain such as a&b&c stays one flat conjunction instead of nesting
+fn join(a:Formula, b:Formula, conjunction:bool)->Node{
+    let mut children:Vec<Link>=vec![];
+    for part in [a,b]{
+        match part.root{
+            Node::And(vec) if conjunction=>children.extend(vec),
+            Node::Or(vec) if !conjunction=>children.extend(vec),
+            root=>children.push(Rc::new(RefCell::new(Formula::new(root))))
+        }
+    }
+    if conjunction{Node::And(children)}else{Node::Or(children)}
+}
+
+impl std::ops::BitAnd for BooleanFormula{
+    type Output=Self;
+    fn bitand(self, formula:Self)->Self{
+        self.combine(&formula,|a,b|join(a,b,true))
+    }
+}
+
+impl std::ops::BitOr for BooleanFormula{
+    type Output=Self;
+    fn bitor(self, formula:Self)->Self{
+        self.combine(&formula,|a,b|join(a,b,false))
+    }
+}
+
+impl std::ops::BitXor for BooleanFormula{
+    type Output=Self;
+    fn bitxor(self, formula:Self)->Self{
+        self.combine(&formula,|a,b|Node::Xor(Rc::new(RefCell::new(a)),Rc::new(RefCell::new(b))))
+    }
+}
+
+impl std::ops::Not for BooleanFormula{
+    type Output=Self;
+    fn not(self)->Self{
+        BooleanFormula::not(&self)
+    }
+}
+
+impl Default for BooleanFormula {
+    fn default() -> Self { 
+        let string=FALSE_ATOM_SYMBOL.to_string();
+        BooleanFormula::from_string(string).unwrap()
+    }
+}
+
+impl Formula{
+    pub fn new(node:Node) -> Self{
+        Formula{
+            root: node
+        }
+    }
+
+    pub fn from_string(s:String) -> Result<Self,ParseError>{
+        Self::from_string_with_symbols(s,&mut SymbolTable::new())
+    }
+
+    pub fn from_string_with_symbols(s:String, symbols:&mut SymbolTable) -> Result<Self,ParseError>{
+        Self::from_string_with_syntax(s,symbols,&Syntax::default())
+    }
+
+    // names not yet in the table are added to it, with ids
+    // that no number in the formula or in the table already uses
+    pub fn from_string_with_syntax(s:String, symbols:&mut SymbolTable, syntax:&Syntax) -> Result<Self,ParseError>{
+        let (formula,errors)=Self::from_string_recovering(s,symbols,syntax);
+        match errors.into_iter().next(){
+            Some(e)=>Err(e),
+            None=>Ok(formula)
+        }
+    }
+
+    // keeps parsing after an error: the parts of the input that could not be read
+    // become error nodes and every problem found is reported, in input order
+    pub fn from_string_recovering(s:String, symbols:&mut SymbolTable, syntax:&Syntax) -> (Self,Vec<ParseError>){
+        read_with_names(symbols,syntax,|parsing|Self::parse(s.clone(),parsing))
+    }
+
+    // same as from_string_with_symbols, reading the conventional syntax
+    pub fn from_conventional_string_with_symbols(s:String, symbols:&mut SymbolTable) -> Result<Self,ParseError>{
+        let (formula,errors)=Self::from_conventional_string_recovering(s,symbols);
+        match errors.into_iter().next(){
+            Some(e)=>Err(e),
+            None=>Ok(formula)
+        }
+    }
+
+    pub fn from_conventional_string_recovering(s:String, symbols:&mut SymbolTable) -> (Self,Vec<ParseError>){
+        let syntax=Syntax::default();
+        read_with_names(symbols,&syntax,|parsing|{
+            let tokens=tokenize_conventional(&s,&mut parsing.errors);
+            let mut position:usize=0;
+            let node=Self::parse_conventional(&tokens,&mut position,0,parsing);
+            let mut trailing=false;
+            while tokens[position].0!=ConventionalToken::End{
+                match &tokens[position]{
+                    (ConventionalToken::Close,span)=>{
+                        parsing.report(ParseError::MissingBracket { bracket: '(', span: *span });
+                        position+=1;
                         continue;
+                    },
+                    (token,span)=>{
+                        if !trailing{
+                            parsing.report(ParseError::TrailingInput { span: Span::new(span.start,s.len()) });
+                            trailing=true;
+                        }
+                        if token.binding_power().is_some(){
+                            position+=1;
+                        }
                     }
-                    if *string.get(*index).unwrap()==')'{
-                        break;
-                    }
-                    let error=format!("Formula not well formatted: Invalid character in sequence of and operators!
-                        At position {} of {}
-                        Expected \")\" or \"{}\"
-                        Found \"{}\" instead",*index,string.into_iter().collect::<String>(),
-                        AND_OPERATOR_SYMBOL,*string.get(*index).unwrap());
-                    return Err(error);
                 }
-                Node::And(atom_vec)
-            },
-            OR_OPERATOR_SYMBOL=>{ // or
-                let mut atom_vec:Vec<Link>=vec![];
-                atom_vec.push(Rc::new(RefCell::new(Formula::new(left_formula))));
-                loop{
-                    let next_atom=match Self::read_atom(string, index){
-                        Ok(node)=>node,
-                        Err(s)=>{return Err(s);}
-                    };
-                    atom_vec.push(Rc::new(RefCell::new(Formula::new(next_atom))));
-                    while *string.get(*index).unwrap()==' '{
-                        *index+=1;
-                        if *index>=string.len(){
-                            return Err("Formula not well formatted: or operator needs to be closed or continued!".to_string());
-                        }    
-                    }
-                    if *string.get(*index).unwrap()==OR_OPERATOR_SYMBOL{
-                        continue;
-                    }
-                    if *string.get(*index).unwrap()==')'{
-                        break;
-                    }
-                    let error=format!("Formula not well formatted: Invalid character in sequence of or operators!
-                        At position {} of {}
-                        Expected \")\" or \"{}\"
-                        Found \"{}\" instead",*index,string.into_iter().collect::<String>(),
-                        OR_OPERATOR_SYMBOL,*string.get(*index).unwrap());
-                    return Err(error);
-                }
-                Node::Or(atom_vec)
-            },
-            XOR_OPERATOR_SYMBOL=>{ // xor
-                let right_formula=match Self::read_atom(string, index){
-                    Ok(node)=>node,
-                    Err(s)=>{return Err(s);}
-                };
-                Node::Xor(
-                    Rc::new(RefCell::new(Formula::new(left_formula))), 
-                    Rc::new(RefCell::new(Formula::new(right_formula))))
+                // what follows is only read for its diagnostics
+                Self::parse_conventional(&tokens,&mut position,0,parsing);
+            }
+            Formula::new(node)
+        })
+    }
+
+    fn from_expression(expression:&Expression)->Self{
+        let link=|e:&Expression|Rc::new(RefCell::new(Formula::from_expression(e)));
+        let root=match expression{
+            Expression::Variable(x)=>Node::Variable(*x),
+            Expression::Constant(true)=>Node::True,
+            Expression::Constant(false)=>Node::False,
+            Expression::Not(a)=>Node::Not(link(a)),
+            // an empty conjunction is T, an empty disjunction F
+            Expression::And(vec) if vec.is_empty()=>Node::True,
+            Expression::Or(vec) if vec.is_empty()=>Node::False,
+            Expression::And(vec) if vec.len()==1=>{
+                return Formula::from_expression(&vec[0]);
             },
-            LEFT_IMPL_OPERATOR_SYMBOL=>{ // left impl
-                let right_formula=match Self::read_atom(string, index){
-                    Ok(node)=>node,
-                    Err(s)=>{return Err(s);}
-                };
-                Node::IsImpliedBy(
-                    Rc::new(RefCell::new(Formula::new(left_formula))), 
-                    Rc::new(RefCell::new(Formula::new(right_formula))))
+            Expression::Or(vec) if vec.len()==1=>{
+                return Formula::from_expression(&vec[0]);
             },
-            IMPL_OPERATOR_SYMBOL=>{ // impl
-                let right_formula=match Self::read_atom(string, index){
-                    Ok(node)=>node,
-                    Err(s)=>{return Err(s);}
-                };
-                Node::Implies(
-                    Rc::new(RefCell::new(Formula::new(left_formula))), 
-                    Rc::new(RefCell::new(Formula::new(right_formula))))
+            Expression::And(vec)=>Node::And(vec.iter().map(link).collect()),
+            Expression::Or(vec)=>Node::Or(vec.iter().map(link).collect()),
+            Expression::Xor(a,b)=>Node::Xor(link(a),link(b)),
+            Expression::Implies(a,b)=>Node::Implies(link(a),link(b)),
+            Expression::Iff(a,b)=>Node::Iff(link(a),link(b)),
+            Expression::Ite(c,a,b)=>Node::Ite(link(c),link(a),link(b)),
+            Expression::AtMost(k,vec)=>Node::AtMost(*k,vec.iter().map(link).collect()),
+            Expression::AtLeast(k,vec)=>Node::AtLeast(*k,vec.iter().map(link).collect()),
+            Expression::Exactly(k,vec)=>Node::Exactly(*k,vec.iter().map(link).collect()),
+            Expression::Parity(vec)=>Node::Parity(vec.iter().map(link).collect())
+        };
+        Formula::new(root)
+    }
+
+    // expects a formula without quantifiers
+    fn to_expression(&self)->Expression{
+        let expression=|link:&Link|Box::new(link.borrow().to_expression());
+        match &self.root{
+            Node::Variable(x)=>Expression::Variable(*x),
+            Node::True=>Expression::Constant(true),
+            Node::False|Node::Error=>Expression::Constant(false),
+            Node::Not(a)=>Expression::Not(expression(a)),
+            Node::And(vec)=>Expression::And(vec.iter().map(|x|x.borrow().to_expression()).collect()),
+            Node::Or(vec)=>Expression::Or(vec.iter().map(|x|x.borrow().to_expression()).collect()),
+            Node::Xor(a,b)=>Expression::Xor(expression(a),expression(b)),
+            Node::Implies(a,b)=>Expression::Implies(expression(a),expression(b)),
+            Node::IsImpliedBy(a,b)=>Expression::Implies(expression(b),expression(a)),
+            Node::Iff(a,b)=>Expression::Iff(expression(a),expression(b)),
+            Node::Ite(c,a,b)=>Expression::Ite(expression(c),expression(a),expression(b)),
+            Node::AtMost(k,vec)=>Expression::AtMost(*k,vec.iter().map(|x|x.borrow().to_expression()).collect()),
+            Node::AtLeast(k,vec)=>Expression::AtLeast(*k,vec.iter().map(|x|x.borrow().to_expression()).collect()),
+            Node::Exactly(k,vec)=>Expression::Exactly(*k,vec.iter().map(|x|x.borrow().to_expression()).collect()),
+            Node::Parity(vec)=>Expression::Parity(vec.iter().map(|x|x.borrow().to_expression()).collect()),
+            Node::Exists(_,_)|Node::ForEach(_,_)=>{unreachable!();}
+        }
+    }
+
+    fn has_error_nodes(&self)->bool{
+        match &self.root{
+            Node::Error=>true,
+            Node::Or(vec)|Node::And(vec)|
+            Node::AtMost(_,vec)|Node::AtLeast(_,vec)|Node::Exactly(_,vec)|Node::Parity(vec)=>vec.iter().any(|x|x.borrow().has_error_nodes()),
+            Node::Xor(a,b)|Node::Iff(a,b)|Node::Implies(a,b)|Node::IsImpliedBy(a,b)=>{
+                a.borrow().has_error_nodes() || b.borrow().has_error_nodes()
             },
-            IFF_OPERATOR_SYMBOL=>{ // iff
-                let right_formula=match Self::read_atom(string, index){
-                    Ok(node)=>node,
-                    Err(s)=>{return Err(s);}
-                };
-                Node::Iff(
-                    Rc::new(RefCell::new(Formula::new(left_formula))), 
-                    Rc::new(RefCell::new(Formula::new(right_formula))))
+            Node::Ite(c,a,b)=>{
+                c.borrow().has_error_nodes() || a.borrow().has_error_nodes() || b.borrow().has_error_nodes()
             },
-            _=>{
-                let error=format!("Formula not well formatted: invalid operator found!
-                    At position {} of {}
-                    Expected \"{}\" (and) or \"{}\" (or) or \"{}\" (impl) or \"{}\" (left impl) or \"{}\" (iff) or \"{}\" (xor)
-                    Found \"{}\" instead",
-                    *index,string.into_iter().collect::<String>(),
-                    AND_OPERATOR_SYMBOL,OR_OPERATOR_SYMBOL,IMPL_OPERATOR_SYMBOL,LEFT_IMPL_OPERATOR_SYMBOL,IFF_OPERATOR_SYMBOL,XOR_OPERATOR_SYMBOL,
-                    *string.get(*index).unwrap());
-                return Err(error);
-            }
-        };
-        while *string.get(*index).unwrap()==' '{
-            *index+=1;
-            if *index>=string.len(){
-                return Err("Formula not well formatted: missing closed brackets".to_string());
-            }    
+            Node::Not(a)|Node::Exists(_,a)|Node::ForEach(_,a)=>a.borrow().has_error_nodes(),
+            Node::Variable(_)|Node::True|Node::False=>false
         }
-        if *string.get(*index).unwrap()!=')'{
-            let error=format!("Formula not well formatted: brackets must be closed before starting a new subformula!
-                At position {} of {}
-                Expected \")\"
-                Found \"{}\" instead",*index,string.into_iter().collect::<String>(),*string.get(*index).unwrap());
-            return Err(error);
+    }
+
+    // errors are collected in symbols, the parts of the input they
+    // concern are replaced by error nodes
+    fn parse(s:String, symbols:&mut ParsingSymbols) -> Self{
+        let mut open="(".to_string();
+        open.push_str(&s);
+        open.push(')');
+        let vec:Vec<char>=open.chars().collect();
+        let mut index: usize=0;
+        let node=Self::from_string_at_char(&vec,&mut index,symbols);
+        while index<vec.len(){
+            // the outer bracket was closed by a ")" of the input, what follows is only checked
+            symbols.report(ParseError::MissingBracket { bracket: '(', span: span_of(&vec,index-1,index) });
+            Self::recover_group(&vec,&mut index,symbols);
         }
-        *index+=1;
-        return Ok(formula);
+        Formula::new(node)
+    }
 
+    fn skip_spaces(string: &[char], index: &mut usize){
+        while *index<string.len() && string[*index].is_whitespace(){
+            *index+=1;
+        }
     }
 
-    fn read_variable_name(string: &Vec<char>, index: &mut usize)->Result<i32,String>{
-        while *string.get(*index).unwrap()==' '{
+    // skips what could not be read, up to the next binary operator or closing bracket outside of brackets
+    fn skip_to_sync(string: &[char], index: &mut usize, syntax: &Syntax){
+        let mut depth=0;
+        while *index<string.len(){
+            let c=string[*index];
+            if c=='('{
+                depth+=1;
+            }else if c==')'{
+                if depth==0{
+                    return;
+                }
+                depth-=1;
+            }else if depth==0 && syntax.is_infix_operator(c){
+                return;
+            }
             *index+=1;
+        }
+    }
+
+    // after an error inside brackets the operands left before the closing
+    // bracket are still read, so that their errors are reported too
+    fn recover_group(string: &[char], index: &mut usize, symbols: &mut ParsingSymbols){
+        loop{
+            Self::skip_spaces(string,index);
             if *index>=string.len(){
-                return Err("Formula not well formatted: Found no character while trying to read a variable!".to_string());
+                return;
+            }
+            let c=string[*index];
+            if c==')'{
+                *index+=1;
+                return;
             }
+            if symbols.syntax.is_infix_operator(c){
+                Self::read_atom(string,index,symbols);
+            }else{
+                Self::skip_to_sync(string,index,symbols.syntax);
+            }
+        }
+    }
+
+    fn from_string_at_char(string: &[char], index: &mut usize, symbols: &mut ParsingSymbols) -> Node{
+        let left_formula=Self::read_atom(string,index,symbols);
+        Self::skip_spaces(string,index);
+        if *index>=string.len(){
+            symbols.report(missing_closing_bracket(string));
+            return left_formula;
         }
-        match *string.get(*index).unwrap(){
-            FRESH_VARIABLE_SYMBOL=>{
+        let operator=string[*index];
+        let syntax=symbols.syntax;
+        let formula=match operator{
+            ')'=>{
                 *index+=1;
-                if *index>=string.len(){
-                    return Err("Formula not well formatted: Found no character while trying to read a fresh variable!".to_string());
-                }
-                match *string.get(*index).unwrap(){
-                    '1'|'2'|'3'|'4'|'5'|'6'|'7'|'8'|'9'=>{
-                        let mut number=*string.get(*index).unwrap() as i32 - '0' as i32;
-                        loop{
-                            *index+=1;
-                            if *index>=string.len(){
-                                return Err("Formula not well formatted: Found no character while trying to read a fresh variable!".to_string());
-                            }
-                            match *string.get(*index).unwrap() {
-                                '0'|'1'|'2'|'3'|'4'|'5'|'6'|'7'|'8'|'9'=>{
-                                    let digit=*string.get(*index).unwrap() as i32 - '0' as i32;
-                                    number=number*10+digit;
-                                },
-                                _=>{
-                                    break;
-                                }
-                            }
-                        }
-                        return Ok(-number);
-                    },
-                    _=>{
-                        let error=format!("Formula not well formatted: Invalid character found while trying to read a fresh variable!
-                            At position {} of {}
-                            Expected a number
-                            Found \"{}\" instead",*index,string.into_iter().collect::<String>(),*string.get(*index).unwrap());
-                        return Err(error);
+                return left_formula;
+            },
+            c if c==syntax.and() || c==syntax.or()=>{
+                let mut atom_vec:Vec<Link>=vec![];
+                atom_vec.push(Rc::new(RefCell::new(Formula::new(left_formula))));
+                loop{
+                    let next_atom=Self::read_atom(string,index,symbols);
+                    atom_vec.push(Rc::new(RefCell::new(Formula::new(next_atom))));
+                    Self::skip_spaces(string,index);
+                    if *index>=string.len(){
+                        symbols.report(missing_closing_bracket(string));
+                        break;
+                    }
+                    if string[*index]==operator{
+                        continue;
+                    }
+                    if string[*index]==')'{
+                        break;
                     }
+                    // and and or can not be mixed without brackets
+                    symbols.report(unexpected(string,*index,&format!("\")\" or \"{}\"",operator)));
+                    Self::recover_group(string,index,symbols);
+                    return Node::Error;
+                }
+                if operator==syntax.and(){
+                    Node::And(atom_vec)
+                }else{
+                    Node::Or(atom_vec)
                 }
             },
-            '0'=>{
-                *index+=1;
+            c if c==syntax.ite_then()=>{
+                let then_formula=Self::read_atom(string,index,symbols);
+                Self::skip_spaces(string,index);
                 if *index>=string.len(){
-                    return Err("Formula not well formatted: Found no character while trying to read a 0 variable!".to_string());
+                    symbols.report(missing_closing_bracket(string));
+                    return Node::Error;
+                }
+                if string[*index]!=syntax.ite_else(){
+                    symbols.report(unexpected(string,*index,&format!("\"{}\"",syntax.ite_else())));
+                    Self::recover_group(string,index,symbols);
+                    return Node::Error;
                 }
-                return Ok(0);
+                let else_formula=Self::read_atom(string,index,symbols);
+                Node::Ite(
+                    Rc::new(RefCell::new(Formula::new(left_formula))),
+                    Rc::new(RefCell::new(Formula::new(then_formula))),
+                    Rc::new(RefCell::new(Formula::new(else_formula))))
             },
-            '1'|'2'|'3'|'4'|'5'|'6'|'7'|'8'|'9'=>{
-                let mut number=*string.get(*index).unwrap() as i32 - '0' as i32;
-                loop{
-                    *index+=1;
-                    if *index>=string.len(){
-                        return Err("Formula not well formatted: Found no character while trying to read an original variable!".to_string());
-                    }
-                    match *string.get(*index).unwrap() {
-                        '0'|'1'|'2'|'3'|'4'|'5'|'6'|'7'|'8'|'9'=>{
-                            let digit=*string.get(*index).unwrap() as i32 - '0' as i32;
-                            number=number*10+digit;
-                        },
-                        _=>{
-                            break;
-                        }
-                    }
+            c if syntax.is_binary_operator(c)=>{
+                let right_formula=Self::read_atom(string,index,symbols);
+                let left=Rc::new(RefCell::new(Formula::new(left_formula)));
+                let right=Rc::new(RefCell::new(Formula::new(right_formula)));
+                if operator==syntax.xor(){
+                    Node::Xor(left,right)
+                }else if operator==syntax.is_implied_by(){
+                    Node::IsImpliedBy(left,right)
+                }else if operator==syntax.implies(){
+                    Node::Implies(left,right)
+                }else{
+                    Node::Iff(left,right)
                 }
-                return Ok(number);
             },
             _=>{
-                let error=format!("Formula not well formatted: Invalid character found while trying to read a variable!
-                    At position {} of {}
-                    Expected \"{}\" or a number
-                    Found \"{}\" instead",*index,string.into_iter().collect::<String>(),
-                    FRESH_VARIABLE_SYMBOL,*string.get(*index).unwrap());
-                return Err(error);
+                let expected=format!("an operator ({}, {}, {}, {}, {}, {}, {}) or \")\"",
+                    syntax.and(),syntax.or(),syntax.implies(),syntax.is_implied_by(),syntax.iff(),syntax.xor(),syntax.ite_then());
+                symbols.report(unexpected(string,*index,&expected));
+                Self::recover_group(string,index,symbols);
+                return Node::Error;
             }
+        };
+        Self::skip_spaces(string,index);
+        if *index>=string.len(){
+            symbols.report(missing_closing_bracket(string));
+            return formula;
+        }
+        if string[*index]!=')'{
+            // a binary operator takes two operands, a longer chain needs brackets
+            symbols.report(unexpected(string,*index,"\")\""));
+            Self::recover_group(string,index,symbols);
+            return Node::Error;
         }
+        *index+=1;
+        formula
     }
 
-    fn read_atom(string: &Vec<char>, index: &mut usize)->Result<Node,String>{
-        *index+=1;
+    fn read_variable_name(string: &[char], index: &mut usize, symbols: &mut ParsingSymbols)->Result<i32,ParseError>{
+        Self::skip_spaces(string,index);
         if *index>=string.len(){
-            return Err("Formula not well formatted: Found no character while trying to read an atom!".to_string());
+            return Err(missing_closing_bracket(string));
         }
-        while *string.get(*index).unwrap()==' '{
-            *index+=1;
-            if *index>=string.len(){
-                return Err("Formula not well formatted: Found no character while trying to read an atom!".to_string());
-            }
-        }
-        match *string.get(*index).unwrap() {
-            FRESH_VARIABLE_SYMBOL|'0'|'1'|'2'|'3'|'4'|'5'|'6'|'7'|'8'|'9'=>{
-                return match Self::read_variable_name(string,index){
-                    Ok(x)=>Ok(Node::Variable(x)),
-                    Err(e)=>Err(e)
-                };
-            },
-            NEGATION_OPERATOR_SYMBOL=>{
-                return match Self::read_atom(string,index){
-                    Ok(atom)=>Ok(Node::Not(Rc::new(RefCell::new(Formula::new(atom))))),
-                    Err(e)=>Err(e)
-                };
-            },
-            UNIVERSAL_QUANTIFIER_SYMBOL=>{
+        match string[*index]{
+            '0'=>{
                 *index+=1;
-                if *index>=string.len(){
-                    return Err("Formula not well formatted: Found no character while trying to read a universal quantifier!".to_string());
-                }
-                let var= match Self::read_variable_name(string,index){
-                    Ok(x)=>x,
-                    Err(e)=>{return Err(e);}
-                };
-                while *string.get(*index).unwrap()==' '{
+                Ok(0)
+            },
+            '1'..='9'=>{
+                let start=*index;
+                let mut number:Option<i32>=Some(0);
+                while *index<string.len() && string[*index].is_ascii_digit(){
+                    let digit=string[*index] as i32 - '0' as i32;
+                    number=number.and_then(|n|n.checked_mul(10)).and_then(|n|n.checked_add(digit));
                     *index+=1;
-                    if *index>=string.len(){
-                        return Err("Formula not well formatted: Found no character while trying to read a variable!".to_string());
-                    }
                 }
-                match *string.get(*index).unwrap(){
-                    QUANTIFIER_SEPARATOR_SYMBOL=>{
-                        let next_atom=match Self::read_atom(string, index){
-                            Ok(atom)=>atom,
-                            Err(e)=>{return Err(e);}
-                        };
-                        return Ok(Node::ForEach(var, Rc::new(RefCell::new(Formula::new(next_atom)))));
+                match number{
+                    Some(number)=>{
+                        symbols.used.insert(number);
+                        Ok(number)
                     },
-                    _=>{
-                        let error=format!("Formula not well formatted: Invalid character found after universal quantifier!
-                            At position {} of {}
-                            Expected \"{}\"
-                            Found \"{}\" instead",*index,string.into_iter().collect::<String>(),
-                            QUANTIFIER_SEPARATOR_SYMBOL,*string.get(*index).unwrap());
-                        return Err(error);
-                    }
+                    None=>Err(ParseError::IntegerOverflow {
+                        number: string[start..*index].iter().collect(),
+                        span: span_of(string,start,*index)
+                    })
                 }
             },
-            EXISTENTIAL_QUANTIFIER_SYMBOL=>{
-                *index+=1;
-                if *index>=string.len(){
-                    return Err("Formula not well formatted: Found no character while trying to read an existential quantifier!".to_string());
-                }
-                let var= match Self::read_variable_name(string,index){
-                    Ok(x)=>x,
+            c if is_identifier_start(c)=>{
+                let start=*index;
+                let name=match Self::read_identifier(string,index){
+                    Ok(name)=>name,
                     Err(e)=>{return Err(e);}
                 };
-                while *string.get(*index).unwrap()==' '{
-                    *index+=1;
-                    if *index>=string.len(){
-                        return Err("Formula not well formatted: Found no character while trying to read a variable!".to_string());
-                    }
-                }
-                match *string.get(*index).unwrap(){
-                    QUANTIFIER_SEPARATOR_SYMBOL=>{
-                        let next_atom=match Self::read_atom(string, index){
-                            Ok(atom)=>atom,
-                            Err(e)=>{return Err(e);}
-                        };
-                        return Ok(Node::Exists(var, Rc::new(RefCell::new(Formula::new(next_atom)))));
-                    },
-                    _=>{
-                        let error=format!("Formula not well formatted: Invalid character found after existential quantifier!
-                            At position {} of {}
-                            Expected \"{}\"
-                            Found \"{}\" instead",*index,string.into_iter().collect::<String>(),
-                            QUANTIFIER_SEPARATOR_SYMBOL,*string.get(*index).unwrap());
-                        return Err(error);
-                    }
-                }
-            },
-            TRUE_ATOM_SYMBOL=>{
-                *index+=1;
-                if *index>=string.len(){
-                    return Err("Formula not well formatted: Found no character after True atom!".to_string());
-                }
-                return Ok(Node::True);
-            },
-            FALSE_ATOM_SYMBOL=>{
-                *index+=1;
-                if *index>=string.len(){
-                    return Err("Formula not well formatted: Found no character after False atom!".to_string());
+                match Self::variable_from_name(&name,symbols){
+                    Some(variable)=>Ok(variable),
+                    None=>Err(invalid_name(string,start,*index,name))
                 }
-                return Ok(Node::False);
             },
-            '('=>{
-                return Self::from_string_at_char(string, index);
+            c if c=='(' || c==')' || symbols.syntax.is_operator(c)=>{
+                Err(ParseError::EmptyVariable { span: span_of(string,*index,*index) })
             },
-            _=>{
-                let error=format!("Formula not well formatted: Invalid character found in the beginning of an atom!
-                    At position {} of {}
-                    Expected quantifier ({},{}) or truth value ({},{}) or negation ({}) or variable number
-                    Found \"{}\" instead",*index,string.into_iter().collect::<String>(),
-                    EXISTENTIAL_QUANTIFIER_SYMBOL,UNIVERSAL_QUANTIFIER_SYMBOL,
-                    TRUE_ATOM_SYMBOL,FALSE_ATOM_SYMBOL,
-                    NEGATION_OPERATOR_SYMBOL,*string.get(*index).unwrap());
-                return Err(error);
-            }
+            _=>Err(unexpected(string,*index,"a variable"))
         }
     }
 
-    pub fn remove_quantifiers(&mut self){
-        match &mut self.root{
+    // reads letters, digits and underscores followed by any number of [index] suffixes
+    fn read_identifier(string: &[char], index: &mut usize)->Result<String,ParseError>{
+        let start=*index;
+        let mut name="".to_string();
+        while *index<string.len() && is_identifier_char(string[*index]){
+            name.push(string[*index]);
+            *index+=1;
+        }
+        while *index<string.len() && string[*index]=='['{
+            name.push('[');
+            *index+=1;
+            while *index<string.len() && string[*index].is_ascii_digit(){
+                name.push(string[*index]);
+                *index+=1;
+            }
+            if *index>=string.len() || string[*index]!=']' || name.ends_with('['){
+                if *index<string.len() && string[*index]==']'{
+                    name.push(']');
+                    *index+=1;
+                }
+                return Err(invalid_name(string,start,*index,name));
+            }
+            name.push(']');
+            *index+=1;
+        }
+        Ok(name)
+    }
+
+    fn variable_from_name(name: &str, symbols: &mut ParsingSymbols)->Option<i32>{
+        if !name.is_empty() && name.chars().all(|c|c.is_ascii_digit()){
+            if name.len()>1 && name.starts_with('0'){
+                return None;
+            }
+            let id=name.parse::<i32>().ok();
+            symbols.used.extend(id);
+            return id;
+        }
+        if is_fresh_variable_name(name,symbols.syntax){
+            let id=name[symbols.syntax.fresh_variable().len_utf8()..].parse::<i32>().ok().map(|x|-x);
+            symbols.used.extend(id);
+            return id;
+        }
+        if is_valid_name(name,symbols.syntax){
+            return Some(symbols.id_of(name));
+        }
+        None
+    }
+
+    fn read_quantified_atom(string: &[char], index: &mut usize, symbols: &mut ParsingSymbols, var: i32, universal: bool)->Result<Node,ParseError>{
+        Self::skip_spaces(string,index);
+        if *index>=string.len(){
+            return Err(missing_closing_bracket(string));
+        }
+        if string[*index]!=symbols.syntax.separator(){
+            return Err(unexpected(string,*index,&format!("\"{}\"",symbols.syntax.separator())));
+        }
+        let next_atom=Self::read_atom(string,index,symbols);
+        let link=Rc::new(RefCell::new(Formula::new(next_atom)));
+        if universal{
+            return Ok(Node::ForEach(var,link));
+        }
+        Ok(Node::Exists(var,link))
+    }
+
+    // keyword(k,a,b,...) or parity(a,b,...) with the index on the opening bracket,
+    // after an error the input is skipped past the closing bracket of the call
+    fn read_call(string: &[char], index: &mut usize, symbols: &mut ParsingSymbols, keyword: &str)->Node{
+        let fail=|error:ParseError,index:&mut usize,symbols:&mut ParsingSymbols|{
+            symbols.report(error);
+            let mut depth=0;
+            while *index<string.len(){
+                if string[*index]=='('{
+                    depth+=1;
+                }else if string[*index]==')'{
+                    if depth==0{
+                        *index+=1;
+                        break;
+                    }
+                    depth-=1;
+                }
+                *index+=1;
+            }
+            Node::Error
+        };
+        let mut bound:usize=0;
+        if keyword!=PARITY_KEYWORD{
+            *index+=1;
+            Self::skip_spaces(string,index);
+            let start=*index;
+            while *index<string.len() && string[*index].is_ascii_digit(){
+                *index+=1;
+            }
+            if start==*index{
+                return fail(unexpected(string,*index,"a number"),index,symbols);
+            }
+            let number:String=string[start..*index].iter().collect();
+            bound=match number.parse::<usize>(){
+                Ok(k)=>k,
+                Err(_)=>{
+                    return fail(ParseError::IntegerOverflow { number, span: span_of(string,start,*index) },index,symbols);
+                }
+            };
+            Self::skip_spaces(string,index);
+            if *index>=string.len() || string[*index]!=ARGUMENT_SEPARATOR_SYMBOL{
+                return fail(unexpected(string,*index,&format!("\"{}\"",ARGUMENT_SEPARATOR_SYMBOL)),index,symbols);
+            }
+        }
+        let mut arguments:Vec<Link>=vec![];
+        loop{
+            // the atom starts after the bracket or the separator
+            let argument=Self::read_atom(string,index,symbols);
+            arguments.push(Rc::new(RefCell::new(Formula::new(argument))));
+            Self::skip_spaces(string,index);
+            if *index>=string.len(){
+                symbols.report(missing_closing_bracket(string));
+                return Node::Error;
+            }
+            if string[*index]==')'{
+                *index+=1;
+                break;
+            }
+            if string[*index]!=ARGUMENT_SEPARATOR_SYMBOL{
+                let expected=format!("\"{}\" or \")\"",ARGUMENT_SEPARATOR_SYMBOL);
+                return fail(unexpected(string,*index,&expected),index,symbols);
+            }
+        }
+        call_node(keyword,bound,arguments)
+    }
+
+    // an atom that can not be read becomes an error node, and the
+    // input is skipped up to the next operator or closing bracket
+    fn read_atom(string: &[char], index: &mut usize, symbols: &mut ParsingSymbols)->Node{
+        match Self::try_read_atom(string,index,symbols){
+            Ok(node)=>node,
+            Err(e)=>{
+                symbols.report(e);
+                Self::skip_to_sync(string,index,symbols.syntax);
+                Node::Error
+            }
+        }
+    }
+
+    fn try_read_atom(string: &[char], index: &mut usize, symbols: &mut ParsingSymbols)->Result<Node,ParseError>{
+        *index+=1;
+        Self::skip_spaces(string,index);
+        if *index>=string.len(){
+            return Err(missing_closing_bracket(string));
+        }
+        let syntax=symbols.syntax;
+        match string[*index]{
+            '0'..='9'=>{
+                match Self::read_variable_name(string,index,symbols){
+                    Ok(x)=>Ok(Node::Variable(x)),
+                    Err(e)=>Err(e)
+                }
+            },
+            c if c==syntax.negation()=>{
+                let atom=Self::read_atom(string,index,symbols);
+                Ok(Node::Not(Rc::new(RefCell::new(Formula::new(atom)))))
+            },
+            '('=>{
+                Ok(Self::from_string_at_char(string,index,symbols))
+            },
+            c if is_identifier_start(c)=>{
+                let start=*index;
+                let token=match Self::read_identifier(string,index){
+                    Ok(name)=>name,
+                    Err(e)=>{return Err(e);}
+                };
+                // a keyword followed by a bracket is a call, otherwise it is an ordinary name
+                if is_call_keyword(&token){
+                    let mut next=*index;
+                    Self::skip_spaces(string,&mut next);
+                    if next<string.len() && string[next]=='('{
+                        *index=next;
+                        return Ok(Self::read_call(string,index,symbols,&token));
+                    }
+                }
+                let first=token.chars().next().unwrap();
+                let universal=first==syntax.for_all();
+                if universal || first==syntax.exists(){
+                    // a lone quantifier symbol is followed by its variable
+                    if token.len()==first.len_utf8(){
+                        let var=match Self::read_variable_name(string,index,symbols){
+                            Ok(x)=>x,
+                            Err(e)=>{return Err(e);}
+                        };
+                        return Self::read_quantified_atom(string,index,symbols,var,universal);
+                    }
+                    // otherwise the token is a quantifier only when a separator follows
+                    let mut next=*index;
+                    Self::skip_spaces(string,&mut next);
+                    if next<string.len() && string[next]==syntax.separator(){
+                        if let Some(var)=Self::variable_from_name(&token[first.len_utf8()..],symbols){
+                            return Self::read_quantified_atom(string,index,symbols,var,universal);
+                        }
+                    }
+                }
+                if token==syntax.true_atom().to_string(){
+                    return Ok(Node::True);
+                }
+                if token==syntax.false_atom().to_string(){
+                    return Ok(Node::False);
+                }
+                match Self::variable_from_name(&token,symbols){
+                    Some(x)=>Ok(Node::Variable(x)),
+                    None=>Err(invalid_name(string,start,*index,token))
+                }
+            },
+            _=>{
+                let expected=format!("a variable, a quantifier ({}, {}), a truth value ({}, {}), a negation ({}) or \"(\"",
+                    syntax.exists(),syntax.for_all(),syntax.true_atom(),syntax.false_atom(),syntax.negation());
+                Err(unexpected(string,*index,&expected))
+            }
+        }
+    }
+
+    // Pratt parser: operators bind tighter than those with a lower binding
+    // power, quantifiers extend as far right as possible
+    fn parse_conventional(tokens: &[(ConventionalToken,Span)], position: &mut usize, min_binding_power: u8, symbols: &mut ParsingSymbols)->Node{
+        let mut left=Self::parse_conventional_prefix(tokens,position,symbols);
+        // a chain of the same and/or operator becomes a single node
+        let mut chained:Option<ConventionalToken>=None;
+        loop{
+            let operator=tokens[*position].0.clone();
+            if operator==ConventionalToken::Invalid{
+                // already reported by the tokenizer, it stands in for an operator
+                *position+=1;
+                Self::parse_conventional(tokens,position,NOT_BINDING_POWER,symbols);
+                left=Node::Error;
+                chained=None;
+                continue;
+            }
+            let (left_power,right_power)=match operator.binding_power(){
+                Some(powers)=>powers,
+                None=>{break;}
+            };
+            if left_power<min_binding_power{
+                break;
+            }
+            *position+=1;
+            if operator==ConventionalToken::Then{
+                // the then branch reaches up to the matching :
+                let then_node=Self::parse_conventional(tokens,position,0,symbols);
+                chained=None;
+                if tokens[*position].0!=ConventionalToken::Else{
+                    symbols.report(unexpected_token(&tokens[*position],"\":\""));
+                    left=Node::Error;
+                    continue;
+                }
+                *position+=1;
+                let else_node=Self::parse_conventional(tokens,position,right_power,symbols);
+                left=Node::Ite(
+                    Rc::new(RefCell::new(Formula::new(left))),
+                    Rc::new(RefCell::new(Formula::new(then_node))),
+                    Rc::new(RefCell::new(Formula::new(else_node))));
+                continue;
+            }
+            let right=Self::parse_conventional(tokens,position,right_power,symbols);
+            let right_link=Rc::new(RefCell::new(Formula::new(right)));
+            if chained.as_ref()==Some(&operator){
+                match &mut left{
+                    Node::And(vec)|Node::Or(vec)=>{
+                        vec.push(right_link);
+                        continue;
+                    },
+                    _=>{}
+                }
+            }
+            let left_link=Rc::new(RefCell::new(Formula::new(left)));
+            left=match operator{
+                ConventionalToken::And=>Node::And(vec![left_link,right_link]),
+                ConventionalToken::Or=>Node::Or(vec![left_link,right_link]),
+                ConventionalToken::Xor=>Node::Xor(left_link,right_link),
+                ConventionalToken::Implies=>Node::Implies(left_link,right_link),
+                ConventionalToken::IsImpliedBy=>Node::IsImpliedBy(left_link,right_link),
+                _=>Node::Iff(left_link,right_link)
+            };
+            chained=Some(operator);
+        }
+        left
+    }
+
+    // keyword(k, a, b, ...) or parity(a, b, ...) with the position on the opening bracket
+    fn parse_conventional_call(tokens: &[(ConventionalToken,Span)], position: &mut usize, symbols: &mut ParsingSymbols, keyword: &str)->Node{
+        // skips past the closing bracket of the call
+        let fail=|error:ParseError,position:&mut usize,symbols:&mut ParsingSymbols|{
+            symbols.report(error);
+            let mut depth=0;
+            loop{
+                match tokens[*position].0{
+                    ConventionalToken::End=>{break;},
+                    ConventionalToken::Open=>{depth+=1;},
+                    ConventionalToken::Close if depth==0=>{
+                        *position+=1;
+                        break;
+                    },
+                    ConventionalToken::Close=>{depth-=1;},
+                    _=>{}
+                }
+                *position+=1;
+            }
+            Node::Error
+        };
+        *position+=1;
+        let mut bound:usize=0;
+        if keyword!=PARITY_KEYWORD{
+            match &tokens[*position]{
+                (ConventionalToken::Name(number),span) if number.chars().all(|c|c.is_ascii_digit())=>{
+                    bound=match number.parse::<usize>(){
+                        Ok(k)=>k,
+                        Err(_)=>{
+                            return fail(ParseError::IntegerOverflow { number: number.clone(), span: *span },position,symbols);
+                        }
+                    };
+                    *position+=1;
+                },
+                other=>{
+                    return fail(unexpected_token(other,"a number"),position,symbols);
+                }
+            }
+            if tokens[*position].0!=ConventionalToken::Comma{
+                return fail(unexpected_token(&tokens[*position],"\",\""),position,symbols);
+            }
+            *position+=1;
+        }
+        let mut arguments:Vec<Link>=vec![];
+        loop{
+            let argument=Self::parse_conventional(tokens,position,0,symbols);
+            arguments.push(Rc::new(RefCell::new(Formula::new(argument))));
+            match tokens[*position].0{
+                ConventionalToken::Comma=>{
+                    *position+=1;
+                },
+                ConventionalToken::Close=>{
+                    *position+=1;
+                    break;
+                },
+                ConventionalToken::End=>{
+                    symbols.report(ParseError::MissingBracket { bracket: ')', span: tokens[*position].1 });
+                    return Node::Error;
+                },
+                _=>{
+                    return fail(unexpected_token(&tokens[*position],"\",\" or \")\""),position,symbols);
+                }
+            }
+        }
+        call_node(keyword,bound,arguments)
+    }
+
+    // reads what is left up to a closing bracket or the end only for its diagnostics
+    fn skip_conventional_group(tokens: &[(ConventionalToken,Span)], position: &mut usize, symbols: &mut ParsingSymbols){
+        loop{
+            match tokens[*position].0{
+                ConventionalToken::Close|ConventionalToken::End=>{
+                    return;
+                },
+                ref token if token.binding_power().is_some()=>{
+                    *position+=1;
+                },
+                _=>{}
+            }
+            Self::parse_conventional(tokens,position,0,symbols);
+        }
+    }
+
+    fn parse_conventional_prefix(tokens: &[(ConventionalToken,Span)], position: &mut usize, symbols: &mut ParsingSymbols)->Node{
+        let (token,span)=tokens[*position].clone();
+        if token==ConventionalToken::End{
+            symbols.report(unexpected_token(&tokens[*position],"a variable, a constant, a negation, a quantifier or \"(\""));
+            return Node::Error;
+        }
+        match token{
+            // left to the enclosing bracket or to the caller
+            ConventionalToken::Close=>{
+                symbols.report(ParseError::MissingBracket { bracket: '(', span });
+                return Node::Error;
+            },
+            ref operator if operator.binding_power().is_some()=>{
+                symbols.report(unexpected_token(&(token,span),"a variable, a constant, a negation, a quantifier or \"(\""));
+                return Node::Error;
+            },
+            _=>{}
+        }
+        *position+=1;
+        match token{
+            ConventionalToken::Invalid=>Node::Error,
+            ConventionalToken::Name(name) if is_call_keyword(&name) && tokens[*position].0==ConventionalToken::Open=>{
+                Self::parse_conventional_call(tokens,position,symbols,&name)
+            },
+            ConventionalToken::Name(name)=>{
+                if name==symbols.syntax.true_atom().to_string() || name=="true"{
+                    return Node::True;
+                }
+                if name==symbols.syntax.false_atom().to_string() || name=="false"{
+                    return Node::False;
+                }
+                if !name.is_empty() && name.chars().all(|c|c.is_ascii_digit()) && name.parse::<i32>().is_err(){
+                    symbols.report(ParseError::IntegerOverflow { number: name, span });
+                    return Node::Error;
+                }
+                match Self::variable_from_name(&name,symbols){
+                    Some(x)=>Node::Variable(x),
+                    None=>{
+                        symbols.report(ParseError::InvalidVariableName { name, span });
+                        Node::Error
+                    }
+                }
+            },
+            ConventionalToken::Not=>{
+                let node=Self::parse_conventional(tokens,position,NOT_BINDING_POWER,symbols);
+                Node::Not(Rc::new(RefCell::new(Formula::new(node))))
+            },
+            ConventionalToken::Open=>{
+                let node=Self::parse_conventional(tokens,position,0,symbols);
+                match tokens[*position].0{
+                    ConventionalToken::Close=>{
+                        *position+=1;
+                        node
+                    },
+                    ConventionalToken::End=>{
+                        symbols.report(ParseError::MissingBracket { bracket: ')', span: tokens[*position].1 });
+                        node
+                    },
+                    _=>{
+                        symbols.report(unexpected_token(&tokens[*position],"an operator or \")\""));
+                        Self::skip_conventional_group(tokens,position,symbols);
+                        match tokens[*position].0{
+                            ConventionalToken::Close=>{
+                                *position+=1;
+                            },
+                            _=>{
+                                symbols.report(ParseError::MissingBracket { bracket: ')', span: tokens[*position].1 });
+                            }
+                        }
+                        Node::Error
+                    }
+                }
+            },
+            ConventionalToken::ForAll|ConventionalToken::Exists=>{
+                // forall x, y. body binds x and y in this order
+                let mut variables:Vec<i32>=vec![];
+                let mut valid=true;
+                loop{
+                    match &tokens[*position]{
+                        (ConventionalToken::Name(name),at)=>{
+                            match Self::variable_from_name(name,symbols){
+                                Some(x)=>variables.push(x),
+                                None=>{
+                                    symbols.report(ParseError::InvalidVariableName { name: name.clone(), span: *at });
+                                    valid=false;
+                                }
+                            }
+                            *position+=1;
+                        },
+                        (ConventionalToken::Comma,_) if !variables.is_empty()=>{
+                            *position+=1;
+                        },
+                        (ConventionalToken::Separator,_) if !variables.is_empty() || !valid=>{
+                            *position+=1;
+                            break;
+                        },
+                        (ConventionalToken::Separator,at)|(ConventionalToken::Comma,at)=>{
+                            symbols.report(ParseError::EmptyVariable { span: Span::point(at.start) });
+                            valid=false;
+                            *position+=1;
+                            if tokens[*position-1].0==ConventionalToken::Separator{
+                                break;
+                            }
+                        },
+                        (ConventionalToken::Invalid,_)=>{
+                            valid=false;
+                            *position+=1;
+                        },
+                        other=>{
+                            symbols.report(unexpected_token(other,"a variable, \",\" or \".\""));
+                            valid=false;
+                            break;
+                        }
+                    }
+                }
+                let mut node=Self::parse_conventional(tokens,position,0,symbols);
+                if !valid{
+                    return Node::Error;
+                }
+                for var in variables.into_iter().rev(){
+                    let link=Rc::new(RefCell::new(Formula::new(node)));
+                    node=if token==ConventionalToken::ForAll{Node::ForEach(var,link)}else{Node::Exists(var,link)};
+                }
+                node
+            },
+            _=>{
+                symbols.report(unexpected_token(&(token,span),"a variable, a constant, a negation, a quantifier or \"(\""));
+                Node::Error
+            }
+        }
+    }
+
+    pub fn remove_quantifiers(&mut self){
+        match &mut self.root{
             Node::Exists(var,formula)=>{
                 (*formula.borrow_mut()).remove_quantifiers();
-                let serial=(*formula.borrow()).to_string();
-                let mut left=Formula::from_string(serial.clone()).unwrap();
-                let mut right=Formula::from_string(serial).unwrap();
+                let mut left=formula.borrow().clone();
+                let mut right=formula.borrow().clone();
                 left.set_truth(*var, true);
                 right.set_truth(*var, false);
                 let vector=vec![Rc::new(RefCell::new(left)),Rc::new(RefCell::new(right))];
@@ -566,16 +1936,19 @@ impl Formula{
             },
             Node::ForEach(var,formula)=>{
                 (*formula.borrow_mut()).remove_quantifiers();
-                let serial=(*formula.borrow()).to_string();
-                let mut left=Formula::from_string(serial.clone()).unwrap();
-                let mut right=Formula::from_string(serial).unwrap();
+                let mut left=formula.borrow().clone();
+                let mut right=formula.borrow().clone();
                 left.set_truth(*var, true);
                 right.set_truth(*var, false);
                 let vector=vec![Rc::new(RefCell::new(left)),Rc::new(RefCell::new(right))];
                 self.root=Node::And(vector);
             },
             Node::And(vec)|
-            Node::Or(vec)=>{
+            Node::Or(vec)|
+            Node::AtMost(_,vec)|
+            Node::AtLeast(_,vec)|
+            Node::Exactly(_,vec)|
+            Node::Parity(vec)=>{
                 for formula in vec{
                     (*formula.borrow_mut()).remove_quantifiers();
                 }
@@ -587,6 +1960,11 @@ impl Formula{
                 (*a.borrow_mut()).remove_quantifiers();
                 (*b.borrow_mut()).remove_quantifiers();
             },
+            Node::Ite(c,a,b)=>{
+                (*c.borrow_mut()).remove_quantifiers();
+                (*a.borrow_mut()).remove_quantifiers();
+                (*b.borrow_mut()).remove_quantifiers();
+            },
             Node::Not(x)=>{
                 (*x.borrow_mut()).remove_quantifiers();
             },
@@ -864,10 +2242,116 @@ impl Formula{
                     }
                 }
             },
+            Node::Ite(c,a,b)=>{
+                (c.borrow_mut()).simplify_truth();
+                (a.borrow_mut()).simplify_truth();
+                (b.borrow_mut()).simplify_truth();
+                let new_node=match (&c.borrow().root,&a.borrow().root,&b.borrow().root){
+                    (Node::True,_,_)=>a.borrow().root.clone(),
+                    (Node::False,_,_)=>b.borrow().root.clone(),
+                    (_,Node::True,Node::True)=>Node::True,
+                    (_,Node::False,Node::False)=>Node::False,
+                    (_,Node::True,Node::False)=>c.borrow().root.clone(),
+                    (_,Node::False,Node::True)=>Node::Not(Rc::clone(c)),
+                    // (c?T:b) |= c*b
+                    (_,Node::True,_)=>Node::Or(vec![Rc::clone(c),Rc::clone(b)]),
+                    // (c?F:b) |= -c+b
+                    (_,Node::False,_)=>Node::And(vec![
+                        Rc::new(RefCell::new(Formula::new(Node::Not(Rc::clone(c))))),
+                        Rc::clone(b)]),
+                    // (c?a:T) |= -c*a
+                    (_,_,Node::True)=>Node::Or(vec![
+                        Rc::new(RefCell::new(Formula::new(Node::Not(Rc::clone(c))))),
+                        Rc::clone(a)]),
+                    // (c?a:F) |= c+a
+                    (_,_,Node::False)=>Node::And(vec![Rc::clone(c),Rc::clone(a)]),
+                    _=>{return;}
+                };
+                self.root=new_node;
+            },
+            Node::AtMost(_,vec)|
+            Node::AtLeast(_,vec)|
+            Node::Exactly(_,vec)=>{
+                // the constants are removed and the bound lowered by the ones that hold
+                let mut holding=0;
+                vec.retain(|x|{
+                    (x.borrow_mut()).simplify_truth();
+                    match x.borrow().root{
+                        Node::True=>{
+                            holding+=1;
+                            false
+                        },
+                        Node::False=>false,
+                        _=>true
+                    }
+                });
+                let rest=std::mem::take(vec);
+                let (lowest,highest)=match self.root{
+                    Node::AtMost(k,_)=>(0,k),
+                    Node::AtLeast(k,_)=>(k,usize::MAX),
+                    Node::Exactly(k,_)=>(k,k),
+                    _=>{unreachable!();}
+                };
+                self.root=if highest<holding{
+                    Node::False
+                }else{
+                    Self::cardinality_node(lowest.saturating_sub(holding),highest-holding,rest)
+                };
+            },
+            Node::Parity(vec)=>{
+                let mut odd=false;
+                vec.retain(|x|{
+                    (x.borrow_mut()).simplify_truth();
+                    match x.borrow().root{
+                        Node::True=>{
+                            odd^=true;
+                            false
+                        },
+                        Node::False=>false,
+                        _=>true
+                    }
+                });
+                let mut rest=std::mem::take(vec);
+                let node=if rest.is_empty(){
+                    Node::False
+                }else if rest.len()==1{
+                    let last=rest.remove(0);
+                    let node=last.borrow().root.clone();
+                    node
+                }else{
+                    Node::Parity(rest)
+                };
+                self.root=match node{
+                    Node::False if odd=>Node::True,
+                    node if odd=>Node::Not(Rc::new(RefCell::new(Formula::new(node)))),
+                    node=>node
+                };
+            },
+            // what could not be parsed counts as F
+            Node::Error=>{
+                self.root=Node::False;
+            },
             Node::True|Node::False=>{return;}
         };       
     }
 
+    // between lowest and highest of the formulas hold, with highest possibly usize::MAX,
+    // either bound is 0 or unbounded or both are equal
+    fn cardinality_node(lowest:usize, highest:usize, vec:Vec<Link>)->Node{
+        let n=vec.len();
+        if lowest>highest || lowest>n{
+            Node::False
+        }else if lowest==0 && highest>=n{
+            Node::True
+        }else if lowest==0{
+            Node::AtMost(highest,vec)
+        }else if highest>=n{
+            Node::AtLeast(lowest,vec)
+        }else{
+            Node::Exactly(lowest,vec)
+        }
+    }
+
     pub fn set_truth(&mut self, variable:i32, truth:bool){
         match &mut self.root{
             Node::Variable(y)=>{
@@ -893,84 +2377,253 @@ impl Formula{
                 (a.borrow_mut()).set_truth(variable,truth);
                 (b.borrow_mut()).set_truth(variable,truth);
             },
+            Node::Ite(c,a,b)=>{
+                (c.borrow_mut()).set_truth(variable,truth);
+                (a.borrow_mut()).set_truth(variable,truth);
+                (b.borrow_mut()).set_truth(variable,truth);
+            },
             Node::And(vec)|
-            Node::Or(vec)=>{
+            Node::Or(vec)|
+            Node::AtMost(_,vec)|
+            Node::AtLeast(_,vec)|
+            Node::Exactly(_,vec)|
+            Node::Parity(vec)=>{
                 for x in vec{
                     (*x.borrow_mut()).set_truth(variable,truth);
                 }
             },
-            Node::True|Node::False=>{}
+            Node::True|Node::False|Node::Error=>{}
         };
         self.simplify_truth();
     }
 
-    pub fn make_nnf(&mut self){
-        self.remove_quantifiers();
-        self.simplify_truth();
-        self.flatten();
-        self.remove_impl();
-        self.flatten();
-        self.push_neg_down();
-        self.flatten();
-    }
-
-    pub fn flatten(&mut self){
-        self.flatten_and();
-        self.flatten_or();
+    // rewrites to a fixpoint: constants, flattening, double negation, duplicates,
+    // complements (a&-a |= F), absorption (a&(a|b) |= a) and equal sides of ^ and <->
+    pub fn simplify(&mut self){
+        loop{
+            let before=self.clone();
+            self.simplify_truth();
+            self.flatten();
+            self.rewrite();
+            if *self==before{
+                return;
+            }
+        }
     }
 
-    fn flatten_or(&mut self){
+    fn rewrite(&mut self){
+        let mut new_node:Option<Node>=None;
+        let conjunction=matches!(self.root,Node::And(_));
+        let xor=matches!(self.root,Node::Xor(_,_));
         match &mut self.root{
-            Node::Or(old_vec)=>{
-                loop{
-                    //scan
-                    let mut vec:Vec<usize>=vec![];
-                    for i in 0..old_vec.len(){
-                        match (*old_vec.get(i).unwrap().borrow()).root{
-                            Node::Or(_)=>{vec.push(i);},
-                            _=>{}
-                        }
-                    }
-                    vec.reverse();
-                    //check
-                    if vec.len()==0{
-                        break;
-                    }
-                    //flatten
-                    for index in vec{
-                        let elem=old_vec.remove(index);
-                        match &(*elem.borrow()).root{
-                            Node::Or(atoms)=>{
-                                for atom in atoms.iter(){
-                                    old_vec.push(Rc::clone(atom));
-                                }
-                            },
-                            _=>{unreachable!();}
-                        };
-                    }
+            Node::Not(a)=>{
+                a.borrow_mut().rewrite();
+                if let Node::Not(b)=&a.borrow().root{
+                    new_node=Some(b.borrow().clone().root);
                 }
             },
-            _=>{}
-        }
-        //apply recursively on all members
-        match &mut self.root{
-            Node::And(vector)|
-            Node::Or(vector)=>{
-                for x in vector.iter(){
-                    (*x.borrow_mut()).flatten_or();
+            Node::And(vec)|
+            Node::Or(vec)=>{
+                for x in vec.iter(){
+                    x.borrow_mut().rewrite();
                 }
+                new_node=rewrite_junction(vec.clone(),conjunction);
             },
-            Node::Iff(a,b)|
+            Node::Xor(a,b)|
+            Node::Iff(a,b)=>{
+                a.borrow_mut().rewrite();
+                b.borrow_mut().rewrite();
+                let (a,b)=(a.borrow(),b.borrow());
+                if *a==*b{
+                    new_node=Some(if xor{Node::False}else{Node::True});
+                }else if a.is_negation_of(&b) || b.is_negation_of(&a){
+                    new_node=Some(if xor{Node::True}else{Node::False});
+                }
+            },
+            Node::Implies(a,b)|
+            Node::IsImpliedBy(a,b)=>{
+                a.borrow_mut().rewrite();
+                b.borrow_mut().rewrite();
+                if *a.borrow()==*b.borrow(){
+                    new_node=Some(Node::True);
+                }
+            },
+            Node::Ite(c,a,b)=>{
+                c.borrow_mut().rewrite();
+                a.borrow_mut().rewrite();
+                b.borrow_mut().rewrite();
+                if *a.borrow()==*b.borrow(){
+                    new_node=Some(a.borrow().clone().root);
+                }
+            },
+            Node::AtMost(_,vec)|
+            Node::AtLeast(_,vec)|
+            Node::Exactly(_,vec)|
+            Node::Parity(vec)=>{
+                for x in vec.iter(){
+                    x.borrow_mut().rewrite();
+                }
+            },
+            Node::Exists(_,f)|
+            Node::ForEach(_,f)=>{
+                f.borrow_mut().rewrite();
+            },
+            Node::Variable(_)|Node::True|Node::False|Node::Error=>{}
+        }
+        if let Some(node)=new_node{
+            self.root=node;
+        }
+    }
+
+    fn is_negation_of(&self,other:&Formula)->bool{
+        match &self.root{
+            Node::Not(a)=>*a.borrow()==*other,
+            _=>false
+        }
+    }
+
+    // the formulas that differ from self in one place: a subformula replaced by
+    // a constant or by one of its children, or a member dropped from a junction,
+    // at most limit of them with the changes near the root first
+    fn variants(&self, limit:usize)->Vec<Formula>{
+        let mut result:Vec<Formula>=vec![Formula::new(Node::True),Formula::new(Node::False)];
+        result.truncate(limit);
+        let children=self.root.children();
+        for child in children.iter(){
+            if result.len()==limit{
+                return result;
+            }
+            result.push(child.borrow().clone());
+        }
+        match &self.root{
+            Node::And(vec)|
+            Node::Or(vec) if vec.len()>1=>{
+                for i in 0..vec.len(){
+                    if result.len()==limit{
+                        return result;
+                    }
+                    let mut copy=self.clone();
+                    if let Node::And(members)|Node::Or(members)=&mut copy.root{
+                        members.remove(i);
+                    }
+                    result.push(copy);
+                }
+            },
+            _=>{}
+        }
+        for (i,child) in children.iter().enumerate(){
+            for variant in child.borrow().variants(limit-result.len()){
+                let copy=self.clone();
+                *copy.root.children()[i].borrow_mut()=variant;
+                result.push(copy);
+            }
+        }
+        result
+    }
+
+    // the number of nodes, counting shared ones once per occurrence
+    pub fn size(&self)->usize{
+        1+self.root.children().iter().map(|x|x.borrow().size()).sum::<usize>()
+    }
+
+    // the number of nodes, counting shared ones once
+    fn shared_size(&self)->usize{
+        let mut visited:HashSet<*const RefCell<Formula>>=HashSet::new();
+        let mut stack:Vec<Link>=self.root.children();
+        while let Some(link)=stack.pop(){
+            if visited.insert(Rc::as_ptr(&link)){
+                stack.extend(link.borrow().root.children());
+            }
+        }
+        1+visited.len()
+    }
+
+    pub fn make_nnf(&mut self){
+        self.nnf(true);
+    }
+
+    // when if-then-else, cardinality and parity nodes are kept, negations are pushed
+    // through them, e.g. -(c?a:b) |= (c?-a:-b), so that the CNF labelling can encode them directly
+    fn nnf(&mut self, expand_derived_operators:bool){
+        self.remove_quantifiers();
+        self.simplify_truth();
+        self.flatten();
+        self.remove_impl();
+        if expand_derived_operators{
+            self.expand_derived_operators();
+            self.simplify_truth();
+        }
+        self.flatten();
+        self.push_neg_down();
+        self.flatten();
+    }
+
+    pub fn flatten(&mut self){
+        self.flatten_and();
+        self.flatten_or();
+    }
+
+    fn flatten_or(&mut self){
+        match &mut self.root{
+            Node::Or(old_vec)=>{
+                loop{
+                    //scan
+                    let mut vec:Vec<usize>=vec![];
+                    for i in 0..old_vec.len(){
+                        match (*old_vec.get(i).unwrap().borrow()).root{
+                            Node::Or(_)=>{vec.push(i);},
+                            _=>{}
+                        }
+                    }
+                    vec.reverse();
+                    //check
+                    if vec.len()==0{
+                        break;
+                    }
+                    //flatten
+                    for index in vec{
+                        let elem=old_vec.remove(index);
+                        match &(*elem.borrow()).root{
+                            Node::Or(atoms)=>{
+                                for atom in atoms.iter(){
+                                    old_vec.push(Rc::clone(atom));
+                                }
+                            },
+                            _=>{unreachable!();}
+                        };
+                    }
+                }
+            },
+            _=>{}
+        }
+        //apply recursively on all members
+        match &mut self.root{
+            Node::And(vector)|
+            Node::Or(vector)|
+            Node::AtMost(_,vector)|
+            Node::AtLeast(_,vector)|
+            Node::Exactly(_,vector)|
+            Node::Parity(vector)=>{
+                for x in vector.iter(){
+                    (*x.borrow_mut()).flatten_or();
+                }
+            },
+            Node::Iff(a,b)|
             Node::Implies(a,b)|
             Node::Xor(a,b)|
             Node::IsImpliedBy(a,b)=>{
                 (*a.borrow_mut()).flatten_or();
                 (*b.borrow_mut()).flatten_or();
             },
+            Node::Ite(c,a,b)=>{
+                (*c.borrow_mut()).flatten_or();
+                (*a.borrow_mut()).flatten_or();
+                (*b.borrow_mut()).flatten_or();
+            },
             Node::Not(a)=>{
                 (*a.borrow_mut()).flatten_or();
             },
-            Node::Variable(_)|Node::True|Node::False=>{return;},
+            Node::Variable(_)|Node::True|Node::False|Node::Error=>{return;},
             Node::Exists(_,f)|
             Node::ForEach(_,f)=>{
                 (*f.borrow_mut()).flatten_and();
@@ -1014,7 +2667,11 @@ impl Formula{
         //apply recursively on all members
         match &mut self.root{
             Node::And(vector)|
-            Node::Or(vector)=>{
+            Node::Or(vector)|
+            Node::AtMost(_,vector)|
+            Node::AtLeast(_,vector)|
+            Node::Exactly(_,vector)|
+            Node::Parity(vector)=>{
                 for x in vector.iter(){
                     (*x.borrow_mut()).flatten_and();
                 }
@@ -1026,10 +2683,15 @@ impl Formula{
                 (*a.borrow_mut()).flatten_and();
                 (*b.borrow_mut()).flatten_and();
             },
+            Node::Ite(c,a,b)=>{
+                (*c.borrow_mut()).flatten_and();
+                (*a.borrow_mut()).flatten_and();
+                (*b.borrow_mut()).flatten_and();
+            },
             Node::Not(a)=>{
                 (*a.borrow_mut()).flatten_and();
             },
-            Node::Variable(_)|Node::True|Node::False=>{return;},
+            Node::Variable(_)|Node::True|Node::False|Node::Error=>{return;},
             Node::Exists(_,f)|
             Node::ForEach(_,f)=>{
                 (*f.borrow_mut()).flatten_and();
@@ -1041,25 +2703,29 @@ impl Formula{
         let mut new_node:Option<Node>=None;
         match &mut self.root{
             Node::And(vec)|
-            Node::Or(vec)=>{
+            Node::Or(vec)|
+            Node::AtMost(_,vec)|
+            Node::AtLeast(_,vec)|
+            Node::Exactly(_,vec)|
+            Node::Parity(vec)=>{
                 for x in vec.iter(){
                     (*x.borrow_mut()).remove_impl();
                 }
             },
             Node::Xor(a,b)=>{
-                // a%b |= a = -b
+                // a%b |= (a*b)+(-a*-b)
                 let mut borrow_left=a.borrow_mut();
                 borrow_left.remove_impl();
                 let mut borrow_right=b.borrow_mut();
                 borrow_right.remove_impl();
                 let right_vec=vec![
-                    Rc::clone(&a),
+                    Rc::new(RefCell::new(Formula::new(Node::Not(Rc::clone(&a))))),
                     Rc::new(RefCell::new(Formula::new(Node::Not(Rc::clone(&b)))))];
                 let right_node=Node::Or(right_vec);
                 let left_vec=vec![
-                    Rc::new(RefCell::new(Formula::new(Node::Not(Rc::clone(&a))))),
+                    Rc::clone(&a),
                     Rc::clone(&b)];
-                let left_node=Node::Not(Rc::new(RefCell::new(Formula::new(Node::Or(left_vec)))));
+                let left_node=Node::Or(left_vec);
                 let new_vec=vec![
                     Rc::new(RefCell::new(Formula::new(left_node))),
                     Rc::new(RefCell::new(Formula::new(right_node))) ];
@@ -1106,6 +2772,11 @@ impl Formula{
                     Rc::new(RefCell::new(Formula::new(right_node)))];
                 new_node=Some(Node::And(full_vec));
             },
+            Node::Ite(c,a,b)=>{
+                (c.borrow_mut()).remove_impl();
+                (a.borrow_mut()).remove_impl();
+                (b.borrow_mut()).remove_impl();
+            },
             Node::Not(x)=>{
                 (x.borrow_mut()).remove_impl();
             },
@@ -1120,11 +2791,111 @@ impl Formula{
         }
     }
 
+    // rewrites if-then-else, cardinality and parity nodes with and, or and
+    // negations, expects a formula without implications
+    fn expand_derived_operators(&mut self){
+        let mut new_node:Option<Node>=None;
+        match &self.root{
+            Node::And(vec)|
+            Node::Or(vec)=>{
+                for x in vec.iter(){
+                    (*x.borrow_mut()).expand_derived_operators();
+                }
+            },
+            Node::Not(x)=>{
+                (x.borrow_mut()).expand_derived_operators();
+            },
+            Node::Ite(c,a,b)=>{
+                // (c?a:b) |= (c+a)*(-c+b), the negated condition is a copy
+                // so that pushing negations down does not change the other branch
+                (c.borrow_mut()).expand_derived_operators();
+                (a.borrow_mut()).expand_derived_operators();
+                (b.borrow_mut()).expand_derived_operators();
+                let negated_condition=Rc::new(RefCell::new(c.borrow().clone()));
+                let left_vec=vec![
+                    Rc::clone(c),
+                    Rc::clone(a)];
+                let right_vec=vec![
+                    Rc::new(RefCell::new(Formula::new(Node::Not(negated_condition)))),
+                    Rc::clone(b)];
+                let new_vec=vec![
+                    Rc::new(RefCell::new(Formula::new(Node::And(left_vec)))),
+                    Rc::new(RefCell::new(Formula::new(Node::And(right_vec))))];
+                new_node=Some(Node::Or(new_vec));
+            },
+            Node::AtMost(k,vec)|
+            Node::AtLeast(k,vec)|
+            Node::Exactly(k,vec)=>{
+                for x in vec.iter(){
+                    (*x.borrow_mut()).expand_derived_operators();
+                }
+                let at_least=|j:usize|Rc::new(RefCell::new(Formula::new(Self::at_least_node(vec,j))));
+                let not=|link:Link|Rc::new(RefCell::new(Formula::new(Node::Not(link))));
+                new_node=Some(match self.root{
+                    Node::AtLeast(_,_)=>Self::at_least_node(vec,*k),
+                    Node::AtMost(_,_)=>Node::Not(at_least(k.saturating_add(1))),
+                    _=>Node::And(vec![at_least(*k),not(at_least(k.saturating_add(1)))])
+                });
+            },
+            Node::Parity(vec)=>{
+                // a%b%c, with each xor rewritten as for implications
+                for x in vec.iter(){
+                    (*x.borrow_mut()).expand_derived_operators();
+                }
+                let mut chain=Formula::new(vec[0].borrow().root.clone());
+                for x in vec.iter().skip(1){
+                    chain=Formula::new(Node::Xor(Rc::new(RefCell::new(chain)),Rc::clone(x)));
+                }
+                chain.remove_impl();
+                new_node=Some(chain.root);
+            },
+            _=>{}
+        }
+        if let Some(node)=new_node{
+            self.root=node;
+        }
+    }
+
+    // at least k of the formulas hold: either the first one holds and k-1 of the others,
+    // or k of the others, the subformulas are shared so the result has n*k nodes
+    fn at_least_node(vec:&[Link], k:usize)->Node{
+        let n=vec.len();
+        if k==0{
+            return Node::True;
+        }
+        if k>n{
+            return Node::False;
+        }
+        // row[j-1] holds when at least j of the formulas from i on hold
+        let mut row:Vec<Link>=vec![];
+        for i in (0..n).rev(){
+            let remaining=n-i;
+            let mut next_row:Vec<Link>=vec![];
+            for j in 1..=remaining.min(k){
+                let take=if j==1{
+                    Rc::clone(&vec[i])
+                }else{
+                    Rc::new(RefCell::new(Formula::new(Node::And(vec![Rc::clone(&vec[i]),Rc::clone(&row[j-2])]))))
+                };
+                if j<remaining{
+                    next_row.push(Rc::new(RefCell::new(Formula::new(Node::Or(vec![take,Rc::clone(&row[j-1])])))));
+                }else{
+                    next_row.push(take);
+                }
+            }
+            row=next_row;
+        }
+        let last=row.remove(k-1);
+        let node=last.borrow().root.clone();
+        node
+    }
+
     fn push_neg_down(&mut self){
         let mut new_node:Option<Node>=None;
         let mut done=false;
         match &self.root{
-            Node::And(_)|Node::Or(_)=>{},
+            Node::And(_)|Node::Or(_)|Node::Ite(_,_,_)|
+            Node::AtMost(_,_)|Node::AtLeast(_,_)|Node::Exactly(_,_)|Node::Parity(_)=>{},
             Node::Variable(_)=>{done=true;},
             Node::True|Node::False=>{done=true;},
             Node::Not(f)=>{
@@ -1147,6 +2918,39 @@ impl Formula{
                         }
                         new_node=Some(Node::And(new_vec));
                     },
+                    Node::Ite(c,a,b)=>{
+                        new_node=Some(Node::Ite(
+                            Rc::clone(c),
+                            Rc::new(RefCell::new(Formula::new(Node::Not(Rc::clone(a))))),
+                            Rc::new(RefCell::new(Formula::new(Node::Not(Rc::clone(b)))))));
+                    },
+                    // the bounds are within 1..n-1 once the truth values are simplified
+                    Node::AtMost(k,vec)=>{
+                        new_node=Some(Node::AtLeast(k+1,vec.clone()));
+                    },
+                    Node::AtLeast(k,vec)=>{
+                        new_node=Some(Node::AtMost(k-1,vec.clone()));
+                    },
+                    Node::Exactly(k,vec)=>{
+                        // fewer or more than k
+                        let mut parts:Vec<Link>=vec![];
+                        if *k>0{
+                            parts.push(Rc::new(RefCell::new(Formula::new(Node::AtMost(k-1,vec.clone())))));
+                        }
+                        if *k<vec.len(){
+                            parts.push(Rc::new(RefCell::new(Formula::new(Node::AtLeast(k+1,vec.clone())))));
+                        }
+                        new_node=Some(if parts.len()==1{
+                            parts[0].borrow().root.clone()
+                        }else{
+                            Node::Or(parts)
+                        });
+                    },
+                    Node::Parity(vec)=>{
+                        let mut new_vec=vec.clone();
+                        new_vec[0]=Rc::new(RefCell::new(Formula::new(Node::Not(Rc::clone(&vec[0])))));
+                        new_node=Some(Node::Parity(new_vec));
+                    },
                     Node::Not(x)=>{// double negation
                         let borrowed_inside=x.borrow_mut();
                         match &borrowed_inside.root{
@@ -1164,6 +2968,12 @@ impl Formula{
                                 }
                                 new_node=Some(Node::Or(new_vec));
                             },
+                            Node::Ite(c,a,b)=>{
+                                new_node=Some(Node::Ite(Rc::clone(c),Rc::clone(a),Rc::clone(b)));
+                            },
+                            node@(Node::AtMost(_,_)|Node::AtLeast(_,_)|Node::Exactly(_,_)|Node::Parity(_))=>{
+                                new_node=Some(node.clone());
+                            },
                             Node::Not(x)=>{
                                 new_node=Some(Node::Not(
                                     Rc::clone(x)
@@ -1193,11 +3003,17 @@ impl Formula{
         }
         if done{return;}
         match &self.root{
-            Node::And(vec)|Node::Or(vec)=>{
+            Node::And(vec)|Node::Or(vec)|
+            Node::AtMost(_,vec)|Node::AtLeast(_,vec)|Node::Exactly(_,vec)|Node::Parity(vec)=>{
                 for x in vec{
                     (*x.borrow_mut()).push_neg_down();
                 }
             },
+            Node::Ite(c,a,b)=>{
+                (*c.borrow_mut()).push_neg_down();
+                (*a.borrow_mut()).push_neg_down();
+                (*b.borrow_mut()).push_neg_down();
+            },
             Node::Not(_)=>{
                 self.push_neg_down();
             },
@@ -1210,7 +3026,7 @@ impl Formula{
         if self.is_cnf(){
             return;
         }
-        self.make_nnf();
+        self.nnf(false);
         // sometimes making the formula in NNF might already make it in cnf
         if self.is_cnf(){
             return;
@@ -1223,15 +3039,18 @@ impl Formula{
         match &self.root{
             Node::And(vector)=>{
                 for x in vector.iter(){
-                    if !(*x.borrow()).is_shallow(){
+                    // a nested conjunction is shallow too, but it is no clause
+                    if !(*x.borrow()).is_shallow() || matches!(x.borrow().root,Node::And(_)){
                         return false;
                     }
                 }
                 return true;
             },
+            // -T and -F are no literals, they go through the conversion like
+            // any other formula
             Node::Not(x)=>{
                 match (*x.borrow()).root{
-                    Node::Variable(_)|Node::True|Node::False=>{return true;},
+                    Node::Variable(_)=>{return true;},
                     _=>{return false;}
                 }
             },
@@ -1256,77 +3075,575 @@ impl Formula{
         }
     }
 
-    fn cnf_label(&mut self){
-        let mut next_fresh=-1;
-        if self.is_false() || self.is_true(){
-            return;
-        }
-        let mut clause_vector:Vec<Link>=vec![];
-        self.cnf_label_recursive(&mut next_fresh, &mut clause_vector);
+    pub fn is_dnf(&self)->bool{
         match &self.root{
-            Node::Variable(fresh)=>{
-                clause_vector.push(Rc::new(RefCell::new(Formula::new(Node::Variable(*fresh)))));
+            Node::Or(vector)=>{
+                for x in vector.iter(){
+                    if !(*x.borrow()).is_shallow(){
+                        return false;
+                    }
+                }
+                true
             },
-            _=>{unreachable!();}
+            // a single term
+            Node::And(_)=>self.is_shallow(),
+            Node::Not(x)=>{
+                matches!(x.borrow().root,Node::Variable(_)|Node::True|Node::False)
+            },
+            Node::Variable(_)|
+            Node::True|
+            Node::False=>true,
+            _=>false
         }
-        self.root=Node::And(clause_vector);
     }
 
-    fn cnf_label_recursive(&mut self, next_fresh:&mut i32, clause_vector: &mut Vec<Link>){
-        // bottom up approach
-        // apply recursively
-        if !self.is_shallow(){
-            match &self.root{
-                Node::And(vector)|
-                Node::Or(vector)=>{
-                    for x in vector.iter(){
-                        (*x.borrow_mut()).cnf_label_recursive(next_fresh,clause_vector);
+    fn to_dnf_terms(&self,max_terms:usize)->Result<Vec<Term>,String>{
+        let mut nnf=self.clone();
+        nnf.make_nnf();
+        let terms=match nnf.dnf_terms_recursive(max_terms){
+            Ok(terms)=>terms,
+            Err(s)=>{return Err(s);}
+        };
+        Ok(minimize_dnf_terms(terms))
+    }
+
+    // expects the formula to be in NNF
+    fn dnf_terms_recursive(&self,max_terms:usize)->Result<Vec<Term>,String>{
+        let terms=match &self.root{
+            Node::Variable(x)=>{
+                vec![Term::from([(*x,true)])]
+            },
+            Node::Not(x)=>{
+                match &x.borrow().root{
+                    Node::Variable(y)=>vec![Term::from([(*y,false)])],
+                    Node::True=>vec![],
+                    Node::False=>vec![Term::new()],
+                    _=>{unreachable!();}
+                }
+            },
+            Node::True=>vec![Term::new()],
+            Node::False=>vec![],
+            Node::Or(vector)=>{
+                let mut terms:Vec<Term>=vec![];
+                for x in vector.iter(){
+                    let child_terms=match (*x.borrow()).dnf_terms_recursive(max_terms){
+                        Ok(terms)=>terms,
+                        Err(s)=>{return Err(s);}
+                    };
+                    terms.extend(child_terms);
+                    if terms.len()>max_terms{
+                        return Err(dnf_limit_error(max_terms));
                     }
-                },
-                _=>{unreachable!();}
-            }
-        }
-        // consume operator
-        let mut new_node:Option<Node>=None;
-        match &mut self.root{
+                }
+                remove_subsumed_terms(terms)
+            },
             Node::And(vector)=>{
-                while vector.len()>1{
-                    let i=vector.remove(vector.len()-1);
-                    let j=vector.remove(vector.len()-1);
-                    let not_i=match &(*i.borrow()).root{
-                        Node::Not(internal)=>{
-                            Rc::clone(&internal)
-                        },
-                        Node::Variable(_)=>{
-                            Rc::new(RefCell::new(Formula::new(Node::Not(Rc::clone(&i)))))
-                        },
-                        _=>{unreachable!();}
-                    };
-                    let not_j=match &(*j.borrow()).root{
-                        Node::Not(internal)=>{
-                            Rc::clone(&internal)
-                        },
-                        Node::Variable(_)=>{
-                            Rc::new(RefCell::new(Formula::new(Node::Not(Rc::clone(&j)))))
-                        },
-                        _=>{unreachable!();}
+                let mut terms:Vec<Term>=vec![Term::new()];
+                for x in vector.iter(){
+                    let child_terms=match (*x.borrow()).dnf_terms_recursive(max_terms){
+                        Ok(terms)=>terms,
+                        Err(s)=>{return Err(s);}
                     };
-                    let next_variable=Rc::new(RefCell::new(Formula::new(Node::Variable(*next_fresh))));
-                    // -B * i
-                    let vec_first_inside=vec![
-                        Rc::new(RefCell::new(Formula::new(Node::Not(
-                            Rc::clone(&next_variable)
-                        )))),
-                        Rc::clone(&i)];
-                    clause_vector.push(
-                        Rc::new(RefCell::new(Formula::new(Node::Or(vec_first_inside))))
-                    );
-                    // -B * j
-                    let vec_second_inside=vec![
-                        Rc::new(RefCell::new(Formula::new(Node::Not(
-                            Rc::clone(&next_variable)
-                        )))),
-                        Rc::clone(&j)];
+                    let mut product:Vec<Term>=vec![];
+                    for left in terms.iter(){
+                        for right in child_terms.iter(){
+                            if let Some(term)=conjoin_terms(left,right){
+                                product.push(term);
+                                if product.len()>max_terms{
+                                    return Err(dnf_limit_error(max_terms));
+                                }
+                            }
+                        }
+                    }
+                    terms=remove_subsumed_terms(product);
+                }
+                terms
+            },
+            _=>{panic!("Unwanted node type while computing DNF
+                (remember that this function must always be called on a formula in NNF)");}
+        };
+        Ok(terms)
+    }
+
+    // T when there is nothing to conjoin
+    fn conjunction_of(mut conjuncts:Vec<Link>)->Self{
+        match conjuncts.len(){
+            0=>Formula::new(Node::True),
+            1=>conjuncts.remove(0).borrow().clone(),
+            _=>Formula::new(Node::And(conjuncts))
+        }
+    }
+
+    fn from_dnf_terms(terms:&[Term])->Self{
+        if terms.is_empty(){
+            return Formula::new(Node::False);
+        }
+        let mut disjuncts:Vec<Link>=vec![];
+        for term in terms.iter(){
+            if term.is_empty(){
+                return Formula::new(Node::True);
+            }
+            let mut literals:Vec<Link>=vec![];
+            for (variable,polarity) in term.iter(){
+                let atom=Rc::new(RefCell::new(Formula::new(Node::Variable(*variable))));
+                if *polarity{
+                    literals.push(atom);
+                }else{
+                    literals.push(Rc::new(RefCell::new(Formula::new(Node::Not(atom)))));
+                }
+            }
+            if literals.len()==1{
+                disjuncts.push(literals.remove(0));
+            }else{
+                disjuncts.push(Rc::new(RefCell::new(Formula::new(Node::And(literals)))));
+            }
+        }
+        if disjuncts.len()==1{
+            let last_node=disjuncts.remove(0);
+            let node=last_node.borrow().root.clone();
+            return Formula::new(node);
+        }
+        Formula::new(Node::Or(disjuncts))
+    }
+
+    fn to_anf(&self,max_monomials:usize)->Result<AlgebraicNormalForm,String>{
+        let res=match &self.root{
+            Node::Variable(x)=>AlgebraicNormalForm::variable(*x),
+            Node::True=>AlgebraicNormalForm::one(),
+            Node::False|Node::Error=>AlgebraicNormalForm::zero(),
+            Node::Not(a)=>{
+                match a.borrow().to_anf(max_monomials){
+                    Ok(anf)=>anf.not(),
+                    Err(s)=>{return Err(s);}
+                }
+            },
+            Node::And(vec)|
+            Node::Or(vec)=>{
+                let is_and=matches!(self.root,Node::And(_));
+                let mut res=if is_and{AlgebraicNormalForm::one()}else{AlgebraicNormalForm::zero()};
+                for x in vec.iter(){
+                    let child=match x.borrow().to_anf(max_monomials){
+                        Ok(anf)=>anf,
+                        Err(s)=>{return Err(s);}
+                    };
+                    let combined=if is_and{res.and(&child,max_monomials)}else{res.or(&child,max_monomials)};
+                    res=match combined{
+                        Ok(anf)=>anf,
+                        Err(s)=>{return Err(s);}
+                    };
+                }
+                res
+            },
+            Node::Xor(a,b)|
+            Node::Iff(a,b)|
+            Node::Implies(a,b)|
+            Node::IsImpliedBy(a,b)=>{
+                let left=match a.borrow().to_anf(max_monomials){
+                    Ok(anf)=>anf,
+                    Err(s)=>{return Err(s);}
+                };
+                let right=match b.borrow().to_anf(max_monomials){
+                    Ok(anf)=>anf,
+                    Err(s)=>{return Err(s);}
+                };
+                let combined=match self.root{
+                    Node::Xor(_,_)=>Ok(left.xor(&right)),
+                    Node::Iff(_,_)=>Ok(left.xor(&right).not()),
+                    // a>b |= -a*b
+                    Node::Implies(_,_)=>left.not().or(&right,max_monomials),
+                    // a<b |= a*-b
+                    _=>left.or(&right.not(),max_monomials)
+                };
+                match combined{
+                    Ok(anf)=>anf,
+                    Err(s)=>{return Err(s);}
+                }
+            },
+            Node::Ite(c,a,b)=>{
+                // (c?a:b) |= b%(c+(a%b))
+                let mut branches:Vec<AlgebraicNormalForm>=vec![];
+                for x in [c,a,b]{
+                    match x.borrow().to_anf(max_monomials){
+                        Ok(anf)=>branches.push(anf),
+                        Err(s)=>{return Err(s);}
+                    }
+                }
+                match branches[0].and(&branches[1].xor(&branches[2]),max_monomials){
+                    Ok(anf)=>anf.xor(&branches[2]),
+                    Err(s)=>{return Err(s);}
+                }
+            },
+            Node::AtMost(k,vec)|
+            Node::AtLeast(k,vec)|
+            Node::Exactly(k,vec)=>{
+                // counts[j] holds when at least j of the formulas hold
+                let limit=k.saturating_add(1).min(vec.len());
+                let mut counts=vec![AlgebraicNormalForm::zero();limit+1];
+                counts[0]=AlgebraicNormalForm::one();
+                for (i,x) in vec.iter().enumerate(){
+                    let child=match x.borrow().to_anf(max_monomials){
+                        Ok(anf)=>anf,
+                        Err(s)=>{return Err(s);}
+                    };
+                    for j in (1..=limit.min(i+1)).rev(){
+                        let with_child=match child.and(&counts[j-1],max_monomials){
+                            Ok(anf)=>anf,
+                            Err(s)=>{return Err(s);}
+                        };
+                        counts[j]=match counts[j].or(&with_child,max_monomials){
+                            Ok(anf)=>anf,
+                            Err(s)=>{return Err(s);}
+                        };
+                    }
+                }
+                let at_least=|j:usize|counts.get(j).cloned().unwrap_or_else(AlgebraicNormalForm::zero);
+                match self.root{
+                    Node::AtLeast(_,_)=>at_least(*k),
+                    Node::AtMost(_,_)=>at_least(k.saturating_add(1)).not(),
+                    // at least k+1 implies at least k
+                    _=>at_least(*k).xor(&at_least(k.saturating_add(1)))
+                }
+            },
+            Node::Parity(vec)=>{
+                let mut res=AlgebraicNormalForm::zero();
+                for x in vec.iter(){
+                    match x.borrow().to_anf(max_monomials){
+                        Ok(anf)=>{res=res.xor(&anf);},
+                        Err(s)=>{return Err(s);}
+                    }
+                }
+                res
+            },
+            Node::Exists(var,f)|
+            Node::ForEach(var,f)=>{
+                let inner=match f.borrow().to_anf(max_monomials){
+                    Ok(anf)=>anf,
+                    Err(s)=>{return Err(s);}
+                };
+                let when_true=inner.restrict(*var,true);
+                let when_false=inner.restrict(*var,false);
+                let combined=match self.root{
+                    Node::Exists(_,_)=>when_true.or(&when_false,max_monomials),
+                    _=>when_true.and(&when_false,max_monomials)
+                };
+                match combined{
+                    Ok(anf)=>anf,
+                    Err(s)=>{return Err(s);}
+                }
+            }
+        };
+        if res.len()>max_monomials{
+            return Err(crate::anf::anf_limit_error(max_monomials));
+        }
+        Ok(res)
+    }
+
+    // builds m1%m2%...%mn, negated when the constant monomial is present
+    fn from_anf(anf:&AlgebraicNormalForm)->Self{
+        let mut xor_tree:Option<Node>=None;
+        for monomial in anf.get_monomials(){
+            if monomial.is_empty(){
+                continue;
+            }
+            let node=if monomial.len()==1{
+                Node::Variable(*monomial.iter().next().unwrap())
+            }else{
+                Node::And(monomial.iter()
+                    .map(|x|Rc::new(RefCell::new(Formula::new(Node::Variable(*x)))))
+                    .collect())
+            };
+            xor_tree=match xor_tree{
+                None=>Some(node),
+                Some(tree)=>Some(Node::Xor(
+                    Rc::new(RefCell::new(Formula::new(tree))),
+                    Rc::new(RefCell::new(Formula::new(node)))))
+            };
+        }
+        let node=match xor_tree{
+            None=>{
+                if anf.has_constant_term(){Node::True}else{Node::False}
+            },
+            Some(tree)=>{
+                if anf.has_constant_term(){
+                    Node::Not(Rc::new(RefCell::new(Formula::new(tree))))
+                }else{
+                    tree
+                }
+            }
+        };
+        Formula::new(node)
+    }
+
+    fn from_bdd(manager:&BddManager,f:BddRef,memo:&mut HashMap<BddRef,Link>)->Link{
+        if let Some(link)=memo.get(&f){
+            return link.clone();
+        }
+        let link=match manager.decision(f){
+            None=>Rc::new(RefCell::new(Formula::new(if f==manager.one(){Node::True}else{Node::False}))),
+            Some((variable,low,high))=>{
+                let positive=Rc::new(RefCell::new(Formula::new(Node::Variable(variable))));
+                let negative=Rc::new(RefCell::new(Formula::new(Node::Not(Rc::new(RefCell::new(Formula::new(Node::Variable(variable))))))));
+                let mut branches:Vec<Link>=vec![];
+                for (literal,child) in [(positive,high),(negative,low)]{
+                    if child==manager.zero(){
+                        continue;
+                    }
+                    if child==manager.one(){
+                        branches.push(literal);
+                    }else{
+                        let child=Formula::from_bdd(manager,child,memo);
+                        branches.push(Rc::new(RefCell::new(Formula::new(Node::And(vec![literal,child])))));
+                    }
+                }
+                if branches.len()==1{
+                    branches.remove(0)
+                }else{
+                    Rc::new(RefCell::new(Formula::new(Node::Or(branches))))
+                }
+            }
+        };
+        memo.insert(f,link.clone());
+        link
+    }
+
+    // input i is the variable i+1, each and gate becomes one node shared by its readers
+    fn from_aig(aig:&Aig)->Vec<Link>{
+        let mut links:HashMap<u32,Link>=HashMap::new();
+        for (i,input) in aig.get_inputs().iter().enumerate(){
+            links.insert(input/2,Rc::new(RefCell::new(Formula::new(Node::Variable(i as i32+1)))));
+        }
+        let literal=|links:&HashMap<u32,Link>,x:u32|{
+            let positive=if x/2==0{
+                Rc::new(RefCell::new(Formula::new(Node::False)))
+            }else{
+                Rc::clone(&links[&(x/2)])
+            };
+            if x&1==1{
+                Rc::new(RefCell::new(Formula::new(Node::Not(positive))))
+            }else{
+                positive
+            }
+        };
+        for gate in aig.topological_order().unwrap(){
+            let gate=&aig.get_ands()[gate];
+            let link=Rc::new(RefCell::new(Formula::new(Node::And(vec![literal(&links,gate.rhs0),literal(&links,gate.rhs1)]))));
+            links.insert(gate.lhs/2,link);
+        }
+        aig.get_outputs().iter().map(|x|literal(&links,*x)).collect()
+    }
+
+    fn to_bdd(&self,manager:&mut BddManager)->BddRef{
+        match &self.root{
+            Node::Variable(x)=>manager.variable(*x),
+            Node::True=>manager.one(),
+            Node::False|Node::Error=>manager.zero(),
+            Node::Not(a)=>{
+                let inner=a.borrow().to_bdd(manager);
+                manager.not(inner)
+            },
+            Node::And(vec)|
+            Node::Or(vec)=>{
+                let (operator,mut res)=match self.root{
+                    Node::And(_)=>(BddOperator::And,manager.one()),
+                    _=>(BddOperator::Or,manager.zero())
+                };
+                for x in vec.iter(){
+                    let child=x.borrow().to_bdd(manager);
+                    res=manager.apply(operator,res,child);
+                }
+                res
+            },
+            Node::Xor(a,b)|
+            Node::Iff(a,b)|
+            Node::Implies(a,b)|
+            Node::IsImpliedBy(a,b)=>{
+                let mut left=a.borrow().to_bdd(manager);
+                let mut right=b.borrow().to_bdd(manager);
+                match self.root{
+                    Node::Xor(_,_)=>manager.xor(left,right),
+                    Node::Iff(_,_)=>manager.iff(left,right),
+                    Node::Implies(_,_)=>{
+                        left=manager.not(left);
+                        manager.or(left,right)
+                    },
+                    _=>{
+                        right=manager.not(right);
+                        manager.or(left,right)
+                    }
+                }
+            },
+            Node::Ite(c,a,b)=>{
+                let condition=c.borrow().to_bdd(manager);
+                let then_branch=a.borrow().to_bdd(manager);
+                let else_branch=b.borrow().to_bdd(manager);
+                let left=manager.and(condition,then_branch);
+                let not_condition=manager.not(condition);
+                let right=manager.and(not_condition,else_branch);
+                manager.or(left,right)
+            },
+            Node::AtMost(k,vec)|
+            Node::AtLeast(k,vec)|
+            Node::Exactly(k,vec)=>{
+                // counts[j] holds when at least j of the formulas hold
+                let limit=k.saturating_add(1).min(vec.len());
+                let mut counts=vec![manager.zero();limit+1];
+                counts[0]=manager.one();
+                for (i,x) in vec.iter().enumerate(){
+                    let child=x.borrow().to_bdd(manager);
+                    for j in (1..=limit.min(i+1)).rev(){
+                        let with_child=manager.and(child,counts[j-1]);
+                        counts[j]=manager.or(counts[j],with_child);
+                    }
+                }
+                let zero=manager.zero();
+                let at_least=|j:usize|counts.get(j).copied().unwrap_or(zero);
+                match self.root{
+                    Node::AtLeast(_,_)=>at_least(*k),
+                    Node::AtMost(_,_)=>manager.not(at_least(k.saturating_add(1))),
+                    _=>manager.xor(at_least(*k),at_least(k.saturating_add(1)))
+                }
+            },
+            Node::Parity(vec)=>{
+                let mut res=manager.zero();
+                for x in vec.iter(){
+                    let child=x.borrow().to_bdd(manager);
+                    res=manager.xor(res,child);
+                }
+                res
+            },
+            Node::Exists(var,f)=>{
+                let inner=f.borrow().to_bdd(manager);
+                manager.exists(inner,*var)
+            },
+            Node::ForEach(var,f)=>{
+                let inner=f.borrow().to_bdd(manager);
+                manager.for_all(inner,*var)
+            }
+        }
+    }
+
+    pub fn evaluate(&self,assignment:&TruthAssignment)->bool{
+        match &self.root{
+            Node::Variable(x)=>assignment.get_assignment_or_default(*x),
+            Node::True=>true,
+            Node::False|Node::Error=>false,
+            Node::Not(a)=>!(*a.borrow()).evaluate(assignment),
+            Node::And(vec)=>vec.iter().all(|x|(*x.borrow()).evaluate(assignment)),
+            Node::Or(vec)=>vec.iter().any(|x|(*x.borrow()).evaluate(assignment)),
+            Node::Xor(a,b)=>(*a.borrow()).evaluate(assignment)!=(*b.borrow()).evaluate(assignment),
+            Node::Iff(a,b)=>(*a.borrow()).evaluate(assignment)==(*b.borrow()).evaluate(assignment),
+            Node::Implies(a,b)=>!(*a.borrow()).evaluate(assignment)||(*b.borrow()).evaluate(assignment),
+            Node::IsImpliedBy(a,b)=>(*a.borrow()).evaluate(assignment)||!(*b.borrow()).evaluate(assignment),
+            Node::AtMost(k,vec)=>vec.iter().filter(|x|(*x.borrow()).evaluate(assignment)).count()<=*k,
+            Node::AtLeast(k,vec)=>vec.iter().filter(|x|(*x.borrow()).evaluate(assignment)).count()>=*k,
+            Node::Exactly(k,vec)=>vec.iter().filter(|x|(*x.borrow()).evaluate(assignment)).count()==*k,
+            Node::Parity(vec)=>vec.iter().filter(|x|(*x.borrow()).evaluate(assignment)).count()%2==1,
+            Node::Ite(c,a,b)=>{
+                if (*c.borrow()).evaluate(assignment){
+                    (*a.borrow()).evaluate(assignment)
+                }else{
+                    (*b.borrow()).evaluate(assignment)
+                }
+            },
+            Node::Exists(var,f)|
+            Node::ForEach(var,f)=>{
+                let mut branch=assignment.clone();
+                branch.add_assignment(*var,true);
+                let when_true=(*f.borrow()).evaluate(&branch);
+                branch.add_assignment(*var,false);
+                let when_false=(*f.borrow()).evaluate(&branch);
+                match self.root{
+                    Node::Exists(_,_)=>when_true||when_false,
+                    _=>when_true&&when_false
+                }
+            }
+        }
+    }
+
+    fn cnf_label(&mut self){
+        let mut next_fresh=-1;
+        if self.is_false() || self.is_true(){
+            return;
+        }
+        let mut clause_vector:Vec<Link>=vec![];
+        self.cnf_label_recursive(&mut next_fresh, &mut clause_vector);
+        match &self.root{
+            Node::Variable(fresh)=>{
+                clause_vector.push(Rc::new(RefCell::new(Formula::new(Node::Variable(*fresh)))));
+            },
+            // an at most node is labelled by a negated counter output
+            Node::Not(fresh)=>{
+                clause_vector.push(Rc::new(RefCell::new(Formula::new(Node::Not(Rc::clone(fresh))))));
+            },
+            _=>{unreachable!();}
+        }
+        self.root=Node::And(clause_vector);
+    }
+
+    fn cnf_label_recursive(&mut self, next_fresh:&mut i32, clause_vector: &mut Vec<Link>){
+        // bottom up approach
+        // apply recursively
+        if !self.is_shallow(){
+            match &self.root{
+                Node::And(vector)|
+                Node::Or(vector)|
+                Node::AtMost(_,vector)|
+                Node::AtLeast(_,vector)|
+                Node::Exactly(_,vector)|
+                Node::Parity(vector)=>{
+                    for x in vector.iter(){
+                        (*x.borrow_mut()).cnf_label_recursive(next_fresh,clause_vector);
+                    }
+                },
+                Node::Ite(c,a,b)=>{
+                    (*c.borrow_mut()).cnf_label_recursive(next_fresh,clause_vector);
+                    (*a.borrow_mut()).cnf_label_recursive(next_fresh,clause_vector);
+                    (*b.borrow_mut()).cnf_label_recursive(next_fresh,clause_vector);
+                },
+                _=>{unreachable!();}
+            }
+        }
+        // consume operator
+        let mut new_node:Option<Node>=None;
+        match &mut self.root{
+            Node::And(vector)=>{
+                while vector.len()>1{
+                    let i=vector.remove(vector.len()-1);
+                    let j=vector.remove(vector.len()-1);
+                    let not_i=match &(*i.borrow()).root{
+                        Node::Not(internal)=>{
+                            Rc::clone(&internal)
+                        },
+                        Node::Variable(_)=>{
+                            Rc::new(RefCell::new(Formula::new(Node::Not(Rc::clone(&i)))))
+                        },
+                        _=>{unreachable!();}
+                    };
+                    let not_j=match &(*j.borrow()).root{
+                        Node::Not(internal)=>{
+                            Rc::clone(&internal)
+                        },
+                        Node::Variable(_)=>{
+                            Rc::new(RefCell::new(Formula::new(Node::Not(Rc::clone(&j)))))
+                        },
+                        _=>{unreachable!();}
+                    };
+                    let next_variable=Rc::new(RefCell::new(Formula::new(Node::Variable(*next_fresh))));
+                    // -B * i
+                    let vec_first_inside=vec![
+                        Rc::new(RefCell::new(Formula::new(Node::Not(
+                            Rc::clone(&next_variable)
+                        )))),
+                        Rc::clone(&i)];
+                    clause_vector.push(
+                        Rc::new(RefCell::new(Formula::new(Node::Or(vec_first_inside))))
+                    );
+                    // -B * j
+                    let vec_second_inside=vec![
+                        Rc::new(RefCell::new(Formula::new(Node::Not(
+                            Rc::clone(&next_variable)
+                        )))),
+                        Rc::clone(&j)];
                     clause_vector.push(
                         Rc::new(RefCell::new(Formula::new(Node::Or(vec_second_inside))))
                     );
@@ -1397,6 +3714,62 @@ impl Formula{
                 let last_element=vector.remove(0);
                 new_node=Some((*last_element.borrow()).root.clone());
             },
+            Node::Ite(c,a,b)=>{
+                let label=*next_fresh;
+                let next_variable=Rc::new(RefCell::new(Formula::new(Node::Variable(label))));
+                let not_next_variable=Rc::new(RefCell::new(Formula::new(Node::Not(Rc::clone(&next_variable)))));
+                let (not_c,not_a,not_b)=(Self::negated_literal(c),Self::negated_literal(a),Self::negated_literal(b));
+                let clauses=[
+                    // -B * -c * a
+                    vec![Rc::clone(&not_next_variable),Rc::clone(&not_c),Rc::clone(a)],
+                    // -B * c * b
+                    vec![Rc::clone(&not_next_variable),Rc::clone(c),Rc::clone(b)],
+                    // B * -c * -a
+                    vec![Rc::clone(&next_variable),not_c,not_a],
+                    // B * c * -b
+                    vec![Rc::clone(&next_variable),Rc::clone(c),not_b]];
+                for clause in clauses{
+                    clause_vector.push(
+                        Rc::new(RefCell::new(Formula::new(Node::Or(clause))))
+                    );
+                }
+                *next_fresh-=1;
+                new_node=Some(Node::Variable(label));
+            },
+            Node::AtLeast(k,vector)=>{
+                let k=*k;
+                let outputs=Self::totalizer(vector,k,next_fresh,clause_vector);
+                new_node=Some(outputs[k-1].borrow().root.clone());
+            },
+            Node::AtMost(k,vector)=>{
+                let k=*k;
+                let outputs=Self::totalizer(vector,k+1,next_fresh,clause_vector);
+                new_node=Some(Self::negated_literal(&outputs[k]).borrow().root.clone());
+            },
+            Node::Exactly(k,vector)=>{
+                let k=*k;
+                let outputs=Self::totalizer(vector,k+1,next_fresh,clause_vector);
+                let mut literals:Vec<Link>=vec![];
+                if k>0{
+                    literals.push(Rc::clone(&outputs[k-1]));
+                }
+                if k<outputs.len(){
+                    literals.push(Self::negated_literal(&outputs[k]));
+                }
+                let literal=if literals.len()==1{
+                    literals.remove(0)
+                }else{
+                    Self::label_and(&literals[0],&literals[1],next_fresh,clause_vector)
+                };
+                new_node=Some(literal.borrow().root.clone());
+            },
+            Node::Parity(vector)=>{
+                let mut literal=Rc::clone(&vector[0]);
+                for x in vector.iter().skip(1){
+                    literal=Self::label_xor(&literal,x,next_fresh,clause_vector);
+                }
+                new_node=Some(literal.borrow().root.clone());
+            },
             _=>{}
         }
         if new_node.is_some(){
@@ -1404,6 +3777,92 @@ impl Formula{
         }
     }
 
+    fn fresh_label(next_fresh:&mut i32)->Link{
+        let label=Rc::new(RefCell::new(Formula::new(Node::Variable(*next_fresh))));
+        *next_fresh-=1;
+        label
+    }
+
+    fn push_clause(literals:Vec<Link>, clause_vector:&mut Vec<Link>){
+        clause_vector.push(Rc::new(RefCell::new(Formula::new(Node::Or(literals)))));
+    }
+
+    // B = i*j
+    fn label_and(i:&Link, j:&Link, next_fresh:&mut i32, clause_vector:&mut Vec<Link>)->Link{
+        let label=Self::fresh_label(next_fresh);
+        let not_label=Self::negated_literal(&label);
+        Self::push_clause(vec![Rc::clone(&not_label),Rc::clone(i)],clause_vector);
+        Self::push_clause(vec![not_label,Rc::clone(j)],clause_vector);
+        Self::push_clause(vec![Rc::clone(&label),Self::negated_literal(i),Self::negated_literal(j)],clause_vector);
+        label
+    }
+
+    // B = i%j
+    fn label_xor(i:&Link, j:&Link, next_fresh:&mut i32, clause_vector:&mut Vec<Link>)->Link{
+        let label=Self::fresh_label(next_fresh);
+        let not_label=Self::negated_literal(&label);
+        let (not_i,not_j)=(Self::negated_literal(i),Self::negated_literal(j));
+        Self::push_clause(vec![Rc::clone(&not_label),Rc::clone(i),Rc::clone(j)],clause_vector);
+        Self::push_clause(vec![not_label,Rc::clone(&not_i),Rc::clone(&not_j)],clause_vector);
+        Self::push_clause(vec![Rc::clone(&label),not_i,Rc::clone(j)],clause_vector);
+        Self::push_clause(vec![Rc::clone(&label),Rc::clone(i),not_j],clause_vector);
+        label
+    }
+
+    // totalizer: the m-th output holds exactly when at least m of the labelled inputs hold,
+    // outputs are only built up to limit since a larger count is never asked for
+    fn totalizer(inputs:&[Link], limit:usize, next_fresh:&mut i32, clause_vector:&mut Vec<Link>)->Vec<Link>{
+        if inputs.len()==1{
+            return vec![Rc::clone(&inputs[0])];
+        }
+        let left=Self::totalizer(&inputs[..inputs.len()/2],limit,next_fresh,clause_vector);
+        let right=Self::totalizer(&inputs[inputs.len()/2..],limit,next_fresh,clause_vector);
+        let (p,q)=(left.len(),right.len());
+        let m=(p+q).min(limit);
+        let outputs:Vec<Link>=(0..m).map(|_|Self::fresh_label(next_fresh)).collect();
+        for i in 0..=p{
+            for j in 0..=q{
+                // i of the left and j of the right make at least i+j
+                if i+j>=1 && i+j<=m{
+                    let mut clause=vec![Rc::clone(&outputs[i+j-1])];
+                    if i>0{
+                        clause.push(Self::negated_literal(&left[i-1]));
+                    }
+                    if j>0{
+                        clause.push(Self::negated_literal(&right[j-1]));
+                    }
+                    Self::push_clause(clause,clause_vector);
+                }
+                // at most i of the left and j of the right make at most i+j,
+                // a side has fewer outputs than limit only when it has no more inputs
+                if i+j<m{
+                    let mut clause=vec![Self::negated_literal(&outputs[i+j])];
+                    if i<p{
+                        clause.push(Rc::clone(&left[i]));
+                    }
+                    if j<q{
+                        clause.push(Rc::clone(&right[j]));
+                    }
+                    Self::push_clause(clause,clause_vector);
+                }
+            }
+        }
+        outputs
+    }
+
+    // the complement of a literal that has already been labelled
+    fn negated_literal(literal:&Link)->Link{
+        match &literal.borrow().root{
+            Node::Not(internal)=>{
+                Rc::clone(internal)
+            },
+            Node::Variable(_)=>{
+                Rc::new(RefCell::new(Formula::new(Node::Not(Rc::clone(literal)))))
+            },
+            _=>{unreachable!();}
+        }
+    }
+
     fn is_shallow(&self)->bool{
         match &self.root{
             Node::And(vector)|
@@ -1439,164 +3898,411 @@ impl Formula{
         }
     }
 
-    fn find_variables(&self)->HashSet<i32>{
-        let mut set=HashSet::new();
-        self.find_variables_recursive(&mut set);
-        return set;
+    fn expand_definitions(&mut self,definitions:&HashMap<i32,&Formula>){
+        match &mut self.root{
+            Node::Variable(x)=>{
+                if let Some(definition)=definitions.get(x){
+                    self.root=(*definition).clone().root;
+                }
+            },
+            Node::Exists(x,f)|
+            Node::ForEach(x,f)=>{
+                // a quantified variable hides the definition of the same name
+                if definitions.contains_key(x){
+                    let mut inner=definitions.clone();
+                    inner.remove(x);
+                    f.borrow_mut().expand_definitions(&inner);
+                }else{
+                    f.borrow_mut().expand_definitions(definitions);
+                }
+            },
+            Node::And(vec)|
+            Node::Or(vec)|
+            Node::AtMost(_,vec)|
+            Node::AtLeast(_,vec)|
+            Node::Exactly(_,vec)|
+            Node::Parity(vec)=>{
+                for f in vec{
+                    f.borrow_mut().expand_definitions(definitions);
+                }
+            },
+            Node::Iff(a,b)|
+            Node::Implies(a,b)|
+            Node::IsImpliedBy(a,b)|
+            Node::Xor(a,b)=>{
+                a.borrow_mut().expand_definitions(definitions);
+                b.borrow_mut().expand_definitions(definitions);
+            },
+            Node::Ite(c,a,b)=>{
+                c.borrow_mut().expand_definitions(definitions);
+                a.borrow_mut().expand_definitions(definitions);
+                b.borrow_mut().expand_definitions(definitions);
+            },
+            Node::Not(a)=>{
+                a.borrow_mut().expand_definitions(definitions);
+            },
+            Node::True|Node::False|Node::Error=>{}
+        }
     }
 
-    fn find_variables_recursive(&self,set:&mut HashSet<i32>){
-        match &self.root{
+    // like expand_definitions, but a quantifier whose variable occurs in a
+    // replacement gets a fresh variable first so that it does not capture it
+    fn substitute(&mut self,substitutions:&HashMap<i32,&Formula>,occurring:&HashSet<i32>,next:&mut i32){
+        if substitutions.is_empty(){
+            return;
+        }
+        match &mut self.root{
+            Node::Variable(x)=>{
+                if let Some(replacement)=substitutions.get(x){
+                    self.root=(*replacement).clone().root;
+                }
+            },
+            Node::Exists(x,f)|
+            Node::ForEach(x,f)=>{
+                let mut inner=substitutions.clone();
+                inner.remove(x);
+                if occurring.contains(x) && !inner.is_empty(){
+                    let fresh=*next;
+                    *next+=1;
+                    let mut mapping=HashMap::new();
+                    mapping.insert(*x,fresh);
+                    f.borrow_mut().rename_variables(&mapping);
+                    *x=fresh;
+                }
+                f.borrow_mut().substitute(&inner,occurring,next);
+            },
             Node::And(vec)|
-            Node::Or(vec)=>{
-                for x in vec.iter(){
-                    (*x.borrow()).find_variables_recursive(set);
+            Node::Or(vec)|
+            Node::AtMost(_,vec)|
+            Node::AtLeast(_,vec)|
+            Node::Exactly(_,vec)|
+            Node::Parity(vec)=>{
+                for f in vec{
+                    f.borrow_mut().substitute(substitutions,occurring,next);
                 }
             },
             Node::Iff(a,b)|
             Node::Implies(a,b)|
             Node::IsImpliedBy(a,b)|
             Node::Xor(a,b)=>{
-                (*a.borrow()).find_variables_recursive(set);
-                (*b.borrow()).find_variables_recursive(set);
+                a.borrow_mut().substitute(substitutions,occurring,next);
+                b.borrow_mut().substitute(substitutions,occurring,next);
             },
-            Node::Not(a)=>{
-                (*a.borrow()).find_variables_recursive(set);
+            Node::Ite(c,a,b)=>{
+                c.borrow_mut().substitute(substitutions,occurring,next);
+                a.borrow_mut().substitute(substitutions,occurring,next);
+                b.borrow_mut().substitute(substitutions,occurring,next);
             },
-            Node::Variable(i)=>{set.insert(*i);},
-            Node::Exists(a,f)|
-            Node::ForEach(a,f)=>{
-                set.insert(*a);
-                (*f.borrow_mut()).find_variables_recursive(set);
+            Node::Not(a)=>{
+                a.borrow_mut().substitute(substitutions,occurring,next);
             },
-            Node::True|Node::False=>{
-                return;
+            Node::True|Node::False|Node::Error=>{}
+        }
+    }
+
+    // every link is visited once, so shared subformulas are not renamed twice
+    fn rename_variables(&mut self,mapping:&HashMap<i32,i32>){
+        let mut visited:HashSet<*const RefCell<Formula>>=HashSet::new();
+        self.rename_variables_recursive(mapping,&mut visited);
+    }
+
+    fn rename_variables_recursive(&mut self,mapping:&HashMap<i32,i32>,visited:&mut HashSet<*const RefCell<Formula>>){
+        let rename=|x:&mut i32|{
+            if let Some(&y)=mapping.get(x){
+                *x=y;
             }
         };
+        let mut children:Vec<Link>=vec![];
+        match &mut self.root{
+            Node::Variable(x)=>rename(x),
+            Node::Exists(x,f)|
+            Node::ForEach(x,f)=>{
+                rename(x);
+                children.push(Rc::clone(f));
+            },
+            Node::And(vec)|
+            Node::Or(vec)|
+            Node::AtMost(_,vec)|
+            Node::AtLeast(_,vec)|
+            Node::Exactly(_,vec)|
+            Node::Parity(vec)=>{
+                children.extend(vec.iter().map(Rc::clone));
+            },
+            Node::Iff(a,b)|
+            Node::Implies(a,b)|
+            Node::IsImpliedBy(a,b)|
+            Node::Xor(a,b)=>{
+                children.push(Rc::clone(a));
+                children.push(Rc::clone(b));
+            },
+            Node::Ite(c,a,b)=>{
+                children.push(Rc::clone(c));
+                children.push(Rc::clone(a));
+                children.push(Rc::clone(b));
+            },
+            Node::Not(a)=>{
+                children.push(Rc::clone(a));
+            },
+            Node::True|Node::False|Node::Error=>{}
+        }
+        for child in children{
+            if visited.insert(Rc::as_ptr(&child)){
+                child.borrow_mut().rename_variables_recursive(mapping,visited);
+            }
+        }
+    }
+
+    // every link is visited once, formulas read from circuits share their gates
+    fn find_variables(&self)->HashSet<i32>{
+        let mut set=HashSet::new();
+        let mut visited:HashSet<*const RefCell<Formula>>=HashSet::new();
+        self.find_variables_recursive(&mut set,&mut visited);
+        return set;
+    }
+
+    fn find_variables_recursive(&self,set:&mut HashSet<i32>,visited:&mut HashSet<*const RefCell<Formula>>){
+        match &self.root{
+            Node::Variable(i)|
+            Node::Exists(i,_)|
+            Node::ForEach(i,_)=>{set.insert(*i);},
+            _=>{}
+        }
+        for child in self.root.children(){
+            if visited.insert(Rc::as_ptr(&child)){
+                child.borrow().find_variables_recursive(set,visited);
+            }
+        }
     }
 
-    fn to_string_recursive(&self) -> String{
+    fn to_string_recursive(&self, symbols:&SymbolTable, syntax:&Syntax) -> String{
         let mut res:String="".to_string();
         match &self.root{
             Node::Variable(x)=>{
-                if *x>=0{
-                    let str_var=x.to_string();
-                    res.push_str(&str_var);
-                }else{
-                    let y=-*x;
-                    res.push(FRESH_VARIABLE_SYMBOL);
-                    let str_var=y.to_string();
-                    res.push_str(&str_var);
-                }
+                res.push_str(&symbols.variable_to_string_with_syntax(*x,syntax));
             },
             Node::True=>{
-                res.push(TRUE_ATOM_SYMBOL);
+                res.push(syntax.true_atom());
+            },
+            Node::Error=>{
+                res.push(ERROR_NODE_SYMBOL);
             },
             Node::False=>{
-                res.push(FALSE_ATOM_SYMBOL);
+                res.push(syntax.false_atom());
             },
             Node::And(vec)=>{
                 res.push_str("(");
                 for i in 0..(vec.len()-1){
-                    let token=(vec.get(i).unwrap().borrow()).to_string_recursive();
+                    let token=(vec.get(i).unwrap().borrow()).to_string_recursive(symbols,syntax);
                     res.push_str(&token);
-                    res.push(AND_OPERATOR_SYMBOL);
+                    res.push(syntax.and());
                 }
-                let token=(vec.get(vec.len()-1).unwrap().borrow()).to_string_recursive();
+                let token=(vec.get(vec.len()-1).unwrap().borrow()).to_string_recursive(symbols,syntax);
                 res.push_str(&token);
                 res.push_str(")");
             },
             Node::Or(vec)=>{
                 res.push_str("(");
                 for i in 0..(vec.len()-1){
-                    let token=(vec.get(i).unwrap().borrow()).to_string_recursive();
+                    let token=(vec.get(i).unwrap().borrow()).to_string_recursive(symbols,syntax);
                     res.push_str(&token);
-                    res.push(OR_OPERATOR_SYMBOL);
+                    res.push(syntax.or());
                 }
-                let token=(vec.get(vec.len()-1).unwrap().borrow()).to_string_recursive();
+                let token=(vec.get(vec.len()-1).unwrap().borrow()).to_string_recursive(symbols,syntax);
                 res.push_str(&token);
                 res.push_str(")");
             },
             Node::Xor(a,b)=>{
                 res.push_str("(");
-                let left_str=(a.borrow()).to_string_recursive();
+                let left_str=(a.borrow()).to_string_recursive(symbols,syntax);
                 res.push_str(&left_str);
-                res.push(XOR_OPERATOR_SYMBOL);
-                let right_str=(b.borrow()).to_string_recursive();
+                res.push(syntax.xor());
+                let right_str=(b.borrow()).to_string_recursive(symbols,syntax);
                 res.push_str(&right_str);
                 res.push_str(")");
             },
             Node::Iff(a,b)=>{
                 res.push_str("(");
-                let left_str=(a.borrow()).to_string_recursive();
+                let left_str=(a.borrow()).to_string_recursive(symbols,syntax);
                 res.push_str(&left_str);
-                res.push(IFF_OPERATOR_SYMBOL);
-                let right_str=(b.borrow()).to_string_recursive();
+                res.push(syntax.iff());
+                let right_str=(b.borrow()).to_string_recursive(symbols,syntax);
                 res.push_str(&right_str);
                 res.push_str(")");
             },
             Node::Implies(a,b)=>{
                 res.push_str("(");
-                let left_str=(a.borrow()).to_string_recursive();
+                let left_str=(a.borrow()).to_string_recursive(symbols,syntax);
                 res.push_str(&left_str);
-                res.push(IMPL_OPERATOR_SYMBOL);
-                let right_str=(b.borrow()).to_string_recursive();
+                res.push(syntax.implies());
+                let right_str=(b.borrow()).to_string_recursive(symbols,syntax);
                 res.push_str(&right_str);
                 res.push_str(")");
             },
             Node::IsImpliedBy(a,b)=>{
                 res.push_str("(");
-                let left_str=(a.borrow()).to_string_recursive();
+                let left_str=(a.borrow()).to_string_recursive(symbols,syntax);
                 res.push_str(&left_str);
-                res.push(LEFT_IMPL_OPERATOR_SYMBOL);
-                let right_str=(b.borrow()).to_string_recursive();
+                res.push(syntax.is_implied_by());
+                let right_str=(b.borrow()).to_string_recursive(symbols,syntax);
                 res.push_str(&right_str);
                 res.push_str(")");
             },
+            Node::Ite(c,a,b)=>{
+                res.push('(');
+                let condition_str=(c.borrow()).to_string_recursive(symbols,syntax);
+                res.push_str(&condition_str);
+                res.push(syntax.ite_then());
+                let then_str=(a.borrow()).to_string_recursive(symbols,syntax);
+                res.push_str(&then_str);
+                res.push(syntax.ite_else());
+                let else_str=(b.borrow()).to_string_recursive(symbols,syntax);
+                res.push_str(&else_str);
+                res.push(')');
+            },
+            Node::AtMost(_,vec)|
+            Node::AtLeast(_,vec)|
+            Node::Exactly(_,vec)|
+            Node::Parity(vec)=>{
+                let mut arguments:Vec<String>=vec![];
+                let keyword=match self.root{
+                    Node::AtMost(k,_)=>{arguments.push(k.to_string());AT_MOST_KEYWORD},
+                    Node::AtLeast(k,_)=>{arguments.push(k.to_string());AT_LEAST_KEYWORD},
+                    Node::Exactly(k,_)=>{arguments.push(k.to_string());EXACTLY_KEYWORD},
+                    _=>PARITY_KEYWORD
+                };
+                for x in vec.iter(){
+                    arguments.push((x.borrow()).to_string_recursive(symbols,syntax));
+                }
+                res.push_str(keyword);
+                res.push('(');
+                res.push_str(&arguments.join(&ARGUMENT_SEPARATOR_SYMBOL.to_string()));
+                res.push(')');
+            },
             Node::Not(a)=>{
-                res.push(NEGATION_OPERATOR_SYMBOL);
-                let for_str=(a.borrow()).to_string_recursive();
+                res.push(syntax.negation());
+                let for_str=(a.borrow()).to_string_recursive(symbols,syntax);
                 res.push_str(&for_str);
             },
             Node::Exists(x,f)=>{
-                res.push(EXISTENTIAL_QUANTIFIER_SYMBOL);
-                if *x>=0{
-                    let str_var=x.to_string();
-                    res.push_str(&str_var);
-                }else{
-                    let y=-*x;
-                    res.push(FRESH_VARIABLE_SYMBOL);
-                    let str_var=y.to_string();
-                    res.push_str(&str_var);
-                }
-                res.push(QUANTIFIER_SEPARATOR_SYMBOL);
-                let formula_string=(*f.borrow()).to_string_recursive();
+                res.push(syntax.exists());
+                res.push_str(&symbols.variable_to_string_with_syntax(*x,syntax));
+                res.push(syntax.separator());
+                let formula_string=(*f.borrow()).to_string_recursive(symbols,syntax);
                 res.push_str(&formula_string);
             },
             Node::ForEach(x,f)=>{
-                res.push(UNIVERSAL_QUANTIFIER_SYMBOL);
-                if *x>=0{
-                    let str_var=x.to_string();
-                    res.push_str(&str_var);
-                }else{
-                    let y=-*x;
-                    res.push(FRESH_VARIABLE_SYMBOL);
-                    let str_var=y.to_string();
-                    res.push_str(&str_var);
-                }
-                res.push(QUANTIFIER_SEPARATOR_SYMBOL);
-                let formula_string=(*f.borrow()).to_string_recursive();
+                res.push(syntax.for_all());
+                res.push_str(&symbols.variable_to_string_with_syntax(*x,syntax));
+                res.push(syntax.separator());
+                let formula_string=(*f.borrow()).to_string_recursive(symbols,syntax);
                 res.push_str(&formula_string);
             }
         }
         return res;
     } 
 
+    // returns the text and its precedence, from 0 (quantifiers and if-then-else) to 6 (atoms and negations)
+    fn to_conventional_string_recursive(&self, symbols:&SymbolTable) -> (String,u8){
+        let child=|link:&Link|(link.borrow()).to_conventional_string_recursive(symbols);
+        let wrap=|(text,precedence):(String,u8),parenthesize:bool|{
+            if parenthesize || precedence==0{
+                format!("({})",text)
+            }else{
+                text
+            }
+        };
+        match &self.root{
+            Node::Variable(x)=>(symbols.variable_to_string(*x),6),
+            Node::True=>("true".to_string(),6),
+            Node::False=>("false".to_string(),6),
+            Node::Error=>(ERROR_NODE_SYMBOL.to_string(),6),
+            Node::Not(a)=>{
+                let (text,precedence)=child(a);
+                (format!("!{}",wrap((text,precedence),precedence<6)),6)
+            },
+            Node::And(vec)=>{
+                let tokens:Vec<String>=vec.iter().map(|x|{
+                    let (text,precedence)=child(x);
+                    wrap((text,precedence),precedence<=5)
+                }).collect();
+                (tokens.join(" & "),5)
+            },
+            Node::Or(vec)=>{
+                let tokens:Vec<String>=vec.iter().map(|x|{
+                    let (text,precedence)=child(x);
+                    wrap((text,precedence),precedence<=3)
+                }).collect();
+                (tokens.join(" | "),3)
+            },
+            Node::Xor(a,b)=>{
+                let (left,left_precedence)=child(a);
+                let (right,right_precedence)=child(b);
+                (format!("{} ^ {}",wrap((left,left_precedence),left_precedence<4),wrap((right,right_precedence),right_precedence<=4)),4)
+            },
+            Node::Iff(a,b)=>{
+                let (left,left_precedence)=child(a);
+                let (right,right_precedence)=child(b);
+                (format!("{} <-> {}",wrap((left,left_precedence),false),wrap((right,right_precedence),right_precedence<=1)),1)
+            },
+            Node::Implies(a,b)=>{
+                let (left,left_precedence)=child(a);
+                let (right,right_precedence)=child(b);
+                (format!("{} -> {}",wrap((left,left_precedence),left_precedence<=2),wrap((right,right_precedence),false)),2)
+            },
+            Node::IsImpliedBy(a,b)=>{
+                // a -> b <- c reads as a -> (b <- c)
+                let chained=matches!(a.borrow().root,Node::IsImpliedBy(_,_));
+                let (left,left_precedence)=child(a);
+                let (right,right_precedence)=child(b);
+                (format!("{} <- {}",wrap((left,left_precedence),left_precedence<=2 && !chained),wrap((right,right_precedence),right_precedence<=2)),2)
+            },
+            Node::AtMost(_,vec)|
+            Node::AtLeast(_,vec)|
+            Node::Exactly(_,vec)|
+            Node::Parity(vec)=>{
+                let mut arguments:Vec<String>=vec![];
+                let keyword=match self.root{
+                    Node::AtMost(k,_)=>{arguments.push(k.to_string());AT_MOST_KEYWORD},
+                    Node::AtLeast(k,_)=>{arguments.push(k.to_string());AT_LEAST_KEYWORD},
+                    Node::Exactly(k,_)=>{arguments.push(k.to_string());EXACTLY_KEYWORD},
+                    _=>PARITY_KEYWORD
+                };
+                // the arguments reach up to the next , or )
+                arguments.extend(vec.iter().map(|x|child(x).0));
+                (format!("{}({})",keyword,arguments.join(", ")),6)
+            },
+            Node::Ite(c,a,b)=>{
+                // the branches reach up to the : and to the right end
+                let condition=child(c);
+                (format!("{} ? {} : {}",wrap(condition,false),child(a).0,child(b).0),0)
+            },
+            Node::Exists(x,f)=>{
+                (format!("exists {}. {}",symbols.variable_to_string(*x),child(f).0),0)
+            },
+            Node::ForEach(x,f)=>{
+                (format!("forall {}. {}",symbols.variable_to_string(*x),child(f).0),0)
+            }
+        }
+    }
+
+    pub fn to_conventional_string_with_symbols(&self, symbols:&SymbolTable)->String{
+        self.to_conventional_string_recursive(symbols).0
+    }
+
     pub fn to_string(&self)->String{
-        let value=self.to_string_recursive();
+        self.to_string_with_symbols(&SymbolTable::new())
+    }
+
+    pub fn to_string_with_symbols(&self, symbols:&SymbolTable)->String{
+        self.to_string_with_syntax(symbols,&Syntax::default())
+    }
+
+    pub fn to_string_with_syntax(&self, symbols:&SymbolTable, syntax:&Syntax)->String{
+        let value=self.to_string_recursive(symbols,syntax);
         match self.root{
             Node::Variable(_)|
-            Node::True|Node::False|
+            Node::True|Node::False|Node::Error|
+            Node::AtMost(_,_)|Node::AtLeast(_,_)|Node::Exactly(_,_)|Node::Parity(_)|
             Node::Not(_)|
             Node::Exists(_,_)|Node::ForEach(_,_)=>{
                 return value;
@@ -1672,521 +4378,1625 @@ impl Formula{
                 }
                 formula.add_clause(clause)
             },
+            // no clauses, and the empty clause
+            Node::True=>{},
+            Node::False=>{
+                formula.add_clause(Clause::new());
+            },
             _=>{unreachable!();}
         }
         return formula;
     }
-}
+}
+
+// a deep copy, the clone shares no subformula with the original
+impl Clone for Formula{
+    fn clone(&self)->Self{
+        let copy=|link:&Link|Rc::new(RefCell::new(link.borrow().clone()));
+        let root=match &self.root{
+            Node::Variable(x)=>Node::Variable(*x),
+            Node::Or(vec)=>Node::Or(vec.iter().map(copy).collect()),
+            Node::And(vec)=>Node::And(vec.iter().map(copy).collect()),
+            Node::Xor(a,b)=>Node::Xor(copy(a),copy(b)),
+            Node::Not(a)=>Node::Not(copy(a)),
+            Node::Iff(a,b)=>Node::Iff(copy(a),copy(b)),
+            Node::Implies(a,b)=>Node::Implies(copy(a),copy(b)),
+            Node::IsImpliedBy(a,b)=>Node::IsImpliedBy(copy(a),copy(b)),
+            Node::Ite(c,a,b)=>Node::Ite(copy(c),copy(a),copy(b)),
+            Node::AtMost(k,vec)=>Node::AtMost(*k,vec.iter().map(copy).collect()),
+            Node::AtLeast(k,vec)=>Node::AtLeast(*k,vec.iter().map(copy).collect()),
+            Node::Exactly(k,vec)=>Node::Exactly(*k,vec.iter().map(copy).collect()),
+            Node::Parity(vec)=>Node::Parity(vec.iter().map(copy).collect()),
+            Node::Exists(x,a)=>Node::Exists(*x,copy(a)),
+            Node::ForEach(x,a)=>Node::ForEach(*x,copy(a)),
+            Node::True=>Node::True,
+            Node::False=>Node::False,
+            Node::Error=>Node::Error
+        };
+        Formula::new(root)
+    }
+}
+
+impl std::fmt::Display for Formula{
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.to_string())
+    }
+}
+
+impl Default for Formula {
+    fn default() -> Self { 
+        let string=FALSE_ATOM_SYMBOL.to_string();
+        Formula::from_string(string).unwrap()
+    }
+}
+
+// the parser reads the input wrapped in brackets, spans refer to the input itself
+fn byte_offset(string:&[char], index:usize)->usize{
+    string[1..index.clamp(1,string.len()-1)].iter().map(|c|c.len_utf8()).sum()
+}
+
+fn span_of(string:&[char], start:usize, end:usize)->Span{
+    Span::new(byte_offset(string,start),byte_offset(string,end))
+}
+
+// the closing bracket added around the input stands for its end
+fn unexpected(string:&[char], index:usize, expected:&str)->ParseError{
+    if index+1>=string.len(){
+        return ParseError::UnexpectedEnd { expected: expected.to_string(), span: span_of(string,index,index) };
+    }
+    ParseError::UnexpectedCharacter { found: string[index], expected: expected.to_string(), span: span_of(string,index,index+1) }
+}
+
+fn missing_closing_bracket(string:&[char])->ParseError{
+    ParseError::MissingBracket { bracket: ')', span: span_of(string,string.len(),string.len()) }
+}
+
+fn invalid_name(string:&[char], start:usize, end:usize, name:String)->ParseError{
+    ParseError::InvalidVariableName { name, span: span_of(string,start,end) }
+}
+
+fn is_call_keyword(name:&str)->bool{
+    [AT_MOST_KEYWORD,AT_LEAST_KEYWORD,EXACTLY_KEYWORD,PARITY_KEYWORD].contains(&name)
+}
+
+fn call_node(keyword:&str, bound:usize, arguments:Vec<Link>)->Node{
+    match keyword{
+        AT_MOST_KEYWORD=>Node::AtMost(bound,arguments),
+        AT_LEAST_KEYWORD=>Node::AtLeast(bound,arguments),
+        EXACTLY_KEYWORD=>Node::Exactly(bound,arguments),
+        _=>Node::Parity(arguments)
+    }
+}
+
+// the rewrites of an n-ary conjunction or disjunction, None when nothing applies
+fn rewrite_junction(children:Vec<Link>, conjunction:bool)->Option<Node>{
+    let mut unique:Vec<Link>=vec![];
+    for child in children.iter(){
+        if !unique.contains(child){
+            unique.push(Rc::clone(child));
+        }
+    }
+    for child in unique.iter(){
+        if let Node::Not(a)=&child.borrow().root{
+            if unique.contains(a){
+                return Some(if conjunction{Node::False}else{Node::True});
+            }
+        }
+    }
+    // the members of a child of the other kind, e.g. {a,b} for a|b inside a conjunction
+    let members:Vec<Vec<Link>>=unique.iter().map(|child|{
+        match &child.borrow().root{
+            Node::Or(vec) if conjunction=>vec.clone(),
+            Node::And(vec) if !conjunction=>vec.clone(),
+            _=>vec![Rc::clone(child)]
+        }
+    }).collect();
+    // a child absorbs every other one with more members, a&(a|b) |= a
+    let mut kept:Vec<Link>=vec![];
+    for (i,child) in unique.iter().enumerate(){
+        let absorbed=(0..unique.len()).any(|j|{
+            j!=i && members[j].len()<members[i].len() && members[j].iter().all(|x|members[i].contains(x))
+        });
+        if !absorbed{
+            kept.push(Rc::clone(child));
+        }
+    }
+    if kept.len()==children.len() && kept.len()>1{
+        return None;
+    }
+    match kept.len(){
+        0=>Some(if conjunction{Node::True}else{Node::False}),
+        1=>Some(kept[0].borrow().clone().root),
+        _=>Some(if conjunction{Node::And(kept)}else{Node::Or(kept)})
+    }
+}
+
+fn dnf_limit_error(max_terms:usize)->String{
+    format!("DNF conversion aborted: the expansion exceeds the limit of {} terms",max_terms)
+}
+
+pub(crate) fn conjoin_terms(left:&Term,right:&Term)->Option<Term>{
+    let mut res=left.clone();
+    for (variable,polarity) in right.iter(){
+        match res.insert(*variable,*polarity){
+            Some(previous) if previous!=*polarity=>{return None;},
+            _=>{}
+        }
+    }
+    Some(res)
+}
+
+pub(crate) fn is_subterm(small:&Term,big:&Term)->bool{
+    small.iter().all(|(variable,polarity)|big.get(variable)==Some(polarity))
+}
+
+// absorption: a+(a*b) |= a
+pub(crate) fn remove_subsumed_terms(mut terms:Vec<Term>)->Vec<Term>{
+    terms.sort_by(|a,b|a.len().cmp(&b.len()).then(a.cmp(b)));
+    terms.dedup();
+    let mut kept:Vec<Term>=vec![];
+    for term in terms.into_iter(){
+        if !kept.iter().any(|k|is_subterm(k,&term)){
+            kept.push(term);
+        }
+    }
+    kept
+}
+
+// applies absorption and the self-subsuming rule (a*x)+(a*b*-x) |= (a*x)+(a*b)
+// until no term can be shortened or removed, this is no covering step, a consensus
+// term such as b*c in (a*b)+(-a*c)+(b*c) is kept
+fn minimize_dnf_terms(terms:Vec<Term>)->Vec<Term>{
+    let mut terms=remove_subsumed_terms(terms);
+    loop{
+        let mut changed=false;
+        for i in 0..terms.len(){
+            for j in 0..terms.len(){
+                if i==j{
+                    continue;
+                }
+                let clashing=terms[i].iter().find(|(variable,polarity)|terms[j].get(variable)==Some(&!**polarity));
+                let variable=match clashing{
+                    Some((variable,_))=>*variable,
+                    None=>{continue;}
+                };
+                let mut reduced_i=terms[i].clone();
+                reduced_i.remove(&variable);
+                let mut reduced_j=terms[j].clone();
+                reduced_j.remove(&variable);
+                if is_subterm(&reduced_i,&reduced_j){
+                    terms[j]=reduced_j;
+                    changed=true;
+                }
+            }
+        }
+        if !changed{
+            break;
+        }
+        terms=remove_subsumed_terms(terms);
+    }
+    terms
+}
+
+#[cfg(test)]
+mod test_parsing{
+    use super::*;
+
+    #[test]
+    fn lone_variable(){
+        let expected=format!("{:?}",Formula::new(Node::Variable(0)));
+        let formula=Formula::from_string("0".to_string()).unwrap();
+        let gotten=format!("{:?}",formula);
+        assert_eq!(expected,gotten);
+    }
+
+    #[test]
+    fn lone_negated_variable(){
+        let expected=format!("{:?}",Formula::new(Node::Not(Rc::new(RefCell::new(Formula::new(Node::Variable(0)))))));
+        let string=format!("{}0",NEGATION_OPERATOR_SYMBOL);
+        let formula=Formula::from_string(string).unwrap();
+        let gotten=format!("{:?}",formula);
+        assert_eq!(gotten,expected);
+    }
+
+    #[test]
+    fn lone_universal_quantifier(){
+        let expected=format!("{:?}",Formula::new(Node::ForEach(
+            0,
+            Rc::new(RefCell::new(Formula::new(Node::Variable(0)))),
+        )));
+        let string=format!("{}0{}0",UNIVERSAL_QUANTIFIER_SYMBOL,QUANTIFIER_SEPARATOR_SYMBOL);
+        let formula=Formula::from_string(string).unwrap();
+        let gotten=format!("{:?}",formula);
+        assert_eq!(gotten,expected);
+    }
+
+    #[test]
+    fn universal_quantifier_with_formula(){
+        let expected=format!("{:?}",Formula::new(Node::ForEach(
+            0,
+            Rc::new(RefCell::new(Formula::new(Node::Iff(
+                Rc::new(RefCell::new(Formula::new(Node::Variable(0)))),
+                Rc::new(RefCell::new(Formula::new(Node::Variable(1)))),
+            )))),
+        )));
+        let string=format!("{}0{}(0{}1)",UNIVERSAL_QUANTIFIER_SYMBOL,QUANTIFIER_SEPARATOR_SYMBOL,IFF_OPERATOR_SYMBOL);
+        let formula=Formula::from_string(string).unwrap();
+        let gotten=format!("{:?}",formula);
+        assert_eq!(gotten,expected);
+    }
+
+    #[test]
+    fn lone_existential_quantifier(){
+        let expected=format!("{:?}",Formula::new(Node::Exists(
+            0,
+            Rc::new(RefCell::new(Formula::new(Node::Variable(0)))),
+        )));
+        let string=format!("{}0{}0",EXISTENTIAL_QUANTIFIER_SYMBOL,QUANTIFIER_SEPARATOR_SYMBOL);
+        let formula=Formula::from_string(string).unwrap();
+        let gotten=format!("{:?}",formula);
+        assert_eq!(gotten,expected);
+    }
+
+    #[test]
+    fn existential_quantifier_with_formula(){
+        let expected=format!("{:?}",Formula::new(Node::Exists(
+            0,
+            Rc::new(RefCell::new(Formula::new(Node::Iff(
+                Rc::new(RefCell::new(Formula::new(Node::Variable(0)))),
+                Rc::new(RefCell::new(Formula::new(Node::Variable(1)))),
+            )))),
+        )));
+        let string=format!("{}0{}(0{}1)",EXISTENTIAL_QUANTIFIER_SYMBOL,QUANTIFIER_SEPARATOR_SYMBOL,IFF_OPERATOR_SYMBOL);
+        let formula=Formula::from_string(string).unwrap();
+        let gotten=format!("{:?}",formula);
+        assert_eq!(gotten,expected);
+    }
+
+    #[test]
+    fn and_operator(){
+        let expected=format!("{:?}",Formula::new(Node::And(
+            vec![Rc::new(RefCell::new(Formula::new(Node::Variable(0)))),
+            Rc::new(RefCell::new(Formula::new(Node::Variable(1)))),
+            Rc::new(RefCell::new(Formula::new(Node::Variable(2))))]
+        )));
+        let string=format!("0{}1{}2",AND_OPERATOR_SYMBOL,AND_OPERATOR_SYMBOL);
+        let formula=Formula::from_string(string).unwrap();
+        let gotten=format!("{:?}",formula);
+        assert_eq!(gotten,expected);
+    }
+
+    #[test]
+    fn or_operator(){
+        let expected=format!("{:?}",Formula::new(Node::Or(
+            vec![Rc::new(RefCell::new(Formula::new(Node::Variable(0)))),
+            Rc::new(RefCell::new(Formula::new(Node::Variable(1)))),
+            Rc::new(RefCell::new(Formula::new(Node::Variable(2))))]
+        )));
+        let string=format!("0{}1{}2",OR_OPERATOR_SYMBOL,OR_OPERATOR_SYMBOL);
+        let formula=Formula::from_string(string).unwrap();
+        let gotten=format!("{:?}",formula);
+        assert_eq!(gotten,expected);
+    }
+
+    #[test]
+    fn xor_operator(){
+        let expected=format!("{:?}",Formula::new(Node::Xor(
+            Rc::new(RefCell::new(Formula::new(Node::Variable(0)))),
+            Rc::new(RefCell::new(Formula::new(Node::Variable(1))))
+        )));
+        let string=format!("0{}1",XOR_OPERATOR_SYMBOL);
+        let formula=Formula::from_string(string).unwrap();
+        let gotten=format!("{:?}",formula);
+        assert_eq!(gotten,expected);
+    }
+
+    #[test]
+    fn impl_operator(){
+        let expected=format!("{:?}",Formula::new(Node::Implies(
+            Rc::new(RefCell::new(Formula::new(Node::Variable(0)))),
+            Rc::new(RefCell::new(Formula::new(Node::Variable(1))))
+        )));
+        let string=format!("0{}1",IMPL_OPERATOR_SYMBOL);
+        let formula=Formula::from_string(string).unwrap();
+        let gotten=format!("{:?}",formula);
+        assert_eq!(gotten,expected);
+    }
+
+    #[test]
+    fn left_impl_operator(){
+        let expected=format!("{:?}",Formula::new(Node::IsImpliedBy(
+            Rc::new(RefCell::new(Formula::new(Node::Variable(0)))),
+            Rc::new(RefCell::new(Formula::new(Node::Variable(1))))
+        )));
+        let string=format!("0{}1",LEFT_IMPL_OPERATOR_SYMBOL);
+        let formula=Formula::from_string(string).unwrap();
+        let gotten=format!("{:?}",formula);
+        assert_eq!(gotten,expected);
+    }
+
+    #[test]
+    fn iff_operator(){
+        let expected=format!("{:?}",Formula::new(Node::Iff(
+            Rc::new(RefCell::new(Formula::new(Node::Variable(0)))),
+            Rc::new(RefCell::new(Formula::new(Node::Variable(1))))
+        )));
+        let string=format!("0{}1",IFF_OPERATOR_SYMBOL);
+        let formula=Formula::from_string(string).unwrap();
+        let gotten=format!("{:?}",formula);
+        assert_eq!(gotten,expected);
+    }
+
+    #[test]
+    fn nested_formula(){
+        let expected=format!("{:?}",Formula::new(Node::Iff(
+            Rc::new(RefCell::new(Formula::new(Node::And(
+                vec![
+                    Rc::new(RefCell::new(Formula::new(Node::Variable(0)))),
+                    Rc::new(RefCell::new(Formula::new(Node::Variable(1))))
+                ]
+            )))),
+            Rc::new(RefCell::new(Formula::new(Node::ForEach(
+                1,
+                Rc::new(RefCell::new(Formula::new(Node::Variable(1))))
+            ))))
+        )));
+        let string=format!("(0{}1){}{}1{}1",AND_OPERATOR_SYMBOL,IFF_OPERATOR_SYMBOL,UNIVERSAL_QUANTIFIER_SYMBOL,QUANTIFIER_SEPARATOR_SYMBOL);
+        let formula=Formula::from_string(string).unwrap();
+        let gotten=format!("{:?}",formula);
+        assert_eq!(gotten,expected);
+    }
+
+    #[test]
+    fn nested_formula_with_too_many_parenthesis(){
+        let expected=format!("{:?}",Formula::new(Node::Iff(
+            Rc::new(RefCell::new(Formula::new(Node::And(
+                vec![
+                    Rc::new(RefCell::new(Formula::new(Node::Variable(0)))),
+                    Rc::new(RefCell::new(Formula::new(Node::Variable(1))))
+                ]
+            )))),
+            Rc::new(RefCell::new(Formula::new(Node::ForEach(
+                1,
+                Rc::new(RefCell::new(Formula::new(Node::Variable(1))))
+            ))))
+        )));
+        let string=format!("(((0{}(1))){}({}1{}1))",AND_OPERATOR_SYMBOL,IFF_OPERATOR_SYMBOL,UNIVERSAL_QUANTIFIER_SYMBOL,QUANTIFIER_SEPARATOR_SYMBOL);
+        let formula=Formula::from_string(string).unwrap();
+        let gotten=format!("{:?}",formula);
+        assert_eq!(gotten,expected);
+    }
+
+    #[test]
+    fn nested_formula_with_blank_spaces(){
+        let expected=format!("{:?}",Formula::new(Node::Iff(
+            Rc::new(RefCell::new(Formula::new(Node::And(
+                vec![
+                    Rc::new(RefCell::new(Formula::new(Node::Variable(0)))),
+                    Rc::new(RefCell::new(Formula::new(Node::Variable(1))))
+                ]
+            )))),
+            Rc::new(RefCell::new(Formula::new(Node::ForEach(
+                1,
+                Rc::new(RefCell::new(Formula::new(Node::Variable(1))))
+            ))))
+        )));
+        let string=format!("    ( 0 {}  1) {} {} 1{} 1   ",AND_OPERATOR_SYMBOL,IFF_OPERATOR_SYMBOL,UNIVERSAL_QUANTIFIER_SYMBOL,QUANTIFIER_SEPARATOR_SYMBOL);
+        let formula=Formula::from_string(string).unwrap();
+        let gotten=format!("{:?}",formula);
+        assert_eq!(gotten,expected);
+    }
+
+    #[test]
+    fn multiple_negations(){
+        let expected=format!("{:?}",
+            Formula::new(Node::Not(
+                Rc::new(RefCell::new(Formula::new(Node::Not(
+                    Rc::new(RefCell::new(Formula::new(Node::Not(
+                        Rc::new(RefCell::new(Formula::new(Node::Variable(1))))
+                    )))),
+                ))))
+            )));
+        let string=format!("{}{}{}1",NEGATION_OPERATOR_SYMBOL,NEGATION_OPERATOR_SYMBOL,NEGATION_OPERATOR_SYMBOL);
+        let formula=Formula::from_string(string).unwrap();
+        let gotten=format!("{:?}",formula);
+        assert_eq!(gotten,expected);
+    }
+
+    #[test]
+    fn lone_fresh_variable(){
+        let expected=format!("{:?}",Formula::new(Node::Variable(-1)));
+        let string=format!("{}1",FRESH_VARIABLE_SYMBOL);
+        let formula=Formula::from_string(string).unwrap();
+        let gotten=format!("{:?}",formula);
+        assert_eq!(gotten,expected);
+    }
+
+    #[test]
+    fn quantifier_without_separator(){
+        let string=format!("{}1(1{}2)",EXISTENTIAL_QUANTIFIER_SYMBOL,AND_OPERATOR_SYMBOL);
+        let error=Formula::from_string(string);
+        assert!(error.is_err());
+    }
+
+    #[test]
+    fn mixture_of_and_plus_or_operators(){
+        let string=format!("0{}1{}2{}3",OR_OPERATOR_SYMBOL,AND_OPERATOR_SYMBOL,AND_OPERATOR_SYMBOL);
+        let error=Formula::from_string(string);
+        assert!(error.is_err());
+    }
+
+    #[test]
+    fn nested_quantifiers(){
+        let expected=format!("{:?}",Formula::new(Node::ForEach(
+            1,
+            Rc::new(RefCell::new(Formula::new(Node::Exists(
+                2,
+                Rc::new(RefCell::new(Formula::new(Node::Variable(1))))
+            ))))
+        )));
+        let string=format!("{}1{}{}2{}1",UNIVERSAL_QUANTIFIER_SYMBOL,QUANTIFIER_SEPARATOR_SYMBOL,EXISTENTIAL_QUANTIFIER_SYMBOL,QUANTIFIER_SEPARATOR_SYMBOL);
+        let formula=Formula::from_string(string).unwrap();
+        let gotten=format!("{:?}",formula);
+        assert_eq!(gotten,expected);
+    }
+}
+
+#[cfg(test)]
+mod test_serialization{
+    use super::*;
+
+    #[test]
+    fn lone_variable(){
+        let expected="1".to_string();
+        let formula=Formula{
+            root: Node::Variable(1)
+        };
+        assert_eq!(expected,formula.to_string());
+    }
+
+    #[test]
+    fn lone_fresh_variable(){
+        let expected=format!("{}1",FRESH_VARIABLE_SYMBOL);
+        let formula=Formula{
+            root: Node::Variable(-1)
+        };
+        assert_eq!(expected,formula.to_string());
+    }
+
+    #[test]
+    fn lone_negation(){
+        let expected=format!("{}1",NEGATION_OPERATOR_SYMBOL);
+        let formula=Formula{
+            root: Node::Not(
+                Rc::new(RefCell::new(Formula::new(Node::Variable(1))))
+            )
+        };
+        assert_eq!(expected,formula.to_string());
+    }
+
+    #[test]
+    fn lone_negation_of_fresh_variable(){
+        let expected=format!("{}{}1",NEGATION_OPERATOR_SYMBOL,FRESH_VARIABLE_SYMBOL);
+        let formula=Formula{
+            root: Node::Not(
+                Rc::new(RefCell::new(Formula::new(Node::Variable(-1))))
+            )
+        };
+        assert_eq!(expected,formula.to_string());
+    }
+
+    #[test]
+    fn multiple_negations(){
+        let expected=format!("{}{}{}1",NEGATION_OPERATOR_SYMBOL,NEGATION_OPERATOR_SYMBOL,NEGATION_OPERATOR_SYMBOL);
+        let formula=Formula{
+            root: Node::Not(
+                Rc::new(RefCell::new(Formula::new(Node::Not(
+                    Rc::new(RefCell::new(Formula::new(Node::Not(
+                        Rc::new(RefCell::new(Formula::new(Node::Variable(1))))
+                    ))))
+                ))))
+            )
+        };
+        assert_eq!(expected,formula.to_string());
+    }
+
+    #[test]
+    fn and_operator(){
+        let expected=format!("1{}2{}1{}3",AND_OPERATOR_SYMBOL,AND_OPERATOR_SYMBOL,AND_OPERATOR_SYMBOL);
+        let formula=Formula{
+            root: Node::And(
+                vec![
+                    Rc::new(RefCell::new(Formula::new(Node::Variable(1)))),
+                    Rc::new(RefCell::new(Formula::new(Node::Variable(2)))),
+                    Rc::new(RefCell::new(Formula::new(Node::Variable(1)))),
+                    Rc::new(RefCell::new(Formula::new(Node::Variable(3)))) 
+                ]
+            )
+        };
+        assert_eq!(expected,formula.to_string());
+    }
+
+    #[test]
+    fn or_operator(){
+        let expected=format!("1{}2{}1{}3",OR_OPERATOR_SYMBOL,OR_OPERATOR_SYMBOL,OR_OPERATOR_SYMBOL);
+        let formula=Formula{
+            root: Node::Or(
+                vec![
+                    Rc::new(RefCell::new(Formula::new(Node::Variable(1)))),
+                    Rc::new(RefCell::new(Formula::new(Node::Variable(2)))),
+                    Rc::new(RefCell::new(Formula::new(Node::Variable(1)))),
+                    Rc::new(RefCell::new(Formula::new(Node::Variable(3)))) 
+                ]
+            )
+        };
+        assert_eq!(expected,formula.to_string());
+    }
+
+    #[test]
+    fn xor_operator(){
+        let expected=format!("1{}2",XOR_OPERATOR_SYMBOL);
+        let formula=Formula{
+            root: Node::Xor(
+                Rc::new(RefCell::new(Formula::new(Node::Variable(1)))),
+                Rc::new(RefCell::new(Formula::new(Node::Variable(2))))
+            )
+        };
+        assert_eq!(expected,formula.to_string());
+    }
+
+    #[test]
+    fn impl_operator(){
+        let expected=format!("1{}2",IMPL_OPERATOR_SYMBOL);
+        let formula=Formula{
+            root: Node::Implies(
+                Rc::new(RefCell::new(Formula::new(Node::Variable(1)))),
+                Rc::new(RefCell::new(Formula::new(Node::Variable(2))))
+            )
+        };
+        assert_eq!(expected,formula.to_string());
+    }
+
+    #[test]
+    fn left_impl_operator(){
+        let expected=format!("1{}2",LEFT_IMPL_OPERATOR_SYMBOL);
+        let formula=Formula{
+            root: Node::IsImpliedBy(
+                Rc::new(RefCell::new(Formula::new(Node::Variable(1)))),
+                Rc::new(RefCell::new(Formula::new(Node::Variable(2))))
+            )
+        };
+        assert_eq!(expected,formula.to_string());
+    }
+
+    #[test]
+    fn iff_operator(){
+        let expected=format!("1{}2",IFF_OPERATOR_SYMBOL);
+        let formula=Formula{
+            root: Node::Iff(
+                Rc::new(RefCell::new(Formula::new(Node::Variable(1)))),
+                Rc::new(RefCell::new(Formula::new(Node::Variable(2))))
+            )
+        };
+        assert_eq!(expected,formula.to_string());
+    }
+
+    #[test]
+    fn nested_formula(){
+        let expected=format!("(1{}2){}(1{}2)",IFF_OPERATOR_SYMBOL,AND_OPERATOR_SYMBOL,XOR_OPERATOR_SYMBOL);
+        let formula=Formula{
+            root: Node::And(
+                vec![Rc::new(RefCell::new(Formula::new(Node::Iff(
+                        Rc::new(RefCell::new(Formula::new(Node::Variable(1)))),
+                        Rc::new(RefCell::new(Formula::new(Node::Variable(2))))
+                    )))),
+                    Rc::new(RefCell::new(Formula::new(Node::Xor(
+                        Rc::new(RefCell::new(Formula::new(Node::Variable(1)))),
+                        Rc::new(RefCell::new(Formula::new(Node::Variable(2))))
+                    )))),
+                ]
+            )
+        };
+        assert_eq!(expected,formula.to_string()); 
+    }
+
+    #[test]
+    fn universal_quantifier_shallow(){
+        let expected=format!("{}1{}2",UNIVERSAL_QUANTIFIER_SYMBOL,QUANTIFIER_SEPARATOR_SYMBOL);
+        let formula=Formula{
+            root: Node::ForEach(
+                1,
+                Rc::new(RefCell::new(Formula::new(Node::Variable(2))))
+            )
+        };
+        assert_eq!(expected,formula.to_string());        
+    }
+
+    #[test]
+    fn existential_quantifier_shallow(){
+        let expected=format!("{}1{}2",EXISTENTIAL_QUANTIFIER_SYMBOL,QUANTIFIER_SEPARATOR_SYMBOL);
+        let formula=Formula{
+            root: Node::Exists(
+                1,
+                Rc::new(RefCell::new(Formula::new(Node::Variable(2))))
+            )
+        };
+        assert_eq!(expected,formula.to_string());        
+    }
+
+    #[test]
+    fn universal_quantifier_deep(){
+        let expected=format!("{}1{}(2{}3)",UNIVERSAL_QUANTIFIER_SYMBOL,QUANTIFIER_SEPARATOR_SYMBOL,IMPL_OPERATOR_SYMBOL);
+        let formula=Formula{
+            root: Node::ForEach(
+                1,
+                Rc::new(RefCell::new(Formula::new(Node::Implies(
+                    Rc::new(RefCell::new(Formula::new(Node::Variable(2)))),
+                    Rc::new(RefCell::new(Formula::new(Node::Variable(3)))) 
+                ))))
+            )
+        };
+        assert_eq!(expected,formula.to_string());        
+    }
+
+    #[test]
+    fn existential_quantifier_deep(){
+        let expected=format!("{}1{}(2{}3)",EXISTENTIAL_QUANTIFIER_SYMBOL,QUANTIFIER_SEPARATOR_SYMBOL,IMPL_OPERATOR_SYMBOL);
+        let formula=Formula{
+            root: Node::Exists(
+                1,
+                Rc::new(RefCell::new(Formula::new(Node::Implies(
+                    Rc::new(RefCell::new(Formula::new(Node::Variable(2)))),
+                    Rc::new(RefCell::new(Formula::new(Node::Variable(3)))) 
+                ))))
+            )
+        };
+        assert_eq!(expected,formula.to_string());          
+    }
+
+    #[test]
+    fn double_quantifier(){
+        let expected=format!("{}1{}{}2{}3",EXISTENTIAL_QUANTIFIER_SYMBOL,QUANTIFIER_SEPARATOR_SYMBOL,UNIVERSAL_QUANTIFIER_SYMBOL,QUANTIFIER_SEPARATOR_SYMBOL);
+        let formula=Formula{
+            root: Node::Exists(
+                1,
+                Rc::new(RefCell::new(Formula::new(Node::ForEach(
+                    2,
+                    Rc::new(RefCell::new(Formula::new(Node::Variable(3)))) 
+                ))))
+            )
+        };
+        assert_eq!(expected,formula.to_string());   
+    }
+}
+
+
+#[cfg(test)]
+mod test_dnf{
+    use super::*;
+
+    fn equivalent_by_enumeration(a:&BooleanFormula,b:&BooleanFormula)->bool{
+        let variables:Vec<i32>=a.get_variables().union(b.get_variables()).copied().collect();
+        for bits in 0..(1u32<<variables.len()){
+            let mut assignment=TruthAssignment::new();
+            for (i,var) in variables.iter().enumerate(){
+                assignment.add_assignment(*var,bits&(1<<i)!=0);
+            }
+            if a.evaluate(&assignment)!=b.evaluate(&assignment){
+                return false;
+            }
+        }
+        true
+    }
+
+    #[test]
+    fn lone_variable_is_dnf(){
+        let formula="1".parse::<BooleanFormula>().unwrap();
+        assert!(formula.is_dnf());
+        assert_eq!(formula.get_dnf().unwrap().to_string(),"1");
+    }
+
+    #[test]
+    fn single_term_is_dnf(){
+        let string=format!("1{}{}2",AND_OPERATOR_SYMBOL,NEGATION_OPERATOR_SYMBOL);
+        let formula=BooleanFormula::from_string(string).unwrap();
+        assert!(formula.is_dnf());
+    }
+
+    #[test]
+    fn cnf_is_not_dnf(){
+        let string=format!("(1{}2){}(3{}4)",OR_OPERATOR_SYMBOL,AND_OPERATOR_SYMBOL,OR_OPERATOR_SYMBOL);
+        let formula=BooleanFormula::from_string(string).unwrap();
+        assert!(!formula.is_dnf());
+        let dnf=formula.get_dnf().unwrap();
+        assert!(dnf.is_dnf());
+        assert!(equivalent_by_enumeration(&formula,&dnf));
+    }
+
+    #[test]
+    fn absorption(){
+        // 1*(1+2) |= 1
+        let string=format!("1{}(1{}2)",OR_OPERATOR_SYMBOL,AND_OPERATOR_SYMBOL);
+        let formula=BooleanFormula::from_string(string).unwrap();
+        assert_eq!(formula.get_dnf().unwrap().to_string(),"1");
+    }
+
+    #[test]
+    fn complementary_terms_are_merged(){
+        // (1+2)*(1+-2) |= 1
+        let string=format!("(1{}2){}(1{}{}2)",AND_OPERATOR_SYMBOL,OR_OPERATOR_SYMBOL,AND_OPERATOR_SYMBOL,NEGATION_OPERATOR_SYMBOL);
+        let formula=BooleanFormula::from_string(string).unwrap();
+        assert_eq!(formula.get_dnf().unwrap().to_string(),"1");
+    }
+
+    #[test]
+    fn consensus_terms_are_kept(){
+        // (1+2)*(-1+3)*(2+3), the last term is covered by the other two
+        let string=format!("(1{}2){}({}1{}3){}(2{}3)",AND_OPERATOR_SYMBOL,OR_OPERATOR_SYMBOL,NEGATION_OPERATOR_SYMBOL,
+            AND_OPERATOR_SYMBOL,OR_OPERATOR_SYMBOL,AND_OPERATOR_SYMBOL);
+        let formula=BooleanFormula::from_string(string).unwrap();
+        let dnf=formula.get_dnf().unwrap();
+        assert!(dnf.is_dnf());
+        assert_eq!(dnf.to_string().matches(OR_OPERATOR_SYMBOL).count(),2);
+        let minimal=formula.get_minimal_dnf().unwrap();
+        assert_eq!(minimal.to_string().matches(OR_OPERATOR_SYMBOL).count(),1);
+        assert!(equivalent_by_enumeration(&dnf,&minimal));
+    }
+
+    #[test]
+    fn xor_elimination(){
+        // 1%2 used to become 1+-2, which is false for -1,2
+        let string=format!("1{}2",XOR_OPERATOR_SYMBOL);
+        let formula=BooleanFormula::from_string(string).unwrap();
+        let nnf=formula.get_nnf();
+        assert!(equivalent_by_enumeration(&formula,&nnf));
+        let mut assignment=TruthAssignment::new();
+        assignment.add_assignment(1,false);
+        assignment.add_assignment(2,true);
+        assert!(nnf.evaluate(&assignment));
+        let dnf=formula.get_dnf().unwrap();
+        assert!(equivalent_by_enumeration(&formula,&dnf));
+        let negated=BooleanFormula::from_string(format!("{}(1{}2)",NEGATION_OPERATOR_SYMBOL,XOR_OPERATOR_SYMBOL)).unwrap();
+        assert!(equivalent_by_enumeration(&negated,&negated.get_nnf()));
+    }
+
+    #[test]
+    fn tautology_and_contradiction(){
+        let string=format!("1{}{}1",OR_OPERATOR_SYMBOL,NEGATION_OPERATOR_SYMBOL);
+        let formula=BooleanFormula::from_string(string).unwrap();
+        assert!(formula.get_dnf().unwrap().is_true());
+        let string=format!("1{}{}1",AND_OPERATOR_SYMBOL,NEGATION_OPERATOR_SYMBOL);
+        let formula=BooleanFormula::from_string(string).unwrap();
+        assert!(formula.get_dnf().unwrap().is_false());
+    }
+
+    #[test]
+    fn implications_and_quantifiers(){
+        let string=format!("{}3{}((1{}2){}(3{}4))",EXISTENTIAL_QUANTIFIER_SYMBOL,QUANTIFIER_SEPARATOR_SYMBOL,
+            IMPL_OPERATOR_SYMBOL,XOR_OPERATOR_SYMBOL,IFF_OPERATOR_SYMBOL);
+        let formula=BooleanFormula::from_string(string).unwrap();
+        let dnf=formula.get_dnf().unwrap();
+        assert!(dnf.is_dnf());
+        assert!(equivalent_by_enumeration(&formula,&dnf));
+    }
+
+    #[test]
+    fn expansion_over_the_limit(){
+        // (1*2)+(3*4)+(5*6) expands to 8 terms
+        let string=format!("(1{}2){}(3{}4){}(5{}6)",OR_OPERATOR_SYMBOL,AND_OPERATOR_SYMBOL,OR_OPERATOR_SYMBOL,
+            AND_OPERATOR_SYMBOL,OR_OPERATOR_SYMBOL);
+        let formula=BooleanFormula::from_string(string).unwrap();
+        assert!(formula.get_dnf_with_limit(7).is_err());
+        let dnf=formula.get_dnf_with_limit(8).unwrap();
+        assert!(equivalent_by_enumeration(&formula,&dnf));
+    }
+}
+
+#[cfg(test)]
+mod test_conventional{
+    use super::*;
+
+    fn native(input:&str)->String{
+        BooleanFormula::from_conventional_str(input).unwrap().to_string()
+    }
+
+    #[test]
+    fn and_binds_tighter_than_or(){
+        assert_eq!(native("a | b & c"),format!("a{}(b{}c)",OR_OPERATOR_SYMBOL,AND_OPERATOR_SYMBOL));
+        assert_eq!(native("(a | b) & c"),format!("(a{}b){}c",OR_OPERATOR_SYMBOL,AND_OPERATOR_SYMBOL));
+    }
 
-impl Clone for Formula{
-    fn clone(&self)->Self{
-        let string=self.to_string();
-        Self::from_string(string).unwrap()
+    #[test]
+    fn negation_binds_tightest(){
+        assert_eq!(native("!a & ~b"),format!("{n}a{}{n}b",AND_OPERATOR_SYMBOL,n=NEGATION_OPERATOR_SYMBOL));
+        assert_eq!(native("!!a"),format!("{n}{n}a",n=NEGATION_OPERATOR_SYMBOL));
+        assert_eq!(native("!(a | b)"),format!("{}(a{}b)",NEGATION_OPERATOR_SYMBOL,OR_OPERATOR_SYMBOL));
     }
-}
 
-impl std::fmt::Display for Formula{
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        write!(f, "{}", self.to_string())
+    #[test]
+    fn xor_between_and_and_or(){
+        assert_eq!(native("a ^ b | c & d"),format!("(a{}b){}(c{}d)",XOR_OPERATOR_SYMBOL,OR_OPERATOR_SYMBOL,AND_OPERATOR_SYMBOL));
+        assert_eq!(native("a ^ b ^ c"),format!("(a{x}b){x}c",x=XOR_OPERATOR_SYMBOL));
     }
-}
 
-impl Default for Formula {
-    fn default() -> Self { 
-        let string=FALSE_ATOM_SYMBOL.to_string();
-        Formula::from_string(string).unwrap()
+    #[test]
+    fn chains_become_one_node(){
+        assert_eq!(native("a & b & c"),format!("a{o}b{o}c",o=AND_OPERATOR_SYMBOL));
+        assert_eq!(native("(a & b) & c"),format!("(a{o}b){o}c",o=AND_OPERATOR_SYMBOL));
+        assert_eq!(native("a | b | c & d | e"),format!("a{o}b{o}(c{}d){o}e",AND_OPERATOR_SYMBOL,o=OR_OPERATOR_SYMBOL));
+    }
+
+    #[test]
+    fn implication_associativity(){
+        assert_eq!(native("a -> b -> c"),format!("a{i}(b{i}c)",i=IMPL_OPERATOR_SYMBOL));
+        assert_eq!(native("a <- b <- c"),format!("(a{i}b){i}c",i=LEFT_IMPL_OPERATOR_SYMBOL));
+        assert_eq!(native("a | b -> c"),format!("(a{}b){}c",OR_OPERATOR_SYMBOL,IMPL_OPERATOR_SYMBOL));
+    }
+
+    #[test]
+    fn iff_binds_loosest(){
+        assert_eq!(native("a <-> b -> c"),format!("a{}(b{}c)",IFF_OPERATOR_SYMBOL,IMPL_OPERATOR_SYMBOL));
+        assert_eq!(native("a<->b<->c"),format!("(a{i}b){i}c",i=IFF_OPERATOR_SYMBOL));
+    }
+
+    #[test]
+    fn quantifiers_extend_to_the_right(){
+        let expected=format!("{u}x{s}{e}y{s}(x{}(y{}z))",OR_OPERATOR_SYMBOL,AND_OPERATOR_SYMBOL,
+            u=UNIVERSAL_QUANTIFIER_SYMBOL,e=EXISTENTIAL_QUANTIFIER_SYMBOL,s=QUANTIFIER_SEPARATOR_SYMBOL);
+        assert_eq!(native("forall x. exists y. x | y & z"),expected);
+        let both=format!("{u}x{s}{u}y{s}(x{}y)",OR_OPERATOR_SYMBOL,u=UNIVERSAL_QUANTIFIER_SYMBOL,s=QUANTIFIER_SEPARATOR_SYMBOL);
+        assert_eq!(native("forall x, y. x | y"),both);
+        assert_eq!(native("forall x y. x | y"),both);
+        assert_eq!(native("z & (exists x. x)"),format!("z{}{}x{}x",AND_OPERATOR_SYMBOL,EXISTENTIAL_QUANTIFIER_SYMBOL,QUANTIFIER_SEPARATOR_SYMBOL));
+    }
+
+    #[test]
+    fn atoms(){
+        assert_eq!(native("true | false"),format!("{}{}{}",TRUE_ATOM_SYMBOL,OR_OPERATOR_SYMBOL,FALSE_ATOM_SYMBOL));
+        assert_eq!(native("T"),TRUE_ATOM_SYMBOL.to_string());
+        assert_eq!(native("1 & x[2] & f3"),format!("1{a}x[2]{a}{}3",FRESH_VARIABLE_SYMBOL,a=AND_OPERATOR_SYMBOL));
+    }
+
+    #[test]
+    fn errors(){
+        for input in ["","a &","(a | b","a | b)","a b","a $ b","forall . a","forall x a","a -> -> b","x[ & a","!"]{
+            assert!(BooleanFormula::from_conventional_str(input).is_err(),"{} should not parse",input);
+        }
+    }
+
+    #[test]
+    fn printing_round_trip(){
+        let inputs=["a | b & c","(a | b) & c","!(a & b) ^ c","a -> b -> c","(a -> b) -> c","a <- b <- c","a <- (b <- c)",
+            "(a -> b) <- c","a <-> (b <-> c)","forall x. exists y. x ^ y","(forall x. x) & !(exists y. y | z)","!!a | true",
+            "(a & b) & c","a ^ (b ^ c)"];
+        for input in inputs{
+            let formula=BooleanFormula::from_conventional_str(input).unwrap();
+            let printed=formula.to_conventional_string();
+            let reparsed=BooleanFormula::from_conventional_string(printed.clone()).unwrap();
+            assert_eq!(formula.to_string(),reparsed.to_string(),"{} printed as {}",input,printed);
+        }
+        assert_eq!(BooleanFormula::from_conventional_str("(a | b) & !c").unwrap().to_conventional_string(),"(a | b) & !c");
+    }
+
+    #[test]
+    fn agrees_with_native_syntax(){
+        let string=format!("(1{}2){}{}3",AND_OPERATOR_SYMBOL,IMPL_OPERATOR_SYMBOL,NEGATION_OPERATOR_SYMBOL);
+        let formula=BooleanFormula::from_string(string.clone()).unwrap();
+        assert_eq!(formula.to_conventional_string(),"1 & 2 -> !3");
+        assert_eq!(native("1 & 2 -> !3"),string);
     }
 }
 
 #[cfg(test)]
-mod test_parsing{
+mod test_recovery{
     use super::*;
 
     #[test]
-    fn lone_variable(){
-        let expected=format!("{:?}",Formula::new(Node::Variable(0)));
-        let formula=Formula::from_string("0".to_string()).unwrap();
-        let gotten=format!("{:?}",formula);
-        assert_eq!(expected,gotten);
+    fn collects_every_error(){
+        let input=format!("1{a}${a}2{a}(3{o}#)",a=AND_OPERATOR_SYMBOL,o=OR_OPERATOR_SYMBOL);
+        let (formula,errors)=BooleanFormula::from_string_recovering(input);
+        assert_eq!(errors.len(),2);
+        assert!(matches!(errors[0],ParseError::UnexpectedCharacter{ found: '$', .. }));
+        assert!(matches!(errors[1],ParseError::UnexpectedCharacter{ found: '#', .. }));
+        let partial=format!("1{a}{e}{a}2{a}(3{o}{e})",a=AND_OPERATOR_SYMBOL,o=OR_OPERATOR_SYMBOL,e=ERROR_NODE_SYMBOL);
+        assert_eq!(formula.to_string(),partial);
+        assert!(formula.has_error_nodes());
     }
 
     #[test]
-    fn lone_negated_variable(){
-        let expected=format!("{:?}",Formula::new(Node::Not(Rc::new(RefCell::new(Formula::new(Node::Variable(0)))))));
-        let string=format!("{}0",NEGATION_OPERATOR_SYMBOL);
-        let formula=Formula::from_string(string).unwrap();
-        let gotten=format!("{:?}",formula);
-        assert_eq!(gotten,expected);
+    fn strict_parsing_reports_the_first_error(){
+        let input=format!("1{a}${a}#",a=AND_OPERATOR_SYMBOL);
+        let (_,errors)=BooleanFormula::from_string_recovering(input.clone());
+        assert_eq!(BooleanFormula::from_string(input).unwrap_err(),errors[0]);
     }
 
     #[test]
-    fn lone_universal_quantifier(){
-        let expected=format!("{:?}",Formula::new(Node::ForEach(
-            0,
-            Rc::new(RefCell::new(Formula::new(Node::Variable(0)))),
-        )));
-        let string=format!("{}0{}0",UNIVERSAL_QUANTIFIER_SYMBOL,QUANTIFIER_SEPARATOR_SYMBOL);
-        let formula=Formula::from_string(string).unwrap();
-        let gotten=format!("{:?}",formula);
-        assert_eq!(gotten,expected);
+    fn missing_operand(){
+        let input=format!("1{a}{a}2",a=AND_OPERATOR_SYMBOL);
+        let (formula,errors)=BooleanFormula::from_string_recovering(input);
+        assert_eq!(errors.len(),1);
+        assert_eq!(formula.to_string(),format!("1{a}{e}{a}2",a=AND_OPERATOR_SYMBOL,e=ERROR_NODE_SYMBOL));
     }
 
     #[test]
-    fn universal_quantifier_with_formula(){
-        let expected=format!("{:?}",Formula::new(Node::ForEach(
-            0,
-            Rc::new(RefCell::new(Formula::new(Node::Iff(
-                Rc::new(RefCell::new(Formula::new(Node::Variable(0)))),
-                Rc::new(RefCell::new(Formula::new(Node::Variable(1)))),
-            )))),
-        )));
-        let string=format!("{}0{}(0{}1)",UNIVERSAL_QUANTIFIER_SYMBOL,QUANTIFIER_SEPARATOR_SYMBOL,IFF_OPERATOR_SYMBOL);
-        let formula=Formula::from_string(string).unwrap();
-        let gotten=format!("{:?}",formula);
-        assert_eq!(gotten,expected);
+    fn brackets(){
+        let unclosed=format!("(1{a}(2{o}3)",a=AND_OPERATOR_SYMBOL,o=OR_OPERATOR_SYMBOL);
+        let (formula,errors)=BooleanFormula::from_string_recovering(unclosed);
+        assert_eq!(errors,vec![ParseError::MissingBracket { bracket: ')', span: Span::point(8) }]);
+        assert_eq!(formula.get_variables().len(),3);
+        let unopened=format!("1)){a}$",a=AND_OPERATOR_SYMBOL);
+        let (_,errors)=BooleanFormula::from_string_recovering(unopened);
+        assert_eq!(errors.len(),3);
+        assert_eq!(errors[0],ParseError::MissingBracket { bracket: '(', span: Span::new(1,2) });
+        assert_eq!(errors[1],ParseError::MissingBracket { bracket: '(', span: Span::new(2,3) });
     }
 
     #[test]
-    fn lone_existential_quantifier(){
-        let expected=format!("{:?}",Formula::new(Node::Exists(
-            0,
-            Rc::new(RefCell::new(Formula::new(Node::Variable(0)))),
-        )));
-        let string=format!("{}0{}0",EXISTENTIAL_QUANTIFIER_SYMBOL,QUANTIFIER_SEPARATOR_SYMBOL);
-        let formula=Formula::from_string(string).unwrap();
-        let gotten=format!("{:?}",formula);
-        assert_eq!(gotten,expected);
+    fn mixed_operators_become_an_error(){
+        let input=format!("(1{a}2{o}3){a}x[",a=AND_OPERATOR_SYMBOL,o=OR_OPERATOR_SYMBOL);
+        let (formula,errors)=BooleanFormula::from_string_recovering(input);
+        assert_eq!(errors.len(),2);
+        assert!(matches!(errors[1],ParseError::InvalidVariableName{ .. }));
+        assert_eq!(formula.to_string(),format!("{e}{a}{e}",a=AND_OPERATOR_SYMBOL,e=ERROR_NODE_SYMBOL));
     }
 
     #[test]
-    fn existential_quantifier_with_formula(){
-        let expected=format!("{:?}",Formula::new(Node::Exists(
-            0,
-            Rc::new(RefCell::new(Formula::new(Node::Iff(
-                Rc::new(RefCell::new(Formula::new(Node::Variable(0)))),
-                Rc::new(RefCell::new(Formula::new(Node::Variable(1)))),
-            )))),
-        )));
-        let string=format!("{}0{}(0{}1)",EXISTENTIAL_QUANTIFIER_SYMBOL,QUANTIFIER_SEPARATOR_SYMBOL,IFF_OPERATOR_SYMBOL);
-        let formula=Formula::from_string(string).unwrap();
-        let gotten=format!("{:?}",formula);
-        assert_eq!(gotten,expected);
+    fn conventional_diagnostics(){
+        let (formula,errors)=BooleanFormula::from_conventional_string_recovering("a & $ | (b -> ) & x[".to_string());
+        assert_eq!(errors.len(),3);
+        assert!(matches!(errors[0],ParseError::UnexpectedCharacter{ found: '$', .. }));
+        assert!(matches!(errors[1],ParseError::InvalidVariableName{ .. }));
+        assert_eq!(errors[2],ParseError::MissingBracket { bracket: '(', span: Span::new(14,15) });
+        assert!(formula.has_error_nodes());
+        assert_eq!(formula.get_variables().len(),2);
     }
 
     #[test]
-    fn and_operator(){
-        let expected=format!("{:?}",Formula::new(Node::And(
-            vec![Rc::new(RefCell::new(Formula::new(Node::Variable(0)))),
-            Rc::new(RefCell::new(Formula::new(Node::Variable(1)))),
-            Rc::new(RefCell::new(Formula::new(Node::Variable(2))))]
-        )));
-        let string=format!("0{}1{}2",AND_OPERATOR_SYMBOL,AND_OPERATOR_SYMBOL);
-        let formula=Formula::from_string(string).unwrap();
-        let gotten=format!("{:?}",formula);
-        assert_eq!(gotten,expected);
+    fn conventional_trailing_input_and_brackets(){
+        let (formula,errors)=BooleanFormula::from_conventional_string_recovering("(a | b c) d)".to_string());
+        assert_eq!(errors.len(),3);
+        assert!(matches!(errors[0],ParseError::UnexpectedCharacter{ found: 'c', .. }));
+        assert_eq!(errors[1],ParseError::TrailingInput { span: Span::new(10,12) });
+        assert_eq!(errors[2],ParseError::MissingBracket { bracket: '(', span: Span::new(11,12) });
+        assert_eq!(formula.to_string(),ERROR_NODE_SYMBOL.to_string());
     }
 
     #[test]
-    fn or_operator(){
-        let expected=format!("{:?}",Formula::new(Node::Or(
-            vec![Rc::new(RefCell::new(Formula::new(Node::Variable(0)))),
-            Rc::new(RefCell::new(Formula::new(Node::Variable(1)))),
-            Rc::new(RefCell::new(Formula::new(Node::Variable(2))))]
-        )));
-        let string=format!("0{}1{}2",OR_OPERATOR_SYMBOL,OR_OPERATOR_SYMBOL);
-        let formula=Formula::from_string(string).unwrap();
-        let gotten=format!("{:?}",formula);
-        assert_eq!(gotten,expected);
+    fn error_nodes_count_as_false(){
+        let input=format!("1{o}{n}$",o=OR_OPERATOR_SYMBOL,n=NEGATION_OPERATOR_SYMBOL);
+        let (formula,_)=BooleanFormula::from_string_recovering(input);
+        let copy=formula.clone();
+        assert_eq!(copy.to_string(),formula.to_string());
+        let mut assignment=TruthAssignment::new();
+        assignment.add_assignment(1,false);
+        assert!(formula.evaluate(&assignment));
+        assert!(!formula.get_nnf().has_error_nodes());
+    }
+}
+
+#[cfg(test)]
+mod test_ite{
+    use super::*;
+    use crate::sat::{Polarity, check_sat_cnf_and_find_assignment};
+
+    fn ite(c:&str,a:&str,b:&str)->String{
+        format!("({}{}{}{}{})",c,ITE_THEN_SYMBOL,a,ITE_ELSE_SYMBOL,b)
+    }
+
+    fn assignments(variables:&[i32])->Vec<TruthAssignment>{
+        (0..(1u32<<variables.len())).map(|bits|{
+            let mut assignment=TruthAssignment::new();
+            for (i,var) in variables.iter().enumerate(){
+                assignment.add_assignment(*var,bits&(1<<i)!=0);
+            }
+            assignment
+        }).collect()
     }
 
     #[test]
-    fn xor_operator(){
-        let expected=format!("{:?}",Formula::new(Node::Xor(
-            Rc::new(RefCell::new(Formula::new(Node::Variable(0)))),
-            Rc::new(RefCell::new(Formula::new(Node::Variable(1))))
-        )));
-        let string=format!("0{}1",XOR_OPERATOR_SYMBOL);
-        let formula=Formula::from_string(string).unwrap();
-        let gotten=format!("{:?}",formula);
-        assert_eq!(gotten,expected);
+    fn parse_and_print(){
+        let string=ite("1",&format!("{}2",NEGATION_OPERATOR_SYMBOL),&ite("3","4","5"));
+        let formula=BooleanFormula::from_string(string.clone()).unwrap();
+        assert_eq!(format!("({})",formula.to_string()),string);
+        assert_eq!(formula.to_conventional_string(),"1 ? !2 : 3 ? 4 : 5");
+        let conventional=BooleanFormula::from_conventional_str("1 ? !2 : 3 ? 4 : 5").unwrap();
+        assert_eq!(conventional.to_string(),formula.to_string());
+        // the then branch reaches up to its :, the condition binds tighter than ?
+        let nested=BooleanFormula::from_conventional_str("1 | 2 ? 3 ? 4 : 5 : 6").unwrap();
+        assert_eq!(nested.to_conventional_string(),"1 | 2 ? 3 ? 4 : 5 : 6");
+        assert_eq!(BooleanFormula::from_conventional_str("(1 ? 2 : 3) & 4").unwrap().to_conventional_string(),"(1 ? 2 : 3) & 4");
     }
 
     #[test]
-    fn impl_operator(){
-        let expected=format!("{:?}",Formula::new(Node::Implies(
-            Rc::new(RefCell::new(Formula::new(Node::Variable(0)))),
-            Rc::new(RefCell::new(Formula::new(Node::Variable(1))))
-        )));
-        let string=format!("0{}1",IMPL_OPERATOR_SYMBOL);
-        let formula=Formula::from_string(string).unwrap();
-        let gotten=format!("{:?}",formula);
-        assert_eq!(gotten,expected);
+    fn malformed_ite(){
+        assert!(BooleanFormula::from_string(format!("1{}2",ITE_THEN_SYMBOL)).is_err());
+        assert!(BooleanFormula::from_string(format!("1{}2{}3",ITE_THEN_SYMBOL,AND_OPERATOR_SYMBOL)).is_err());
+        assert!(BooleanFormula::from_string(format!("1{}2",ITE_ELSE_SYMBOL)).is_err());
+        let (_,errors)=BooleanFormula::from_conventional_string_recovering("a ? b".to_string());
+        assert_eq!(errors,vec![ParseError::UnexpectedEnd { expected: "\":\"".to_string(), span: Span::point(5) }]);
     }
 
     #[test]
-    fn left_impl_operator(){
-        let expected=format!("{:?}",Formula::new(Node::IsImpliedBy(
-            Rc::new(RefCell::new(Formula::new(Node::Variable(0)))),
-            Rc::new(RefCell::new(Formula::new(Node::Variable(1))))
-        )));
-        let string=format!("0{}1",LEFT_IMPL_OPERATOR_SYMBOL);
-        let formula=Formula::from_string(string).unwrap();
-        let gotten=format!("{:?}",formula);
-        assert_eq!(gotten,expected);
+    fn evaluation_and_normal_forms(){
+        let formula=BooleanFormula::from_string(ite(&format!("(1{}2)",XOR_OPERATOR_SYMBOL),"3",&format!("{}3",NEGATION_OPERATOR_SYMBOL))).unwrap();
+        let nnf=formula.get_nnf();
+        let anf=formula.get_anf().unwrap();
+        for assignment in assignments(&[1,2,3]){
+            let expected=if assignment.get_assignment_or_default(1)!=assignment.get_assignment_or_default(2){
+                assignment.get_assignment_or_default(3)
+            }else{
+                !assignment.get_assignment_or_default(3)
+            };
+            assert_eq!(formula.evaluate(&assignment),expected);
+            assert_eq!(nnf.evaluate(&assignment),expected);
+            assert_eq!(anf.evaluate(&assignment),expected);
+        }
+        assert!(formula.bdd_equivalent(&nnf));
+        assert!(!nnf.to_string().contains(ITE_THEN_SYMBOL));
     }
 
     #[test]
-    fn iff_operator(){
-        let expected=format!("{:?}",Formula::new(Node::Iff(
-            Rc::new(RefCell::new(Formula::new(Node::Variable(0)))),
-            Rc::new(RefCell::new(Formula::new(Node::Variable(1))))
-        )));
-        let string=format!("0{}1",IFF_OPERATOR_SYMBOL);
-        let formula=Formula::from_string(string).unwrap();
-        let gotten=format!("{:?}",formula);
-        assert_eq!(gotten,expected);
+    fn truth_values_are_simplified(){
+        let cases=[
+            (ite("T","1","2"),"1".to_string()),
+            (ite("F","1","2"),"2".to_string()),
+            (ite("1","T","F"),"1".to_string()),
+            (ite("1","F","T"),format!("{}1",NEGATION_OPERATOR_SYMBOL)),
+            (ite("1","T","2"),format!("1{}2",OR_OPERATOR_SYMBOL)),
+            (ite("1","2","F"),format!("1{}2",AND_OPERATOR_SYMBOL))];
+        for (input,expected) in cases{
+            assert_eq!(BooleanFormula::from_string(input).unwrap().get_nnf().to_string(),expected);
+        }
     }
 
     #[test]
-    fn nested_formula(){
-        let expected=format!("{:?}",Formula::new(Node::Iff(
-            Rc::new(RefCell::new(Formula::new(Node::And(
-                vec![
-                    Rc::new(RefCell::new(Formula::new(Node::Variable(0)))),
-                    Rc::new(RefCell::new(Formula::new(Node::Variable(1))))
-                ]
-            )))),
-            Rc::new(RefCell::new(Formula::new(Node::ForEach(
-                1,
-                Rc::new(RefCell::new(Formula::new(Node::Variable(1))))
-            ))))
-        )));
-        let string=format!("(0{}1){}{}1{}1",AND_OPERATOR_SYMBOL,IFF_OPERATOR_SYMBOL,UNIVERSAL_QUANTIFIER_SYMBOL,QUANTIFIER_SEPARATOR_SYMBOL);
-        let formula=Formula::from_string(string).unwrap();
-        let gotten=format!("{:?}",formula);
-        assert_eq!(gotten,expected);
+    fn quantifiers(){
+        let formula=BooleanFormula::from_string(format!("E1.{}",ite("1","2","3"))).unwrap();
+        let expected=BooleanFormula::from_string(format!("2{}3",OR_OPERATOR_SYMBOL)).unwrap();
+        assert!(formula.without_quantifiers().bdd_equivalent(&expected));
     }
 
     #[test]
-    fn nested_formula_with_too_many_parenthesis(){
-        let expected=format!("{:?}",Formula::new(Node::Iff(
-            Rc::new(RefCell::new(Formula::new(Node::And(
-                vec![
-                    Rc::new(RefCell::new(Formula::new(Node::Variable(0)))),
-                    Rc::new(RefCell::new(Formula::new(Node::Variable(1))))
-                ]
-            )))),
-            Rc::new(RefCell::new(Formula::new(Node::ForEach(
-                1,
-                Rc::new(RefCell::new(Formula::new(Node::Variable(1))))
-            ))))
-        )));
-        let string=format!("(((0{}(1))){}({}1{}1))",AND_OPERATOR_SYMBOL,IFF_OPERATOR_SYMBOL,UNIVERSAL_QUANTIFIER_SYMBOL,QUANTIFIER_SEPARATOR_SYMBOL);
-        let formula=Formula::from_string(string).unwrap();
-        let gotten=format!("{:?}",formula);
-        assert_eq!(gotten,expected);
+    fn direct_cnf_encoding(){
+        let negated=format!("{}{}",NEGATION_OPERATOR_SYMBOL,ite("1","2","3"));
+        for input in [ite("1","2","3"),negated]{
+            let formula=BooleanFormula::from_string(input).unwrap();
+            let cnf=formula.to_cnf_representation();
+            // four clauses defining the label of the node and the unit clause asserting it
+            assert_eq!(cnf.get_clauses().len(),5);
+            for assignment in assignments(&[1,2,3]){
+                let mut restricted=CNF::new();
+                for clause in cnf.get_clauses(){
+                    restricted.add_clause(clause.clone());
+                }
+                for var in 1..=3{
+                    let polarity=if assignment.get_assignment_or_default(var){Polarity::Positive}else{Polarity::Negative};
+                    let mut unit=Clause::new();
+                    unit.add_literal(Literal::new(polarity,var));
+                    restricted.add_clause(unit);
+                }
+                assert_eq!(check_sat_cnf_and_find_assignment(restricted).is_some(),formula.evaluate(&assignment));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_cardinality{
+    use super::*;
+    use crate::sat::{Polarity, check_sat_cnf_and_find_assignment};
+
+    fn call(keyword:&str,arguments:&[&str])->String{
+        format!("{}({})",keyword,arguments.join(&ARGUMENT_SEPARATOR_SYMBOL.to_string()))
+    }
+
+    // every assignment of the variables 1..=n, with the number of them that hold
+    fn assignments(n:i32)->Vec<(TruthAssignment,usize)>{
+        (0..(1u32<<n)).map(|bits|{
+            let mut assignment=TruthAssignment::new();
+            for var in 1..=n{
+                assignment.add_assignment(var,bits&(1<<(var-1))!=0);
+            }
+            (assignment,bits.count_ones() as usize)
+        }).collect()
+    }
+
+    // the labelled CNF has a model extending the assignment exactly when the formula holds
+    fn cnf_agrees(formula:&BooleanFormula,assignment:&TruthAssignment,n:i32)->bool{
+        let cnf=formula.to_cnf_representation();
+        let mut restricted=CNF::new();
+        for clause in cnf.get_clauses(){
+            restricted.add_clause(clause.clone());
+        }
+        for var in 1..=n{
+            let polarity=if assignment.get_assignment_or_default(var){Polarity::Positive}else{Polarity::Negative};
+            let mut unit=Clause::new();
+            unit.add_literal(Literal::new(polarity,var));
+            restricted.add_clause(unit);
+        }
+        check_sat_cnf_and_find_assignment(restricted).is_some()==formula.evaluate(assignment)
     }
 
     #[test]
-    fn nested_formula_with_blank_spaces(){
-        let expected=format!("{:?}",Formula::new(Node::Iff(
-            Rc::new(RefCell::new(Formula::new(Node::And(
-                vec![
-                    Rc::new(RefCell::new(Formula::new(Node::Variable(0)))),
-                    Rc::new(RefCell::new(Formula::new(Node::Variable(1))))
-                ]
-            )))),
-            Rc::new(RefCell::new(Formula::new(Node::ForEach(
-                1,
-                Rc::new(RefCell::new(Formula::new(Node::Variable(1))))
-            ))))
-        )));
-        let string=format!("    ( 0 {}  1) {} {} 1{} 1   ",AND_OPERATOR_SYMBOL,IFF_OPERATOR_SYMBOL,UNIVERSAL_QUANTIFIER_SYMBOL,QUANTIFIER_SEPARATOR_SYMBOL);
-        let formula=Formula::from_string(string).unwrap();
-        let gotten=format!("{:?}",formula);
-        assert_eq!(gotten,expected);
+    fn parse_and_print(){
+        let inner=format!("(3{}4)",AND_OPERATOR_SYMBOL);
+        let string=call(AT_MOST_KEYWORD,&["2","1",&format!("{}2",NEGATION_OPERATOR_SYMBOL),&inner]);
+        let formula=BooleanFormula::from_string(string.clone()).unwrap();
+        assert_eq!(formula.to_string(),string);
+        assert_eq!(formula.to_conventional_string(),"atmost(2, 1, !2, 3 & 4)");
+        let conventional=BooleanFormula::from_conventional_str("atmost(2, 1, !2, 3 & 4)").unwrap();
+        assert_eq!(conventional.to_string(),string);
+        let parity=BooleanFormula::from_conventional_str("parity(a, b ? c : d, exists x. x & a) | atleast(1, a)").unwrap();
+        assert_eq!(parity.to_conventional_string(),"parity(a, b ? c : d, exists x. x & a) | atleast(1, a)");
+        // without a bracket the keyword is an ordinary name
+        let name=BooleanFormula::from_string(format!("{}{}1",AT_MOST_KEYWORD,AND_OPERATOR_SYMBOL)).unwrap();
+        assert!(name.get_variable_id(AT_MOST_KEYWORD).is_some());
+    }
+
+    #[test]
+    fn malformed_calls(){
+        assert!(BooleanFormula::from_string(call(AT_MOST_KEYWORD,&["a","b"])).is_err());
+        assert!(BooleanFormula::from_string(format!("{}(1)",EXACTLY_KEYWORD)).is_err());
+        assert!(BooleanFormula::from_string(format!("{}(1{}2",PARITY_KEYWORD,ARGUMENT_SEPARATOR_SYMBOL)).is_err());
+        let input=format!("{}{}{}",call(AT_LEAST_KEYWORD,&["1","$"]),AND_OPERATOR_SYMBOL,"#");
+        let (_,errors)=BooleanFormula::from_string_recovering(input);
+        assert_eq!(errors.len(),2);
+        let (formula,errors)=BooleanFormula::from_conventional_string_recovering("atmost(2, a b) & #".to_string());
+        assert_eq!(errors.len(),2);
+        assert!(formula.has_error_nodes());
+    }
+
+    #[test]
+    fn semantics_and_encodings(){
+        let variables=["1","2","3","4"];
+        let cases:[(&str,Vec<&str>,fn(usize)->bool);6]=[
+            (AT_MOST_KEYWORD,vec!["1"],|c|c<=1),
+            (AT_MOST_KEYWORD,vec!["2"],|c|c<=2),
+            (AT_LEAST_KEYWORD,vec!["3"],|c|c>=3),
+            (EXACTLY_KEYWORD,vec!["2"],|c|c==2),
+            (EXACTLY_KEYWORD,vec!["0"],|c|c==0),
+            (PARITY_KEYWORD,vec![],|c|c%2==1)];
+        for (keyword,bound,expected) in cases.iter(){
+            let mut arguments=bound.clone();
+            arguments.extend(variables.iter());
+            let formula=BooleanFormula::from_string(call(keyword,&arguments)).unwrap();
+            let negated=formula.not();
+            let nnf=formula.get_nnf();
+            let anf=formula.get_anf().unwrap();
+            assert!(formula.bdd_equivalent(&nnf));
+            for (assignment,count) in assignments(4){
+                assert_eq!(formula.evaluate(&assignment),expected(count));
+                assert_eq!(nnf.evaluate(&assignment),expected(count));
+                assert_eq!(anf.evaluate(&assignment),expected(count));
+                assert!(cnf_agrees(&formula,&assignment,4));
+                assert!(cnf_agrees(&negated,&assignment,4));
+            }
+        }
+    }
+
+    #[test]
+    fn nested_arguments(){
+        let inner=[format!("(1{}2)",AND_OPERATOR_SYMBOL),format!("{}3",NEGATION_OPERATOR_SYMBOL),
+            format!("(4{}1)",XOR_OPERATOR_SYMBOL),call(PARITY_KEYWORD,&["2","3"])];
+        let arguments:Vec<&str>=["1"].into_iter().chain(inner.iter().map(|x|x.as_str())).collect();
+        for keyword in [AT_MOST_KEYWORD,AT_LEAST_KEYWORD,EXACTLY_KEYWORD]{
+            let formula=BooleanFormula::from_string(call(keyword,&arguments)).unwrap();
+            let nnf=formula.get_nnf();
+            for (assignment,_) in assignments(4){
+                assert_eq!(nnf.evaluate(&assignment),formula.evaluate(&assignment));
+                assert!(cnf_agrees(&formula,&assignment,4));
+                assert!(cnf_agrees(&formula.not(),&assignment,4));
+            }
+        }
+    }
+
+    #[test]
+    fn truth_values_are_simplified(){
+        let cases=[
+            (call(AT_LEAST_KEYWORD,&["2","T","1","2"]),call(AT_LEAST_KEYWORD,&["1","1","2"])),
+            (call(AT_MOST_KEYWORD,&["1","T","T","1"]),"F".to_string()),
+            (call(AT_MOST_KEYWORD,&["3","1","F","2"]),"T".to_string()),
+            (call(EXACTLY_KEYWORD,&["2","T","1","F","2"]),call(EXACTLY_KEYWORD,&["1","1","2"])),
+            (call(PARITY_KEYWORD,&["T","1"]),format!("{}1",NEGATION_OPERATOR_SYMBOL)),
+            (call(PARITY_KEYWORD,&["T","F","T"]),"F".to_string())];
+        for (input,expected) in cases{
+            let mut formula=BooleanFormula::from_string(input).unwrap();
+            formula.root.simplify_truth();
+            assert_eq!(formula.root.to_string(),expected);
+        }
+    }
+
+    #[test]
+    fn largest_bound(){
+        let bound=usize::MAX.to_string();
+        let cases:[(&str,fn(usize)->bool);3]=[
+            (AT_MOST_KEYWORD,|_|true),
+            (AT_LEAST_KEYWORD,|_|false),
+            (EXACTLY_KEYWORD,|_|false)];
+        for (keyword,expected) in cases.iter(){
+            let formula=BooleanFormula::from_string(call(keyword,&[&bound,"1","2"])).unwrap();
+            let anf=formula.get_anf().unwrap();
+            let mut manager=BddManager::new();
+            let bdd=formula.to_bdd(&mut manager);
+            assert_eq!(bdd,if expected(0){manager.one()}else{manager.zero()});
+            assert!(formula.bdd_equivalent(&formula.get_nnf()));
+            assert_eq!(crate::aiger::Aig::from_formula(&formula).check_output(0).is_some(),expected(0));
+            for (assignment,count) in assignments(2){
+                assert_eq!(formula.evaluate(&assignment),expected(count));
+                assert_eq!(anf.evaluate(&assignment),expected(count));
+                assert!(cnf_agrees(&formula,&assignment,2));
+                assert!(cnf_agrees(&formula.not(),&assignment,2));
+            }
+        }
+    }
+
+    #[test]
+    fn encoding_size(){
+        let variables:Vec<String>=(1..=32).map(|x|x.to_string()).collect();
+        let mut arguments=vec!["1"];
+        arguments.extend(variables.iter().map(|x|x.as_str()));
+        let formula=BooleanFormula::from_string(call(AT_MOST_KEYWORD,&arguments)).unwrap();
+        // a counter up to 2 costs a few clauses per input, expanding by hand needs one per pair
+        assert!(formula.to_cnf_representation().get_clauses().len()<32*31/2);
+    }
+}
+
+#[cfg(test)]
+mod test_builder{
+    use super::*;
+    use crate::sat::{equivalent, is_valid};
+
+    fn parse(s:&str)->BooleanFormula{
+        BooleanFormula::from_conventional_str(s).unwrap()
     }
 
     #[test]
-    fn multiple_negations(){
-        let expected=format!("{:?}",
-            Formula::new(Node::Not(
-                Rc::new(RefCell::new(Formula::new(Node::Not(
-                    Rc::new(RefCell::new(Formula::new(Node::Not(
-                        Rc::new(RefCell::new(Formula::new(Node::Variable(1))))
-                    )))),
-                ))))
-            )));
-        let string=format!("{}{}{}1",NEGATION_OPERATOR_SYMBOL,NEGATION_OPERATOR_SYMBOL,NEGATION_OPERATOR_SYMBOL);
-        let formula=Formula::from_string(string).unwrap();
-        let gotten=format!("{:?}",formula);
-        assert_eq!(gotten,expected);
+    fn operators_build_the_expected_formula(){
+        let (a,b,c)=(BooleanFormula::var(1),BooleanFormula::var(2),BooleanFormula::var(3));
+        let built=(a.clone()&b.clone()&!c.clone())|(a.clone()^c.clone());
+        assert!(equivalent(&built,&parse("(1 & 2 & !3) | (1 ^ 3)")).is_ok());
+        assert!(equivalent(&a.implies(&b),&parse("1 -> 2")).is_ok());
+        assert!(equivalent(&a.iff(&b),&parse("1 <-> 2")).is_ok());
     }
 
     #[test]
-    fn lone_fresh_variable(){
-        let expected=format!("{:?}",Formula::new(Node::Variable(-1)));
-        let string=format!("{}1",FRESH_VARIABLE_SYMBOL);
-        let formula=Formula::from_string(string).unwrap();
-        let gotten=format!("{:?}",formula);
-        assert_eq!(gotten,expected);
+    fn derived_operators(){
+        let (a,b,c)=(parse("a"),parse("b"),parse("c"));
+        assert_eq!(a.ite(&b,&c).to_conventional_string(),"a ? b : c");
+        assert_eq!(a.is_implied_by(&b).to_conventional_string(),"a <- b");
+        let formulas=[a.clone(),b.clone(),a.clone()&c.clone()];
+        assert_eq!(BooleanFormula::at_most(1,&formulas).to_conventional_string(),"atmost(1, a, b, a & c)");
+        assert!(equivalent(&BooleanFormula::at_least(2,&formulas),&parse("atleast(2, a, b, a & c)")).is_ok());
+        assert!(equivalent(&BooleanFormula::exactly(0,&formulas),&parse("!a & !b")).is_ok());
+        assert!(equivalent(&BooleanFormula::parity(&formulas),&parse("a ^ b ^ (a & c)")).is_ok());
+        assert_eq!(BooleanFormula::parity(&formulas).get_variables().len(),3);
     }
 
     #[test]
-    fn quantifier_without_separator(){
-        let string=format!("{}1(1{}2)",EXISTENTIAL_QUANTIFIER_SYMBOL,AND_OPERATOR_SYMBOL);
-        let error=Formula::from_string(string);
-        assert!(error.is_err());
+    fn chains_stay_flat(){
+        let built=BooleanFormula::var(1)&BooleanFormula::var(2)&BooleanFormula::var(3);
+        match &built.root.root{
+            Node::And(vec)=>assert_eq!(vec.len(),3),
+            _=>panic!("expected a conjunction")
+        }
     }
 
     #[test]
-    fn mixture_of_and_plus_or_operators(){
-        let string=format!("0{}1{}2{}3",OR_OPERATOR_SYMBOL,AND_OPERATOR_SYMBOL,AND_OPERATOR_SYMBOL);
-        let error=Formula::from_string(string);
-        assert!(error.is_err());
+    fn constants_and_quantifiers(){
+        let x=BooleanFormula::var(1);
+        assert!(is_valid(&(x.clone()|BooleanFormula::constant(true))).is_ok());
+        assert!(is_valid(&(x.clone()&BooleanFormula::constant(false))).is_err());
+        assert!(is_valid(&(x.clone()|!x.clone()).forall(1)).is_ok());
+        assert!(is_valid(&x.exists(1)).is_ok());
+        assert!(is_valid(&x.forall(1)).is_err());
     }
 
     #[test]
-    fn nested_quantifiers(){
-        let expected=format!("{:?}",Formula::new(Node::ForEach(
-            1,
-            Rc::new(RefCell::new(Formula::new(Node::Exists(
-                2,
-                Rc::new(RefCell::new(Formula::new(Node::Variable(1))))
-            ))))
-        )));
-        let string=format!("{}1{}{}2{}1",UNIVERSAL_QUANTIFIER_SYMBOL,QUANTIFIER_SEPARATOR_SYMBOL,EXISTENTIAL_QUANTIFIER_SYMBOL,QUANTIFIER_SEPARATOR_SYMBOL);
-        let formula=Formula::from_string(string).unwrap();
-        let gotten=format!("{:?}",formula);
-        assert_eq!(gotten,expected);
+    fn numeric_variables_do_not_take_names(){
+        let a=BooleanFormula::from_conventional_str("a").unwrap();
+        let one=BooleanFormula::var(1);
+        for built in [a.clone()&one.clone(),one.clone()&a.clone()]{
+            assert_eq!(built.get_variables().len(),2);
+            assert!(built.get_variables().contains(&1));
+            let named=built.get_variable_id("a").unwrap();
+            assert_ne!(named,1);
+            assert_eq!(built.get_variable_name(1),"1");
+            assert!(is_valid(&built.iff(&a)).is_err());
+        }
+        let twice=one.clone()&a.clone()&BooleanFormula::from_conventional_str("a | 1").unwrap();
+        assert_eq!(twice.get_variables().len(),2);
+    }
+
+    #[test]
+    fn named_variables_are_matched_by_name(){
+        let mut symbols=SymbolTable::new();
+        let a=BooleanFormula::from_conventional_string_with_symbols("a".to_string(),&mut symbols).unwrap();
+        let b=BooleanFormula::from_conventional_string_with_symbols("b".to_string(),&mut SymbolTable::new()).unwrap();
+        let built=a.clone()&!b.clone()&a.clone();
+        assert_eq!(built.get_variables().len(),2);
+        assert!(equivalent(&built,&parse("a & !b")).is_ok());
     }
 }
 
 #[cfg(test)]
-mod test_serialization{
+mod test_substitution{
     use super::*;
+    use crate::sat::equivalent;
 
-    #[test]
-    fn lone_variable(){
-        let expected="1".to_string();
-        let formula=Formula{
-            root: Node::Variable(1)
-        };
-        assert_eq!(expected,formula.to_string());
+    fn parse(s:&str,symbols:&mut SymbolTable)->BooleanFormula{
+        BooleanFormula::from_conventional_string_with_symbols(s.to_string(),symbols).unwrap()
     }
 
     #[test]
-    fn lone_fresh_variable(){
-        let expected=format!("{}1",FRESH_VARIABLE_SYMBOL);
-        let formula=Formula{
-            root: Node::Variable(-1)
-        };
-        assert_eq!(expected,formula.to_string());
+    fn substitute_one_variable(){
+        let mut symbols=SymbolTable::new();
+        let formula=parse("a & (b | a)",&mut symbols);
+        let replacement=parse("c -> b",&mut symbols);
+        let result=formula.substitute(symbols.get_id("a").unwrap(),&replacement);
+        assert!(equivalent(&result,&parse("(c -> b) & (b | (c -> b))",&mut symbols)).is_ok());
+        assert!(!result.get_variables().contains(&symbols.get_id("a").unwrap()));
     }
 
     #[test]
-    fn lone_negation(){
-        let expected=format!("{}1",NEGATION_OPERATOR_SYMBOL);
-        let formula=Formula{
-            root: Node::Not(
-                Rc::new(RefCell::new(Formula::new(Node::Variable(1))))
-            )
-        };
-        assert_eq!(expected,formula.to_string());
+    fn substitution_is_simultaneous(){
+        let mut symbols=SymbolTable::new();
+        let formula=parse("a & !b",&mut symbols);
+        let (a,b)=(symbols.get_id("a").unwrap(),symbols.get_id("b").unwrap());
+        let mut substitutions=HashMap::new();
+        substitutions.insert(a,parse("b",&mut symbols));
+        substitutions.insert(b,parse("a",&mut symbols));
+        assert!(equivalent(&formula.substitute_all(&substitutions),&parse("b & !a",&mut symbols)).is_ok());
+        let mut mapping=HashMap::new();
+        mapping.insert(a,b);
+        mapping.insert(b,a);
+        assert!(equivalent(&formula.rename(&mapping),&parse("b & !a",&mut symbols)).is_ok());
     }
 
     #[test]
-    fn lone_negation_of_fresh_variable(){
-        let expected=format!("{}{}1",NEGATION_OPERATOR_SYMBOL,FRESH_VARIABLE_SYMBOL);
-        let formula=Formula{
-            root: Node::Not(
-                Rc::new(RefCell::new(Formula::new(Node::Variable(-1))))
-            )
-        };
-        assert_eq!(expected,formula.to_string());
+    fn replacements_are_matched_by_name(){
+        let formula=BooleanFormula::from_conventional_str("x & y").unwrap();
+        let replacement=BooleanFormula::from_conventional_str("y | z").unwrap();
+        let x=formula.get_variable_id("x").unwrap();
+        let result=formula.substitute(x,&replacement);
+        let expected=BooleanFormula::from_conventional_str("(y | z) & y").unwrap();
+        assert!(equivalent(&result,&expected).is_ok());
+        assert_eq!(result.get_variables().len(),2);
     }
 
     #[test]
-    fn multiple_negations(){
-        let expected=format!("{}{}{}1",NEGATION_OPERATOR_SYMBOL,NEGATION_OPERATOR_SYMBOL,NEGATION_OPERATOR_SYMBOL);
-        let formula=Formula{
-            root: Node::Not(
-                Rc::new(RefCell::new(Formula::new(Node::Not(
-                    Rc::new(RefCell::new(Formula::new(Node::Not(
-                        Rc::new(RefCell::new(Formula::new(Node::Variable(1))))
-                    ))))
-                ))))
-            )
-        };
-        assert_eq!(expected,formula.to_string());
+    fn numeric_replacements_keep_their_ids(){
+        // a has id 1, which the replacement uses for a numeric variable
+        let formula=BooleanFormula::from_conventional_str("a & b").unwrap();
+        let b=formula.get_variable_id("b").unwrap();
+        let result=formula.substitute(b,&BooleanFormula::var(1));
+        assert_eq!(result.get_variables().len(),2);
+        assert!(result.get_variables().contains(&1));
+        assert_ne!(result.get_variable_id("a").unwrap(),1);
+        assert!(result.get_variable_id("b").is_some());
+        assert!(!result.get_variables().contains(&result.get_variable_id("b").unwrap()));
     }
 
     #[test]
-    fn and_operator(){
-        let expected=format!("1{}2{}1{}3",AND_OPERATOR_SYMBOL,AND_OPERATOR_SYMBOL,AND_OPERATOR_SYMBOL);
-        let formula=Formula{
-            root: Node::And(
-                vec![
-                    Rc::new(RefCell::new(Formula::new(Node::Variable(1)))),
-                    Rc::new(RefCell::new(Formula::new(Node::Variable(2)))),
-                    Rc::new(RefCell::new(Formula::new(Node::Variable(1)))),
-                    Rc::new(RefCell::new(Formula::new(Node::Variable(3)))) 
-                ]
-            )
-        };
-        assert_eq!(expected,formula.to_string());
+    fn quantifiers_do_not_capture(){
+        let mut symbols=SymbolTable::new();
+        // exists b. (a & !b) is just a, so replacing a by b must give b
+        let formula=parse("exists b. (a & !b)",&mut symbols);
+        let result=formula.substitute(symbols.get_id("a").unwrap(),&parse("b",&mut symbols));
+        assert!(equivalent(&result,&parse("b",&mut symbols)).is_ok());
+        // a bound variable is not replaced
+        let formula=parse("a & forall a. (a | !a)",&mut symbols);
+        let result=formula.substitute(symbols.get_id("a").unwrap(),&parse("F",&mut symbols));
+        assert!(equivalent(&result,&parse("F",&mut symbols)).is_ok());
+    }
+}
+
+#[cfg(test)]
+mod test_cofactor{
+    use super::*;
+    use crate::sat::equivalent;
+
+    fn parse(s:&str,symbols:&mut SymbolTable)->BooleanFormula{
+        BooleanFormula::from_conventional_string_with_symbols(s.to_string(),symbols).unwrap()
     }
 
     #[test]
-    fn or_operator(){
-        let expected=format!("1{}2{}1{}3",OR_OPERATOR_SYMBOL,OR_OPERATOR_SYMBOL,OR_OPERATOR_SYMBOL);
-        let formula=Formula{
-            root: Node::Or(
-                vec![
-                    Rc::new(RefCell::new(Formula::new(Node::Variable(1)))),
-                    Rc::new(RefCell::new(Formula::new(Node::Variable(2)))),
-                    Rc::new(RefCell::new(Formula::new(Node::Variable(1)))),
-                    Rc::new(RefCell::new(Formula::new(Node::Variable(3)))) 
-                ]
-            )
-        };
-        assert_eq!(expected,formula.to_string());
+    fn cofactors(){
+        let mut symbols=SymbolTable::new();
+        let formula=parse("(a & b) | (!a & c)",&mut symbols);
+        let a=symbols.get_id("a").unwrap();
+        assert!(equivalent(&formula.cofactor(a,true),&parse("b",&mut symbols)).is_ok());
+        assert!(equivalent(&formula.cofactor(a,false),&parse("c",&mut symbols)).is_ok());
+        assert!(!formula.cofactor(a,true).get_variables().contains(&a));
     }
 
     #[test]
-    fn xor_operator(){
-        let expected=format!("1{}2",XOR_OPERATOR_SYMBOL);
-        let formula=Formula{
-            root: Node::Xor(
-                Rc::new(RefCell::new(Formula::new(Node::Variable(1)))),
-                Rc::new(RefCell::new(Formula::new(Node::Variable(2))))
-            )
-        };
-        assert_eq!(expected,formula.to_string());
+    fn shannon_expansion_is_equivalent(){
+        let mut symbols=SymbolTable::new();
+        let formula=parse("(a ^ b) -> (c | a)",&mut symbols);
+        for variable in formula.get_variables().clone(){
+            assert!(equivalent(&formula.shannon_expand(variable),&formula).is_ok());
+        }
     }
 
     #[test]
-    fn impl_operator(){
-        let expected=format!("1{}2",IMPL_OPERATOR_SYMBOL);
-        let formula=Formula{
-            root: Node::Implies(
-                Rc::new(RefCell::new(Formula::new(Node::Variable(1)))),
-                Rc::new(RefCell::new(Formula::new(Node::Variable(2))))
-            )
-        };
-        assert_eq!(expected,formula.to_string());
+    fn dependence(){
+        let mut symbols=SymbolTable::new();
+        // b occurs but cancels out
+        let formula=parse("(a & b) | (a & !b) | c",&mut symbols);
+        let (a,b,c)=(symbols.get_id("a").unwrap(),symbols.get_id("b").unwrap(),symbols.get_id("c").unwrap());
+        assert!(formula.get_variables().contains(&b));
+        assert!(formula.depends_on(a));
+        assert!(!formula.depends_on(b));
+        assert!(formula.depends_on(c));
+        assert!(equivalent(&formula.boolean_difference(a),&parse("!c",&mut symbols)).is_ok());
+    }
+}
+
+#[cfg(test)]
+mod test_simplify{
+    use super::*;
+    use crate::sat::equivalent;
+
+    fn simplified(s:&str)->String{
+        let mut formula=BooleanFormula::from_conventional_str(s).unwrap();
+        formula.simplify();
+        formula.to_conventional_string()
     }
 
     #[test]
-    fn left_impl_operator(){
-        let expected=format!("1{}2",LEFT_IMPL_OPERATOR_SYMBOL);
-        let formula=Formula{
-            root: Node::IsImpliedBy(
-                Rc::new(RefCell::new(Formula::new(Node::Variable(1)))),
-                Rc::new(RefCell::new(Formula::new(Node::Variable(2))))
-            )
-        };
-        assert_eq!(expected,formula.to_string());
+    fn rewrites(){
+        let cases=[
+            ("a & a & b","a & b"),
+            ("a | (b | a)","a | b"),
+            ("!!a","a"),
+            ("a & !a & b","false"),
+            ("a | b | !a","true"),
+            ("a & (a | b)","a"),
+            ("a | (a & b) | c","a | c"),
+            ("(a | b) & (a | b | c) & d","(a | b) & d"),
+            ("a ^ a","false"),
+            ("a <-> !a","false"),
+            ("(a & b) <-> (a & b)","true"),
+            ("T ^ a","!a"),
+            ("F <-> a","!a"),
+            ("a ? b : b","b")];
+        for (input,expected) in cases{
+            assert_eq!(simplified(input),expected,"{}",input);
+        }
     }
 
     #[test]
-    fn iff_operator(){
-        let expected=format!("1{}2",IFF_OPERATOR_SYMBOL);
-        let formula=Formula{
-            root: Node::Iff(
-                Rc::new(RefCell::new(Formula::new(Node::Variable(1)))),
-                Rc::new(RefCell::new(Formula::new(Node::Variable(2))))
-            )
-        };
-        assert_eq!(expected,formula.to_string());
+    fn rewrites_reach_a_fixpoint(){
+        // the absorption only shows once the double negation and the duplicate are gone
+        assert_eq!(simplified("x & !!(x | y) & (z | z)"),"x & z");
     }
 
     #[test]
-    fn nested_formula(){
-        let expected=format!("(1{}2){}(1{}2)",IFF_OPERATOR_SYMBOL,AND_OPERATOR_SYMBOL,XOR_OPERATOR_SYMBOL);
-        let formula=Formula{
-            root: Node::And(
-                vec![Rc::new(RefCell::new(Formula::new(Node::Iff(
-                        Rc::new(RefCell::new(Formula::new(Node::Variable(1)))),
-                        Rc::new(RefCell::new(Formula::new(Node::Variable(2))))
-                    )))),
-                    Rc::new(RefCell::new(Formula::new(Node::Xor(
-                        Rc::new(RefCell::new(Formula::new(Node::Variable(1)))),
-                        Rc::new(RefCell::new(Formula::new(Node::Variable(2))))
-                    )))),
-                ]
-            )
-        };
-        assert_eq!(expected,formula.to_string()); 
+    fn subformulas_are_compared_by_structure(){
+        let pair=||BooleanFormula::var(1)|!BooleanFormula::var(2);
+        let mut formula=pair()&pair()&!pair();
+        formula.simplify();
+        assert!(formula.is_false());
+        // thousands of members, duplicates are found without printing them
+        let members:Vec<String>=(0..2000).map(|i|format!("(x{} | x{})",i,i+1)).collect();
+        let input=format!("{} & {}",members.join(" & "),members.join(" & "));
+        let mut formula=BooleanFormula::from_conventional_str(&input).unwrap();
+        formula.simplify();
+        assert_eq!(formula.to_conventional_string(),members.join(" & "));
     }
 
     #[test]
-    fn universal_quantifier_shallow(){
-        let expected=format!("{}1{}2",UNIVERSAL_QUANTIFIER_SYMBOL,QUANTIFIER_SEPARATOR_SYMBOL);
-        let formula=Formula{
-            root: Node::ForEach(
-                1,
-                Rc::new(RefCell::new(Formula::new(Node::Variable(2))))
-            )
-        };
-        assert_eq!(expected,formula.to_string());        
+    fn simplification_keeps_the_meaning(){
+        let inputs=["(a & !b) | (a & b) | (a & (a | c))","((a ^ b) <-> (b ^ a)) & c","!(a | !(b & a)) -> (c ? a : a)",
+            "atmost(1, a, a & b, !!c) | (d & !d)","exists a. (a & (a | b))"];
+        for input in inputs{
+            let original=BooleanFormula::from_conventional_str(input).unwrap();
+            let mut formula=original.clone();
+            let (before,after)=formula.simplify();
+            assert_eq!(before,original.size());
+            assert_eq!(after,formula.size());
+            assert!(after<=before,"{}",input);
+            assert!(equivalent(&original,&formula).is_ok(),"{}",input);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_minimize{
+    use super::*;
+    use crate::sat::equivalent;
+
+    fn parse(s:&str,symbols:&mut SymbolTable)->BooleanFormula{
+        BooleanFormula::from_conventional_string_with_symbols(s.to_string(),symbols).unwrap()
     }
 
     #[test]
-    fn existential_quantifier_shallow(){
-        let expected=format!("{}1{}2",EXISTENTIAL_QUANTIFIER_SYMBOL,QUANTIFIER_SEPARATOR_SYMBOL);
-        let formula=Formula{
-            root: Node::Exists(
-                1,
-                Rc::new(RefCell::new(Formula::new(Node::Variable(2))))
-            )
-        };
-        assert_eq!(expected,formula.to_string());        
+    fn finds_smaller_equivalent_formulas(){
+        let cases=[
+            ("(a & b) | (!a & b)","b"),
+            ("(a & b) | (!a & c) | (b & c)","a & b | !a & c"),
+            ("(a -> b) & (b -> a & (a | c))","(a -> b) & (b -> a)"),
+            ("(a | b) & !(!a & !b & c)","a | b")];
+        for (input,expected) in cases{
+            let mut symbols=SymbolTable::new();
+            let formula=parse(input,&mut symbols);
+            let minimized=formula.minimize(1000);
+            assert!(equivalent(&formula,&minimized).is_ok(),"{}",input);
+            assert_eq!(minimized.to_conventional_string(),expected,"{}",input);
+        }
     }
 
     #[test]
-    fn universal_quantifier_deep(){
-        let expected=format!("{}1{}(2{}3)",UNIVERSAL_QUANTIFIER_SYMBOL,QUANTIFIER_SEPARATOR_SYMBOL,IMPL_OPERATOR_SYMBOL);
-        let formula=Formula{
-            root: Node::ForEach(
-                1,
-                Rc::new(RefCell::new(Formula::new(Node::Implies(
-                    Rc::new(RefCell::new(Formula::new(Node::Variable(2)))),
-                    Rc::new(RefCell::new(Formula::new(Node::Variable(3)))) 
-                ))))
-            )
-        };
-        assert_eq!(expected,formula.to_string());        
+    fn care_set(){
+        let mut symbols=SymbolTable::new();
+        let formula=parse("(a & !b) | (!a & b)",&mut symbols);
+        // a and b are never true together, so a disjunction does as well
+        let care=parse("!(a & b)",&mut symbols);
+        let minimized=formula.minimize_with_care_set(&care,1000);
+        assert_eq!(minimized.to_conventional_string(),"a | b");
+        assert!(equivalent(&formula,&minimized).is_err());
     }
 
     #[test]
-    fn existential_quantifier_deep(){
-        let expected=format!("{}1{}(2{}3)",EXISTENTIAL_QUANTIFIER_SYMBOL,QUANTIFIER_SEPARATOR_SYMBOL,IMPL_OPERATOR_SYMBOL);
-        let formula=Formula{
-            root: Node::Exists(
-                1,
-                Rc::new(RefCell::new(Formula::new(Node::Implies(
-                    Rc::new(RefCell::new(Formula::new(Node::Variable(2)))),
-                    Rc::new(RefCell::new(Formula::new(Node::Variable(3)))) 
-                ))))
-            )
-        };
-        assert_eq!(expected,formula.to_string());          
+    fn budget(){
+        let mut symbols=SymbolTable::new();
+        let formula=parse("((a & b) | (!a & b)) & (c | c)",&mut symbols);
+        // no solver call, only the rewrites
+        assert_eq!(formula.minimize(0).to_conventional_string(),"(a & b | !a & b) & c");
+        assert_eq!(formula.minimize(1000).to_conventional_string(),"b & c");
     }
 
     #[test]
-    fn double_quantifier(){
-        let expected=format!("{}1{}{}2{}3",EXISTENTIAL_QUANTIFIER_SYMBOL,QUANTIFIER_SEPARATOR_SYMBOL,UNIVERSAL_QUANTIFIER_SYMBOL,QUANTIFIER_SEPARATOR_SYMBOL);
-        let formula=Formula{
-            root: Node::Exists(
-                1,
-                Rc::new(RefCell::new(Formula::new(Node::ForEach(
-                    2,
-                    Rc::new(RefCell::new(Formula::new(Node::Variable(3)))) 
-                ))))
-            )
-        };
-        assert_eq!(expected,formula.to_string());   
+    fn budget_bounds_the_candidates(){
+        let clauses:Vec<String>=(0..200).map(|i|format!("(x{} | !x{})",i,i+1)).collect();
+        let formula=parse(&clauses.join(" & "),&mut SymbolTable::new());
+        assert!(formula.variants(0).is_empty());
+        assert_eq!(formula.variants(25).len(),25);
+        // the 10 candidates use up the budget before any solver call
+        let minimized=formula.minimize(10);
+        assert_eq!(minimized.to_conventional_string(),formula.to_conventional_string());
     }
 }
 
+#[cfg(test)]
+mod test_cnf_representation{
+    use super::*;
+    use crate::sat::check_sat_cnf_and_find_assignment;
+
+    #[test]
+    fn constants(){
+        let formula=BooleanFormula::constant(true);
+        assert!(formula.is_cnf());
+        let cnf=formula.to_cnf_representation();
+        assert!(cnf.get_clauses().is_empty());
+        assert!(check_sat_cnf_and_find_assignment(cnf).is_some());
+        let formula=BooleanFormula::constant(false);
+        assert!(formula.is_cnf());
+        let cnf=formula.to_cnf_representation();
+        assert_eq!(cnf.get_clauses().len(),1);
+        assert!(cnf.get_clauses()[0].literals.is_empty());
+        assert!(check_sat_cnf_and_find_assignment(cnf).is_none());
+    }
+
+    #[test]
+    fn negated_constants_are_no_literals(){
+        for (value,satisfiable) in [(true,false),(false,true)]{
+            let formula=!BooleanFormula::constant(value);
+            assert!(!formula.is_cnf());
+            let cnf=formula.to_cnf_representation();
+            assert_eq!(check_sat_cnf_and_find_assignment(cnf).is_some(),satisfiable);
+        }
+        let formula=BooleanFormula::var(1)&!BooleanFormula::constant(false);
+        assert!(!formula.is_cnf());
+        assert!(check_sat_cnf_and_find_assignment(formula.to_cnf_representation()).is_some());
+    }
+
+    #[test]
+    fn negated_variables_are_literals(){
+        let formula=!BooleanFormula::var(1);
+        assert!(formula.is_cnf());
+        let cnf=formula.to_cnf_representation();
+        assert_eq!(cnf.get_clauses().len(),1);
+        assert_eq!(cnf.get_clauses()[0].literals,HashSet::from([Literal::new(crate::sat::Polarity::Negative,1)]));
+    }
+}
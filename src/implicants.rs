@@ -0,0 +1,223 @@
+use std::collections::{BTreeSet,HashSet};
+
+use crate::formula::{Term, conjoin_terms, is_subterm, remove_subsumed_terms};
+
+pub const DEFAULT_MAX_COVER_VARIABLES:usize=16;
+// above this many non essential primes the cover is chosen greedily
+const MAX_EXACT_COVER_CANDIDATES:usize=24;
+
+// iterated consensus: closing a DNF under consensus and absorption
+// yields exactly the set of its prime implicants
+pub fn prime_implicants(terms:Vec<Term>,max_terms:usize)->Result<Vec<Term>,String>{
+    let mut primes=remove_subsumed_terms(terms);
+    loop{
+        let mut new_terms:Vec<Term>=vec![];
+        for i in 0..primes.len(){
+            for j in (i+1)..primes.len(){
+                let resolvent=match consensus(&primes[i],&primes[j]){
+                    Some(term)=>term,
+                    None=>{continue;}
+                };
+                if !primes.iter().chain(new_terms.iter()).any(|p|is_subterm(p,&resolvent)){
+                    new_terms.push(resolvent);
+                }
+            }
+        }
+        if new_terms.is_empty(){
+            break;
+        }
+        primes.extend(new_terms);
+        primes=remove_subsumed_terms(primes);
+        if primes.len()>max_terms{
+            return Err(format!("Prime implicant computation aborted: found more than {} prime implicants",max_terms));
+        }
+    }
+    Ok(primes)
+}
+
+// (a*x)+(b*-x) |= (a*x)+(b*-x)+(a*b)
+fn consensus(a:&Term,b:&Term)->Option<Term>{
+    let clashing:Vec<i32>=a.iter()
+        .filter(|(variable,polarity)|b.get(variable)==Some(&!**polarity))
+        .map(|(variable,_)|*variable)
+        .collect();
+    if clashing.len()!=1{
+        return None;
+    }
+    let mut left=a.clone();
+    left.remove(&clashing[0]);
+    let mut right=b.clone();
+    right.remove(&clashing[0]);
+    conjoin_terms(&left,&right)
+}
+
+// selects a subset of the primes covering every minterm of their disjunction,
+// with as few terms (and then literals) as possible
+pub fn minimal_cover(primes:&[Term],max_variables:usize)->Result<Vec<Term>,String>{
+    if primes.iter().any(|prime|prime.is_empty()){
+        return Ok(vec![Term::new()]);
+    }
+    let variables:Vec<i32>=primes.iter()
+        .flat_map(|prime|prime.keys().copied())
+        .collect::<BTreeSet<i32>>()
+        .into_iter()
+        .collect();
+    if variables.len()>max_variables{
+        return Err(format!("Two-level minimization aborted: {} variables exceed the limit of {}",variables.len(),max_variables));
+    }
+    let coverage:Vec<HashSet<u32>>=primes.iter().map(|prime|minterms_of(prime,&variables)).collect();
+    let mut uncovered:HashSet<u32>=coverage.iter().flatten().copied().collect();
+    let mut chosen:Vec<usize>=vec![];
+    // essential primes are the only ones covering some minterm
+    for minterm in uncovered.iter(){
+        let covering:Vec<usize>=(0..primes.len()).filter(|i|coverage[*i].contains(minterm)).collect();
+        if covering.len()==1 && !chosen.contains(&covering[0]){
+            chosen.push(covering[0]);
+        }
+    }
+    for i in chosen.iter(){
+        for minterm in coverage[*i].iter(){
+            uncovered.remove(minterm);
+        }
+    }
+    let candidates:Vec<usize>=(0..primes.len())
+        .filter(|i|!chosen.contains(i) && coverage[*i].iter().any(|m|uncovered.contains(m)))
+        .collect();
+    if candidates.len()<=MAX_EXACT_COVER_CANDIDATES{
+        let mut best:Option<Vec<usize>>=None;
+        exact_cover(primes,&coverage,&candidates,&uncovered,&mut vec![],&mut best);
+        chosen.extend(best.unwrap_or_default());
+    }else{
+        chosen.extend(greedy_cover(primes,&coverage,&candidates,uncovered));
+    }
+    let mut cover:Vec<Term>=chosen.into_iter().map(|i|primes[i].clone()).collect();
+    cover.sort_by(|a,b|a.len().cmp(&b.len()).then(a.cmp(b)));
+    Ok(cover)
+}
+
+fn minterms_of(prime:&Term,variables:&[i32])->HashSet<u32>{
+    let mut mask:u32=0;
+    let mut value:u32=0;
+    for (i,variable) in variables.iter().enumerate(){
+        if let Some(polarity)=prime.get(variable){
+            mask|=1<<i;
+            if *polarity{
+                value|=1<<i;
+            }
+        }
+    }
+    (0..(1u32<<variables.len())).filter(|m|m&mask==value).collect()
+}
+
+fn cover_cost(primes:&[Term],selection:&[usize])->(usize,usize){
+    (selection.len(),selection.iter().map(|i|primes[*i].len()).sum())
+}
+
+// branch and bound on the uncovered minterm with the fewest covering candidates
+fn exact_cover(primes:&[Term],coverage:&[HashSet<u32>],candidates:&[usize],uncovered:&HashSet<u32>,
+        current:&mut Vec<usize>,best:&mut Option<Vec<usize>>){
+    if let Some(best_selection)=best{
+        if current.len()>best_selection.len() || (current.len()==best_selection.len() && !uncovered.is_empty()){
+            return;
+        }
+    }
+    let mut branching:Option<Vec<usize>>=None;
+    for minterm in uncovered.iter(){
+        let covering:Vec<usize>=candidates.iter().copied().filter(|i|coverage[*i].contains(minterm)).collect();
+        if branching.as_ref().is_none_or(|b|covering.len()<b.len()){
+            branching=Some(covering);
+        }
+    }
+    let branching=match branching{
+        Some(covering)=>covering,
+        None=>{
+            if best.as_ref().is_none_or(|b|cover_cost(primes,current)<cover_cost(primes,b)){
+                *best=Some(current.clone());
+            }
+            return;
+        }
+    };
+    for i in branching{
+        let remaining:HashSet<u32>=uncovered.difference(&coverage[i]).copied().collect();
+        current.push(i);
+        exact_cover(primes,coverage,candidates,&remaining,current,best);
+        current.pop();
+    }
+}
+
+fn greedy_cover(primes:&[Term],coverage:&[HashSet<u32>],candidates:&[usize],mut uncovered:HashSet<u32>)->Vec<usize>{
+    let mut chosen:Vec<usize>=vec![];
+    while !uncovered.is_empty(){
+        let next=candidates.iter().copied()
+            .filter(|i|!chosen.contains(i))
+            .max_by_key(|i|(coverage[*i].intersection(&uncovered).count(),std::cmp::Reverse(primes[*i].len())))
+            .unwrap();
+        for minterm in coverage[next].iter(){
+            uncovered.remove(minterm);
+        }
+        chosen.push(next);
+    }
+    chosen
+}
+
+#[cfg(test)]
+mod test_implicants{
+    use crate::formula::BooleanFormula;
+    use crate::operators::*;
+
+    fn strings(formulas:Vec<BooleanFormula>)->Vec<String>{
+        let mut res:Vec<String>=formulas.iter().map(|f|f.to_string()).collect();
+        res.sort();
+        res
+    }
+
+    #[test]
+    fn consensus_finds_hidden_prime(){
+        // (1+2)*(-1+3) has the prime 2+3 which is not among its terms
+        let string=format!("(1{}2){}({}1{}3)",AND_OPERATOR_SYMBOL,OR_OPERATOR_SYMBOL,NEGATION_OPERATOR_SYMBOL,AND_OPERATOR_SYMBOL);
+        let formula=BooleanFormula::from_string(string).unwrap();
+        let primes=strings(formula.prime_implicants().unwrap());
+        let expected=vec![
+            format!("-1{}3",AND_OPERATOR_SYMBOL),
+            format!("1{}2",AND_OPERATOR_SYMBOL),
+            format!("2{}3",AND_OPERATOR_SYMBOL)];
+        assert_eq!(primes,expected);
+    }
+
+    #[test]
+    fn minimal_cover_drops_consensus_term(){
+        // (1+2)*(-1+3)*(2+3) |= (1+2)*(-1+3)
+        let string=format!("(1{}2){}({}1{}3){}(2{}3)",AND_OPERATOR_SYMBOL,OR_OPERATOR_SYMBOL,NEGATION_OPERATOR_SYMBOL,
+            AND_OPERATOR_SYMBOL,OR_OPERATOR_SYMBOL,AND_OPERATOR_SYMBOL);
+        let formula=BooleanFormula::from_string(string).unwrap();
+        let minimal=formula.get_minimal_dnf().unwrap();
+        assert!(minimal.is_dnf());
+        assert_eq!(minimal.to_string(),format!("(-1{}3){}(1{}2)",AND_OPERATOR_SYMBOL,OR_OPERATOR_SYMBOL,AND_OPERATOR_SYMBOL));
+    }
+
+    #[test]
+    fn xor_has_no_smaller_cover(){
+        let string=format!("1{}2",XOR_OPERATOR_SYMBOL);
+        let formula=BooleanFormula::from_string(string).unwrap();
+        assert_eq!(formula.prime_implicants().unwrap().len(),2);
+        assert_eq!(formula.get_minimal_dnf().unwrap().get_variables().len(),2);
+    }
+
+    #[test]
+    fn prime_implicates_of_conjunction(){
+        let string=format!("1{}(2{}3)",AND_OPERATOR_SYMBOL,OR_OPERATOR_SYMBOL);
+        let formula=BooleanFormula::from_string(string).unwrap();
+        let implicates=strings(formula.prime_implicates().unwrap());
+        assert_eq!(implicates,vec!["1".to_string(),format!("2{}3",OR_OPERATOR_SYMBOL)]);
+    }
+
+    #[test]
+    fn constants(){
+        let tautology=BooleanFormula::from_string(format!("1{}{}1",OR_OPERATOR_SYMBOL,NEGATION_OPERATOR_SYMBOL)).unwrap();
+        assert!(tautology.get_minimal_dnf().unwrap().is_true());
+        assert!(tautology.prime_implicates().unwrap().is_empty());
+        let contradiction=BooleanFormula::from_string(format!("1{}{}1",AND_OPERATOR_SYMBOL,NEGATION_OPERATOR_SYMBOL)).unwrap();
+        assert!(contradiction.get_minimal_dnf().unwrap().is_false());
+        assert!(contradiction.prime_implicants().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,26 @@
+pub mod formula;
+pub mod sat;
+pub mod operators;
+pub mod symbols;
+pub mod parse_error;
+pub mod anf;
+pub mod bdd;
+pub mod dnnf;
+pub mod script;
+pub mod smtlib;
+pub mod aiger;
+pub mod truth_table;
+pub mod random;
+pub mod differential;
+pub mod cli;
+mod implicants;
+
+// the types and functions most users need, the modules hold the rest
+pub use formula::BooleanFormula;
+pub use sat::{CNF, Clause, Literal, Polarity, TruthAssignment};
+pub use sat::{check_sat_dpll, check_sat_dpll_and_find_assignment, check_sat_cnf_and_find_assignment,
+    check_validity_dpll, check_entailment_dpll, is_valid, entails, equivalent};
+pub use operators::{Syntax, SyntaxSymbols};
+pub use symbols::SymbolTable;
+pub use truth_table::TruthTable;
+pub use parse_error::{ParseError, Span};
@@ -1,11 +1,4 @@
 pub const AND_OPERATOR_SYMBOL:char='+';
-/*
- * WARNING
- *  
- * Always make these symbols different from each other.
- * Same symbols with different meanings might end up
- * in inconsistent behaviour by the parser
- */
 pub const OR_OPERATOR_SYMBOL:char='*';
 pub const XOR_OPERATOR_SYMBOL:char='%';
 pub const IFF_OPERATOR_SYMBOL:char='=';
@@ -17,4 +10,256 @@ pub const FRESH_VARIABLE_SYMBOL:char='f';
 pub const EXISTENTIAL_QUANTIFIER_SYMBOL:char='E';
 pub const UNIVERSAL_QUANTIFIER_SYMBOL:char='A';
 pub const NEGATION_OPERATOR_SYMBOL:char='-';
-pub const QUANTIFIER_SEPARATOR_SYMBOL:char='.';
\ No newline at end of file
+pub const QUANTIFIER_SEPARATOR_SYMBOL:char='.';
+// (c?a:b) reads as if c then a else b
+pub const ITE_THEN_SYMBOL:char='?';
+pub const ITE_ELSE_SYMBOL:char=':';
+// n-ary operators written as calls, e.g. atmost(2,a,b,c),
+// parity(a,b,c) holds when an odd number of its arguments hold
+pub const AT_MOST_KEYWORD:&str="atmost";
+pub const AT_LEAST_KEYWORD:&str="atleast";
+pub const EXACTLY_KEYWORD:&str="exactly";
+pub const PARITY_KEYWORD:&str="parity";
+pub const ARGUMENT_SEPARATOR_SYMBOL:char=',';
+// printed in place of the parts of the input that could not be parsed
+pub const ERROR_NODE_SYMBOL:char='@';
+// the symbols above are the default profile, any other set of symbols
+// can be used for reading and printing formulas through a Syntax
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+pub struct SyntaxSymbols{
+    pub and: char,
+    pub or: char,
+    pub xor: char,
+    pub iff: char,
+    pub implies: char,
+    pub is_implied_by: char,
+    pub negation: char,
+    pub true_atom: char,
+    pub false_atom: char,
+    pub fresh_variable: char,
+    pub exists: char,
+    pub for_all: char,
+    pub separator: char,
+    pub ite_then: char,
+    pub ite_else: char
+}
+
+impl Default for SyntaxSymbols{
+    fn default()->Self{
+        SyntaxSymbols {
+            and: AND_OPERATOR_SYMBOL,
+            or: OR_OPERATOR_SYMBOL,
+            xor: XOR_OPERATOR_SYMBOL,
+            iff: IFF_OPERATOR_SYMBOL,
+            implies: IMPL_OPERATOR_SYMBOL,
+            is_implied_by: LEFT_IMPL_OPERATOR_SYMBOL,
+            negation: NEGATION_OPERATOR_SYMBOL,
+            true_atom: TRUE_ATOM_SYMBOL,
+            false_atom: FALSE_ATOM_SYMBOL,
+            fresh_variable: FRESH_VARIABLE_SYMBOL,
+            exists: EXISTENTIAL_QUANTIFIER_SYMBOL,
+            for_all: UNIVERSAL_QUANTIFIER_SYMBOL,
+            separator: QUANTIFIER_SEPARATOR_SYMBOL,
+            ite_then: ITE_THEN_SYMBOL,
+            ite_else: ITE_ELSE_SYMBOL
+        }
+    }
+}
+
+// a set of symbols checked to be unambiguous for the parser
+#[derive(Debug,Clone,Default,PartialEq,Eq)]
+pub struct Syntax{
+    symbols: SyntaxSymbols
+}
+
+impl Syntax{
+    pub fn new(symbols:SyntaxSymbols)->Result<Self,String>{
+        // operators sit between atoms, the other symbols start an atom and are read as part of a name
+        let operators=[("and",symbols.and),("or",symbols.or),("xor",symbols.xor),("iff",symbols.iff),
+            ("implies",symbols.implies),("is implied by",symbols.is_implied_by),("negation",symbols.negation),
+            ("quantifier separator",symbols.separator),("if-then-else then",symbols.ite_then),("if-then-else else",symbols.ite_else)];
+        let letters=[("true atom",symbols.true_atom),("false atom",symbols.false_atom),
+            ("fresh variable prefix",symbols.fresh_variable),("existential quantifier",symbols.exists),
+            ("universal quantifier",symbols.for_all)];
+        for (name,symbol) in operators.iter(){
+            if symbol.is_alphanumeric() || symbol.is_whitespace() || ['_','(',')','[',']',ARGUMENT_SEPARATOR_SYMBOL,ERROR_NODE_SYMBOL].contains(symbol){
+                return Err(format!("Invalid syntax: the {} symbol \"{}\" can not be a letter, a digit, a blank space, \"_\", \"{}\", \"{}\" or a bracket",
+                    name,symbol,ARGUMENT_SEPARATOR_SYMBOL,ERROR_NODE_SYMBOL));
+            }
+        }
+        for (name,symbol) in letters.iter(){
+            if !symbol.is_ascii_alphabetic(){
+                return Err(format!("Invalid syntax: the {} symbol \"{}\" must be a letter",name,symbol));
+            }
+            // the call keywords stay plain words, with an existential a the input
+            // atmost.x would bind a variable tmost
+            let keyword=[AT_MOST_KEYWORD,AT_LEAST_KEYWORD,EXACTLY_KEYWORD,PARITY_KEYWORD].into_iter().find(|keyword|keyword.starts_with(*symbol));
+            if let Some(keyword)=keyword{
+                return Err(format!("Invalid syntax: the {} symbol \"{}\" can not start the keyword {}",name,symbol,keyword));
+            }
+        }
+        let all:Vec<(&str,char)>=operators.iter().chain(letters.iter()).copied().collect();
+        for i in 0..all.len(){
+            for j in (i+1)..all.len(){
+                if all[i].1==all[j].1{
+                    return Err(format!("Invalid syntax: the symbol \"{}\" is used for both the {} and the {}",all[i].1,all[i].0,all[j].0));
+                }
+            }
+        }
+        Ok(Syntax { symbols })
+    }
+
+    pub fn get_symbols(&self)->&SyntaxSymbols{
+        &self.symbols
+    }
+
+    // the symbols written between two atoms or before a quantified formula
+    pub fn is_operator(&self,symbol:char)->bool{
+        [self.and(),self.or(),self.xor(),self.iff(),self.implies(),self.is_implied_by(),self.negation(),self.separator(),
+            self.ite_then(),self.ite_else()].contains(&symbol)
+    }
+
+    pub fn is_binary_operator(&self,symbol:char)->bool{
+        [self.and(),self.or(),self.xor(),self.iff(),self.implies(),self.is_implied_by()].contains(&symbol)
+    }
+
+    // the binary operators and the two symbols of the if-then-else
+    pub fn is_infix_operator(&self,symbol:char)->bool{
+        self.is_binary_operator(symbol) || symbol==self.ite_then() || symbol==self.ite_else()
+    }
+
+    pub fn and(&self)->char{
+        self.symbols.and
+    }
+
+    pub fn or(&self)->char{
+        self.symbols.or
+    }
+
+    pub fn xor(&self)->char{
+        self.symbols.xor
+    }
+
+    pub fn iff(&self)->char{
+        self.symbols.iff
+    }
+
+    pub fn implies(&self)->char{
+        self.symbols.implies
+    }
+
+    pub fn is_implied_by(&self)->char{
+        self.symbols.is_implied_by
+    }
+
+    pub fn negation(&self)->char{
+        self.symbols.negation
+    }
+
+    pub fn true_atom(&self)->char{
+        self.symbols.true_atom
+    }
+
+    pub fn false_atom(&self)->char{
+        self.symbols.false_atom
+    }
+
+    pub fn fresh_variable(&self)->char{
+        self.symbols.fresh_variable
+    }
+
+    pub fn exists(&self)->char{
+        self.symbols.exists
+    }
+
+    pub fn for_all(&self)->char{
+        self.symbols.for_all
+    }
+
+    pub fn separator(&self)->char{
+        self.symbols.separator
+    }
+
+    pub fn ite_then(&self)->char{
+        self.symbols.ite_then
+    }
+
+    pub fn ite_else(&self)->char{
+        self.symbols.ite_else
+    }
+}
+
+#[cfg(test)]
+mod test_syntax{
+    use super::*;
+    use crate::formula::BooleanFormula;
+
+    fn c_like()->Syntax{
+        Syntax::new(SyntaxSymbols { and: '&', or: '|', negation: '!', xor: '^', ..SyntaxSymbols::default() }).unwrap()
+    }
+
+    #[test]
+    fn default_profile_is_valid(){
+        assert_eq!(Syntax::new(SyntaxSymbols::default()).unwrap(),Syntax::default());
+        assert_eq!(Syntax::default().and(),AND_OPERATOR_SYMBOL);
+    }
+
+    #[test]
+    fn collisions_are_rejected(){
+        assert!(Syntax::new(SyntaxSymbols { and: '*', ..SyntaxSymbols::default() }).is_err());
+        assert!(Syntax::new(SyntaxSymbols { for_all: 'T', ..SyntaxSymbols::default() }).is_err());
+        assert!(Syntax::new(SyntaxSymbols { separator: '-', ..SyntaxSymbols::default() }).is_err());
+        assert!(Syntax::new(SyntaxSymbols { ite_else: '?', ..SyntaxSymbols::default() }).is_err());
+    }
+
+    #[test]
+    fn reserved_symbols_and_keywords_are_rejected(){
+        assert!(Syntax::new(SyntaxSymbols { and: ERROR_NODE_SYMBOL, ..SyntaxSymbols::default() }).is_err());
+        assert!(Syntax::new(SyntaxSymbols { ite_then: ARGUMENT_SEPARATOR_SYMBOL, ..SyntaxSymbols::default() }).is_err());
+        assert!(Syntax::new(SyntaxSymbols { exists: 'a', ..SyntaxSymbols::default() }).is_err());
+        assert!(Syntax::new(SyntaxSymbols { for_all: 'p', ..SyntaxSymbols::default() }).is_err());
+        assert!(Syntax::new(SyntaxSymbols { true_atom: 'e', ..SyntaxSymbols::default() }).is_err());
+        assert!(Syntax::new(SyntaxSymbols { exists: 'x', for_all: 'y', ..SyntaxSymbols::default() }).is_ok());
+    }
+
+    #[test]
+    fn symbols_must_fit_their_role(){
+        assert!(Syntax::new(SyntaxSymbols { and: 'x', ..SyntaxSymbols::default() }).is_err());
+        assert!(Syntax::new(SyntaxSymbols { or: '(', ..SyntaxSymbols::default() }).is_err());
+        assert!(Syntax::new(SyntaxSymbols { negation: ' ', ..SyntaxSymbols::default() }).is_err());
+        assert!(Syntax::new(SyntaxSymbols { true_atom: '1', ..SyntaxSymbols::default() }).is_err());
+        assert!(Syntax::new(SyntaxSymbols { fresh_variable: '$', ..SyntaxSymbols::default() }).is_err());
+    }
+
+    #[test]
+    fn parse_and_print_with_custom_symbols(){
+        let syntax=c_like();
+        let string="(a&!b)|T|(c^1)".to_string();
+        let formula=BooleanFormula::from_string_with_syntax(string.clone(),&syntax).unwrap();
+        assert_eq!(formula.to_string_with_syntax(&syntax),string);
+        assert_eq!(formula.to_string(),format!("(a{}{}b){o}{}{o}(c{}1)",AND_OPERATOR_SYMBOL,NEGATION_OPERATOR_SYMBOL,
+            TRUE_ATOM_SYMBOL,XOR_OPERATOR_SYMBOL,o=OR_OPERATOR_SYMBOL));
+        // the default symbols mean nothing in the custom syntax
+        assert!(BooleanFormula::from_string_with_syntax(format!("a{}b",AND_OPERATOR_SYMBOL),&syntax).is_err());
+    }
+
+    #[test]
+    fn custom_letters(){
+        let syntax=Syntax::new(SyntaxSymbols { exists: 'X', for_all: 'Y', true_atom: 'V', fresh_variable: 'g', ..SyntaxSymbols::default() }).unwrap();
+        let string=format!("Xa.(a{}g2{}V{}E)",AND_OPERATOR_SYMBOL,AND_OPERATOR_SYMBOL,AND_OPERATOR_SYMBOL);
+        let formula=BooleanFormula::from_string_with_syntax(string.clone(),&syntax).unwrap();
+        // E is an ordinary name here, g2 a fresh variable
+        assert_eq!(formula.get_symbols().len(),2);
+        assert!(formula.get_variables().contains(&-2));
+        assert_eq!(formula.to_string_with_syntax(&syntax),string);
+    }
+
+    #[test]
+    fn cnf_printing(){
+        let syntax=c_like();
+        let formula=BooleanFormula::from_string_with_syntax("!1&2".to_string(),&syntax).unwrap();
+        let cnf=formula.to_cnf_representation();
+        let printed=cnf.to_string_with_syntax(&syntax);
+        assert!(printed=="(!1)&(2)" || printed=="(2)&(!1)");
+    }
+}
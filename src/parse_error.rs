@@ -0,0 +1,214 @@
+// byte offsets into the parsed input, end excluded
+#[derive(Debug,Clone,Copy,PartialEq,Eq,Hash)]
+pub struct Span{
+    pub start: usize,
+    pub end: usize
+}
+
+impl Span{
+    pub fn new(start:usize,end:usize)->Self{
+        Span { start, end }
+    }
+
+    pub fn point(offset:usize)->Self{
+        Span { start: offset, end: offset }
+    }
+}
+
+#[derive(Debug,Clone,PartialEq,Eq)]
+pub enum ParseError{
+    UnexpectedCharacter{ found: char, expected: String, span: Span },
+    UnexpectedEnd{ expected: String, span: Span },
+    // bracket is the missing one: "(" for a stray ")", ")" for an unclosed "("
+    MissingBracket{ bracket: char, span: Span },
+    EmptyVariable{ span: Span },
+    InvalidVariableName{ name: String, span: Span },
+    TrailingInput{ span: Span },
+    IntegerOverflow{ number: String, span: Span }
+}
+
+impl ParseError{
+    pub fn span(&self)->Span{
+        match self{
+            ParseError::UnexpectedCharacter{ span, .. }|
+            ParseError::UnexpectedEnd{ span, .. }|
+            ParseError::MissingBracket{ span, .. }|
+            ParseError::EmptyVariable{ span }|
+            ParseError::InvalidVariableName{ span, .. }|
+            ParseError::TrailingInput{ span }|
+            ParseError::IntegerOverflow{ span, .. }=>*span
+        }
+    }
+
+    pub fn message(&self)->String{
+        match self{
+            ParseError::UnexpectedCharacter{ found, .. }=>format!("unexpected character \"{}\"",found),
+            ParseError::UnexpectedEnd{ .. }=>"unexpected end of formula".to_string(),
+            ParseError::MissingBracket{ bracket: '(', .. }=>"missing opening bracket".to_string(),
+            ParseError::MissingBracket{ .. }=>"missing closing bracket".to_string(),
+            ParseError::EmptyVariable{ .. }=>"empty variable".to_string(),
+            ParseError::InvalidVariableName{ name, .. }=>format!("invalid variable name \"{}\"",name),
+            ParseError::TrailingInput{ .. }=>"unexpected input after the end of the formula".to_string(),
+            ParseError::IntegerOverflow{ number, .. }=>format!("variable number {} is too large",number)
+        }
+    }
+
+    // the explanation printed under the carets
+    pub fn label(&self)->String{
+        match self{
+            ParseError::UnexpectedCharacter{ expected, .. }|
+            ParseError::UnexpectedEnd{ expected, .. }=>format!("expected {}",expected),
+            ParseError::MissingBracket{ bracket: '(', .. }=>"this \")\" closes no \"(\"".to_string(),
+            ParseError::MissingBracket{ bracket, .. }=>format!("expected \"{}\"",bracket),
+            ParseError::EmptyVariable{ .. }=>"expected a variable".to_string(),
+            ParseError::InvalidVariableName{ .. }=>"expected a number, a fresh variable or a name".to_string(),
+            ParseError::TrailingInput{ .. }=>"expected an operator or the end of the formula".to_string(),
+            ParseError::IntegerOverflow{ .. }=>format!("variable numbers can be at most {}",i32::MAX)
+        }
+    }
+
+    // rustc style report, underlining the span in the line of the input containing it:
+    //
+    // error: unexpected character "$"
+    //  --> 1:3
+    //   |
+    // 1 | a+$
+    //   |   ^ expected a variable
+    pub fn render(&self,source:&str)->String{
+        render_report(source,self.span(),&self.message(),&self.label())
+    }
+
+    // the same error for an input that starts offset bytes into a larger text
+    pub fn shifted(self,offset:usize)->Self{
+        let shift=|span:Span|Span::new(span.start+offset,span.end+offset);
+        match self{
+            ParseError::UnexpectedCharacter{ found, expected, span }=>ParseError::UnexpectedCharacter { found, expected, span: shift(span) },
+            ParseError::UnexpectedEnd{ expected, span }=>ParseError::UnexpectedEnd { expected, span: shift(span) },
+            ParseError::MissingBracket{ bracket, span }=>ParseError::MissingBracket { bracket, span: shift(span) },
+            ParseError::EmptyVariable{ span }=>ParseError::EmptyVariable { span: shift(span) },
+            ParseError::InvalidVariableName{ name, span }=>ParseError::InvalidVariableName { name, span: shift(span) },
+            ParseError::TrailingInput{ span }=>ParseError::TrailingInput { span: shift(span) },
+            ParseError::IntegerOverflow{ number, span }=>ParseError::IntegerOverflow { number, span: shift(span) }
+        }
+    }
+}
+
+pub(crate) fn render_report(source:&str,span:Span,message:&str,label:&str)->String{
+    let start=span.start.min(source.len());
+    let end=span.end.clamp(start,source.len());
+    let line_start=source[..start].rfind('\n').map(|i|i+1).unwrap_or(0);
+    let line_end=source[start..].find('\n').map(|i|i+start).unwrap_or(source.len());
+    let line_number=source[..start].matches('\n').count()+1;
+    let column=source[line_start..start].chars().count()+1;
+    let width=source[start..end.min(line_end)].chars().count().max(1);
+    let gutter=" ".repeat(line_number.to_string().len());
+    let mut res=format!("error: {}\n",message);
+    res.push_str(&format!("{}--> {}:{}\n",gutter,line_number,column));
+    res.push_str(&format!("{} |\n",gutter));
+    res.push_str(&format!("{} | {}\n",line_number,&source[line_start..line_end]));
+    res.push_str(&format!("{} | {}{} {}",gutter," ".repeat(column-1),"^".repeat(width),label));
+    res
+}
+
+impl std::fmt::Display for ParseError{
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let span=self.span();
+        write!(f, "{}, {} (at {}..{})", self.message(), self.label(), span.start, span.end)
+    }
+}
+
+impl std::error::Error for ParseError{}
+
+#[cfg(test)]
+mod test_parse_error{
+    use super::*;
+    use crate::formula::BooleanFormula;
+    use crate::operators::*;
+
+    fn native_error(input:String)->ParseError{
+        BooleanFormula::from_string(input).unwrap_err()
+    }
+
+    fn conventional_error(input:&str)->ParseError{
+        BooleanFormula::from_conventional_str(input).unwrap_err()
+    }
+
+    #[test]
+    fn unexpected_character(){
+        let error=native_error(format!("1{}$",AND_OPERATOR_SYMBOL));
+        assert!(matches!(error,ParseError::UnexpectedCharacter{ found: '$', .. }));
+        assert_eq!(error.span(),Span::new(2,3));
+    }
+
+    #[test]
+    fn spans_count_bytes(){
+        let error=native_error(format!("1{}é",AND_OPERATOR_SYMBOL));
+        assert_eq!(error.span(),Span::new(2,4));
+    }
+
+    #[test]
+    fn unexpected_end(){
+        let error=native_error(format!("1{}",AND_OPERATOR_SYMBOL));
+        assert!(matches!(error,ParseError::UnexpectedEnd{ .. }));
+        assert_eq!(error.span(),Span::point(2));
+        assert_eq!(conventional_error("a &").span(),Span::point(3));
+    }
+
+    #[test]
+    fn missing_brackets(){
+        let unclosed=native_error(format!("(1{}2",AND_OPERATOR_SYMBOL));
+        assert_eq!(unclosed,ParseError::MissingBracket { bracket: ')', span: Span::point(4) });
+        let unopened=native_error(format!("1{}2)",AND_OPERATOR_SYMBOL));
+        assert_eq!(unopened,ParseError::MissingBracket { bracket: '(', span: Span::new(3,4) });
+        assert_eq!(conventional_error("(a | b"),ParseError::MissingBracket { bracket: ')', span: Span::point(6) });
+        assert_eq!(conventional_error("a | b)"),ParseError::MissingBracket { bracket: '(', span: Span::new(5,6) });
+    }
+
+    #[test]
+    fn empty_variable(){
+        let error=native_error(format!("{}{}1",EXISTENTIAL_QUANTIFIER_SYMBOL,QUANTIFIER_SEPARATOR_SYMBOL));
+        assert_eq!(error,ParseError::EmptyVariable { span: Span::point(1) });
+        assert_eq!(conventional_error("forall . a"),ParseError::EmptyVariable { span: Span::point(7) });
+    }
+
+    #[test]
+    fn invalid_variable_name(){
+        let error=native_error(format!("x[{}1",AND_OPERATOR_SYMBOL));
+        assert_eq!(error,ParseError::InvalidVariableName { name: "x[".to_string(), span: Span::new(0,2) });
+    }
+
+    #[test]
+    fn trailing_input(){
+        assert_eq!(conventional_error("a b c"),ParseError::TrailingInput { span: Span::new(2,5) });
+    }
+
+    #[test]
+    fn integer_overflow(){
+        let error=native_error(format!("99999999999{}1",AND_OPERATOR_SYMBOL));
+        assert_eq!(error,ParseError::IntegerOverflow { number: "99999999999".to_string(), span: Span::new(0,11) });
+        assert!(matches!(conventional_error("2147483648 | a"),ParseError::IntegerOverflow{ .. }));
+        assert!(BooleanFormula::from_conventional_str("2147483647 | 1").is_ok());
+    }
+
+    #[test]
+    fn render_underlines_the_span(){
+        let input="a & $";
+        let expected="error: unexpected character \"$\"
+ --> 1:5
+  |
+1 | a & $
+  |     ^ expected a variable, an operator or a bracket";
+        assert_eq!(conventional_error(input).render(input),expected);
+    }
+
+    #[test]
+    fn render_finds_the_line(){
+        let input="a &\n(b | xy[1)";
+        let expected="error: invalid variable name \"xy[1\"
+ --> 2:6
+  |
+2 | (b | xy[1)
+  |      ^^^^ expected a number, a fresh variable or a name";
+        assert_eq!(conventional_error(input).render(input),expected);
+    }
+}
@@ -0,0 +1,340 @@
+use crate::formula::BooleanFormula;
+use crate::sat::{CNF, Clause, Literal, Polarity, TruthAssignment};
+
+// splitmix64, small and good enough for test inputs, the same seed gives the same sequence
+#[derive(Debug,Clone)]
+pub struct Rng{
+    state: u64
+}
+
+impl Rng{
+    pub fn new(seed:u64)->Self{
+        Rng { state: seed }
+    }
+
+    pub fn next_u64(&mut self)->u64{
+        self.state=self.state.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z=self.state;
+        z=(z^(z>>30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z=(z^(z>>27)).wrapping_mul(0x94d049bb133111eb);
+        z^(z>>31)
+    }
+
+    // uniform in 0..n, n must not be 0
+    pub fn below(&mut self, n:usize)->usize{
+        assert!(n>0,"below(0) has no value to return");
+        (self.next_u64()%(n as u64)) as usize
+    }
+
+    pub fn coin(&mut self)->bool{
+        self.next_u64()&1==1
+    }
+
+    // k distinct values of 0..n in random order, k must not exceed n
+    pub fn sample(&mut self, n:usize, k:usize)->Vec<usize>{
+        assert!(k<=n,"cannot sample {} distinct values out of {}",k,n);
+        let mut values:Vec<usize>=(0..n).collect();
+        for i in 0..k{
+            let j=i+self.below(n-i);
+            values.swap(i,j);
+        }
+        values.truncate(k);
+        values
+    }
+}
+
+// the relative weights of the operators in random trees, 0 leaves one out
+#[derive(Debug,Clone,PartialEq,Eq)]
+pub struct OperatorMix{
+    pub not: usize,
+    pub and: usize,
+    pub or: usize,
+    pub xor: usize,
+    pub implies: usize,
+    pub iff: usize,
+    pub is_implied_by: usize,
+    pub ite: usize,
+    // atmost, atleast and exactly, equally likely
+    pub cardinality: usize,
+    pub parity: usize,
+    pub exists: usize,
+    pub forall: usize
+}
+
+impl Default for OperatorMix{
+    fn default()->Self{
+        OperatorMix { not: 1, and: 2, or: 2, xor: 1, implies: 1, iff: 1, is_implied_by: 1, ite: 1, cardinality: 1, parity: 1, exists: 1, forall: 1 }
+    }
+}
+
+impl OperatorMix{
+    // only the connectives, for generators whose output goes to tools without quantifiers
+    pub fn without_quantifiers()->Self{
+        OperatorMix { exists: 0, forall: 0, ..Self::default() }
+    }
+
+    fn total(&self)->usize{
+        self.not+self.and+self.or+self.xor+self.implies+self.iff+self.is_implied_by+self.ite+self.cardinality+self.parity+self.exists+self.forall
+    }
+}
+
+// a random tree over the variables 1..=variables, leaves are reached at the
+// latest at the given depth, constants show up now and then; there must be at
+// least one variable
+pub fn random_formula(rng:&mut Rng, variables:usize, depth:usize)->BooleanFormula{
+    random_formula_with_mix(rng,variables,depth,&OperatorMix::default())
+}
+
+pub fn random_formula_with_mix(rng:&mut Rng, variables:usize, depth:usize, mix:&OperatorMix)->BooleanFormula{
+    assert!(variables>0,"random formulas need at least one variable");
+    if depth==0 || mix.total()==0 || rng.below(4)==0{
+        if rng.below(10)==0{
+            return BooleanFormula::constant(rng.coin());
+        }
+        return BooleanFormula::var(rng.below(variables) as i32+1);
+    }
+    let child=|rng:&mut Rng|random_formula_with_mix(rng,variables,depth-1,mix);
+    // two or three arguments for the n-ary operators, bounds from 0 to one past them
+    let children=|rng:&mut Rng|{
+        let n=2+rng.below(2);
+        (0..n).map(|_|child(rng)).collect::<Vec<BooleanFormula>>()
+    };
+    let mut choice=rng.below(mix.total());
+    let operators=[(mix.not,0),(mix.and,1),(mix.or,2),(mix.xor,3),(mix.implies,4),(mix.iff,5),(mix.is_implied_by,6),
+        (mix.ite,7),(mix.cardinality,8),(mix.parity,9),(mix.exists,10),(mix.forall,11)];
+    for (weight,operator) in operators{
+        if choice>=weight{
+            choice-=weight;
+            continue;
+        }
+        return match operator{
+            0=>!child(rng),
+            1=>child(rng)&child(rng),
+            2=>child(rng)|child(rng),
+            3=>child(rng)^child(rng),
+            4=>child(rng).implies(&child(rng)),
+            5=>child(rng).iff(&child(rng)),
+            6=>child(rng).is_implied_by(&child(rng)),
+            7=>child(rng).ite(&child(rng),&child(rng)),
+            8=>{
+                let formulas=children(rng);
+                let k=rng.below(formulas.len()+2);
+                match rng.below(3){
+                    0=>BooleanFormula::at_most(k,&formulas),
+                    1=>BooleanFormula::at_least(k,&formulas),
+                    _=>BooleanFormula::exactly(k,&formulas)
+                }
+            },
+            9=>BooleanFormula::parity(&children(rng)),
+            10=>child(rng).exists(rng.below(variables) as i32+1),
+            _=>child(rng).forall(rng.below(variables) as i32+1)
+        };
+    }
+    unreachable!();
+}
+
+fn random_clause(rng:&mut Rng, k:usize, variables:usize)->Clause{
+    let mut clause=Clause::new();
+    for variable in rng.sample(variables,k){
+        let polarity=if rng.coin(){Polarity::Positive}else{Polarity::Negative};
+        clause.add_literal(Literal::new(polarity,variable as i32+1));
+    }
+    clause
+}
+
+fn clause_count(variables:usize, ratio:f64)->usize{
+    (ratio*variables as f64).round() as usize
+}
+
+// ratio*variables clauses of k distinct variables each, with 3-SAT the hard
+// instances are around a ratio of 4.26; k must not exceed variables
+pub fn random_k_sat(rng:&mut Rng, k:usize, variables:usize, ratio:f64)->CNF{
+    let mut cnf=CNF::new();
+    for _ in 0..clause_count(variables,ratio){
+        cnf.add_clause(random_clause(rng,k,variables));
+    }
+    cnf
+}
+
+// like random_k_sat, but the clauses falsified by a hidden assignment are
+// drawn again, so the instance is satisfiable and the assignment is a witness
+pub fn planted_k_sat(rng:&mut Rng, k:usize, variables:usize, ratio:f64)->(CNF,TruthAssignment){
+    let mut planted=TruthAssignment::new();
+    for variable in 1..=variables{
+        planted.add_assignment(variable as i32,rng.coin());
+    }
+    let mut cnf=CNF::new();
+    for _ in 0..clause_count(variables,ratio){
+        loop{
+            let clause=random_clause(rng,k,variables);
+            let satisfied=clause.literals.iter().any(|l|planted.get_assignment_or_default(l.variable)==(l.polarity==Polarity::Positive));
+            if satisfied{
+                cnf.add_clause(clause);
+                break;
+            }
+        }
+    }
+    (cnf,planted)
+}
+
+// holes+1 pigeons in the given holes, each pigeon somewhere and no two in one
+// hole, unsatisfiable and hard for resolution, variable i*holes+j+1 puts pigeon i in hole j
+pub fn pigeonhole(holes:usize)->CNF{
+    let variable=|pigeon:usize,hole:usize|(pigeon*holes+hole+1) as i32;
+    let mut cnf=CNF::new();
+    for pigeon in 0..=holes{
+        let mut clause=Clause::new();
+        for hole in 0..holes{
+            clause.add_literal(Literal::new(Polarity::Positive,variable(pigeon,hole)));
+        }
+        cnf.add_clause(clause);
+    }
+    for hole in 0..holes{
+        for first in 0..=holes{
+            for second in first+1..=holes{
+                let mut clause=Clause::new();
+                clause.add_literal(Literal::new(Polarity::Negative,variable(first,hole)));
+                clause.add_literal(Literal::new(Polarity::Negative,variable(second,hole)));
+                cnf.add_clause(clause);
+            }
+        }
+    }
+    cnf
+}
+
+// the same variables 1..=variables xored twice, in two random orders, through chains
+// of auxiliary variables, the two sums are asked to differ when it is unsatisfiable;
+// there must be at least one variable
+pub fn parity(rng:&mut Rng, variables:usize, satisfiable:bool)->CNF{
+    assert!(variables>0,"parity instances need at least one variable");
+    let mut cnf=CNF::new();
+    let mut next=variables as i32+1;
+    let mut sums:Vec<i32>=vec![];
+    for _ in 0..2{
+        let order=rng.sample(variables,variables);
+        let mut sum=order[0] as i32+1;
+        for x in order.iter().skip(1){
+            add_xor(&mut cnf,next,sum,*x as i32+1);
+            sum=next;
+            next+=1;
+        }
+        sums.push(sum);
+    }
+    // sums[0]==sums[1], or the opposite
+    for (a,b) in [(true,false),(false,true)]{
+        let mut clause=Clause::new();
+        clause.add_literal(Literal::new(if a==satisfiable{Polarity::Positive}else{Polarity::Negative},sums[0]));
+        clause.add_literal(Literal::new(if b{Polarity::Positive}else{Polarity::Negative},sums[1]));
+        cnf.add_clause(clause);
+    }
+    cnf
+}
+
+// the four clauses of x <-> (a ^ b)
+fn add_xor(cnf:&mut CNF, x:i32, a:i32, b:i32){
+    for (sx,sa,sb) in [(false,true,true),(false,false,false),(true,true,false),(true,false,true)]{
+        let mut clause=Clause::new();
+        for (sign,variable) in [(sx,x),(sa,a),(sb,b)]{
+            clause.add_literal(Literal::new(if sign{Polarity::Positive}else{Polarity::Negative},variable));
+        }
+        cnf.add_clause(clause);
+    }
+}
+
+#[cfg(test)]
+mod test_random{
+    use super::*;
+    use crate::sat::{check_sat_cnf_and_find_assignment, check_sat_dpll};
+
+    fn satisfies(cnf:&CNF, assignment:&TruthAssignment)->bool{
+        cnf.get_clauses().iter().all(|clause|clause.literals.iter().any(|l|{
+            assignment.get_assignment_or_default(l.variable)==(l.polarity==Polarity::Positive)
+        }))
+    }
+
+    #[test]
+    fn same_seed_same_instance(){
+        let first=random_k_sat(&mut Rng::new(3),3,20,4.26);
+        let second=random_k_sat(&mut Rng::new(3),3,20,4.26);
+        assert_eq!(first.to_dimacs(),second.to_dimacs());
+        assert_ne!(first.to_dimacs(),random_k_sat(&mut Rng::new(4),3,20,4.26).to_dimacs());
+    }
+
+    #[test]
+    fn k_sat_shape(){
+        let cnf=random_k_sat(&mut Rng::new(1),3,10,2.5);
+        assert_eq!(cnf.get_clauses().len(),25);
+        assert!(cnf.get_clauses().iter().all(|clause|clause.literals.len()==3));
+        assert!(cnf.get_variables().iter().all(|x|(1..=10).contains(x)));
+        let dimacs=cnf.to_dimacs();
+        assert!(dimacs.starts_with("p cnf 10 25\n"));
+        assert_eq!(dimacs.lines().count(),26);
+        assert!(dimacs.lines().skip(1).all(|line|line.ends_with(" 0")));
+    }
+
+    #[test]
+    fn planted_instances_are_satisfied_by_the_plant(){
+        for seed in 0..5{
+            let (cnf,planted)=planted_k_sat(&mut Rng::new(seed),3,15,6.0);
+            assert!(satisfies(&cnf,&planted));
+            assert!(check_sat_cnf_and_find_assignment(cnf).is_some());
+        }
+    }
+
+    #[test]
+    fn pigeonhole_is_unsatisfiable(){
+        let cnf=pigeonhole(3);
+        // 4 at-least-one clauses and 3 holes times 6 pairs
+        assert_eq!(cnf.get_clauses().len(),4+3*6);
+        assert!(check_sat_cnf_and_find_assignment(cnf).is_none());
+    }
+
+    #[test]
+    fn parity_instances(){
+        for seed in 0..5{
+            assert!(check_sat_cnf_and_find_assignment(parity(&mut Rng::new(seed),6,true)).is_some());
+            assert!(check_sat_cnf_and_find_assignment(parity(&mut Rng::new(seed),6,false)).is_none());
+        }
+    }
+
+    #[test]
+    #[should_panic(expected="at least one variable")]
+    fn formulas_need_a_variable(){
+        random_formula(&mut Rng::new(0),0,3);
+    }
+
+    #[test]
+    #[should_panic(expected="at least one variable")]
+    fn parity_needs_a_variable(){
+        parity(&mut Rng::new(0),0,true);
+    }
+
+    #[test]
+    fn cnf_as_formula(){
+        let cnf=random_k_sat(&mut Rng::new(9),3,8,3.0);
+        let formula=BooleanFormula::from_cnf(&cnf);
+        assert!(formula.is_cnf());
+        assert_eq!(formula.to_cnf_representation().to_dimacs(),cnf.to_dimacs());
+        assert_eq!(check_sat_dpll(&formula),check_sat_cnf_and_find_assignment(cnf).is_some());
+    }
+
+    #[test]
+    fn operator_mix(){
+        let mix=OperatorMix { not: 0, and: 0, or: 0, xor: 1, implies: 0, iff: 0, is_implied_by: 0, ite: 0, cardinality: 0, parity: 0, exists: 0, forall: 0 };
+        let mut rng=Rng::new(5);
+        for _ in 0..20{
+            let formula=random_formula_with_mix(&mut rng,4,4,&mix);
+            let text=formula.to_conventional_string();
+            assert!(!text.contains('&') && !text.contains('|') && !text.contains("exists"),"{}",text);
+        }
+        let mut rng=Rng::new(5);
+        let texts:Vec<String>=(0..50).map(|_|random_formula(&mut rng,4,5).to_conventional_string()).collect();
+        for operator in ["forall","<-","?","parity"]{
+            assert!(texts.iter().any(|text|text.contains(operator)),"{}",operator);
+        }
+        assert!(texts.iter().any(|text|text.contains("atmost") || text.contains("atleast") || text.contains("exactly")));
+        let mut rng=Rng::new(5);
+        let mix=OperatorMix::without_quantifiers();
+        assert!((0..50).all(|_|!random_formula_with_mix(&mut rng,4,5,&mix).to_conventional_string().contains("forall")));
+    }
+}
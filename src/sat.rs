@@ -1,9 +1,12 @@
-use crate::{formula::BooleanFormula, operators::{NEGATION_OPERATOR_SYMBOL, FRESH_VARIABLE_SYMBOL, OR_OPERATOR_SYMBOL, AND_OPERATOR_SYMBOL}};
+use crate::{formula::BooleanFormula, symbols::SymbolTable, operators::Syntax};
 use std::collections::{HashSet,HashMap};
+use std::rc::Rc;
 
 #[derive(Debug,Clone)]
 pub struct CNF{
-    clauses: Vec<Clause>
+    clauses: Vec<Clause>,
+    // shared, since the solver clones the formula at every step
+    symbols: Rc<SymbolTable>
 }
 
 #[derive(Debug,Clone)]
@@ -38,9 +41,36 @@ impl PartialEq for Literal{
     }
 }
 
+impl Default for CNF{
+    fn default()->Self{
+        Self::new()
+    }
+}
+
+impl Default for Clause{
+    fn default()->Self{
+        Self::new()
+    }
+}
+
+impl Default for TruthAssignment{
+    fn default()->Self{
+        Self::new()
+    }
+}
+
 impl CNF{
     pub fn new()->Self{
-        CNF { clauses: vec![] }
+        CNF { clauses: vec![], symbols: Rc::new(SymbolTable::new()) }
+    }
+
+    pub fn with_symbols(mut self,symbols: SymbolTable)->Self{
+        self.symbols=Rc::new(symbols);
+        self
+    }
+
+    pub fn get_symbols(&self)->&SymbolTable{
+        &self.symbols
     }
 
     pub fn from_boolean_formula(formula: &BooleanFormula)->Self{
@@ -51,6 +81,20 @@ impl CNF{
         self.clauses.push(clause);
     }
 
+    pub fn get_clauses(&self)->&Vec<Clause>{
+        &self.clauses
+    }
+
+    pub fn get_variables(&self)->HashSet<i32>{
+        let mut res:HashSet<i32>=HashSet::new();
+        for clause in self.clauses.iter(){
+            for literal in clause.literals.iter(){
+                res.insert(literal.variable);
+            }
+        }
+        res
+    }
+
     pub fn is_true(&self)->bool{
         self.clauses.len()==0
     }
@@ -198,17 +242,45 @@ impl CNF{
     }
 
     pub fn to_string(&self)->String{
+        self.to_string_with_syntax(&Syntax::default())
+    }
+
+    pub fn to_string_with_syntax(&self,syntax: &Syntax)->String{
         let mut res="".to_string();
         for clause in self.clauses.iter(){
             res.push_str("(");
-            let clause_str=clause.to_string();
+            let clause_str=clause.to_string_with_syntax(&self.symbols,syntax);
             res.push_str(&clause_str);
             res.push_str(")");
-            res.push(AND_OPERATOR_SYMBOL);
+            res.push(syntax.and());
         }
         res.pop();
         return res;
     }
+
+    // DIMACS wants positive numbers, so the fresh variables of a labelling,
+    // which are negative, are numbered after the highest original one
+    pub fn to_dimacs(&self)->String{
+        let variables=self.get_variables();
+        let highest=variables.iter().copied().filter(|x|*x>0).max().unwrap_or(0);
+        let mut fresh:Vec<i32>=variables.iter().copied().filter(|x|*x<=0).collect();
+        fresh.sort_by(|a,b|b.cmp(a));
+        let numbers:HashMap<i32,i32>=fresh.iter().enumerate().map(|(i,x)|(*x,highest+i as i32+1)).collect();
+        let number=|x:i32|*numbers.get(&x).unwrap_or(&x);
+        let mut res=format!("p cnf {} {}\n",highest+fresh.len() as i32,self.clauses.len());
+        for clause in self.clauses.iter(){
+            let mut literals:Vec<&Literal>=clause.literals.iter().collect();
+            literals.sort_by_key(|l|(number(l.variable),l.polarity==Polarity::Positive));
+            for l in literals{
+                match l.polarity{
+                    Polarity::Positive=>res.push_str(&format!("{} ",number(l.variable))),
+                    Polarity::Negative=>res.push_str(&format!("-{} ",number(l.variable)))
+                }
+            }
+            res.push_str("0\n");
+        }
+        res
+    }
 }
 
 impl Clause{
@@ -247,22 +319,22 @@ impl Clause{
     }
 
     pub fn to_string(&self)->String{
+        self.to_string_with_symbols(&SymbolTable::new())
+    }
+
+    pub fn to_string_with_symbols(&self,symbols: &SymbolTable)->String{
+        self.to_string_with_syntax(symbols,&Syntax::default())
+    }
+
+    pub fn to_string_with_syntax(&self,symbols: &SymbolTable,syntax: &Syntax)->String{
         let mut res="".to_string();
         for literal in self.literals.iter(){
             if literal.polarity==Polarity::Negative{
-                res.push(NEGATION_OPERATOR_SYMBOL)
+                res.push(syntax.negation())
             }
-            let x=literal.variable;
-            if x>=0{
-                let str_var=x.to_string();
-                res.push_str(&str_var);
-            }else{
-                let y=-x;
-                res.push(FRESH_VARIABLE_SYMBOL);
-                let str_var=y.to_string();
-                res.push_str(&str_var);
-            }
-            res.push(OR_OPERATOR_SYMBOL);
+            let str_var=symbols.variable_to_string_with_syntax(literal.variable,syntax);
+            res.push_str(&str_var);
+            res.push(syntax.or());
         }
         res.pop();
         return res;
@@ -380,6 +452,11 @@ pub fn check_sat_dpll_and_find_assignment(formula: &BooleanFormula)->Option<Trut
     }
 }
 
+// for clauses built directly, e.g. by the Tseitin encoding of a circuit
+pub fn check_sat_cnf_and_find_assignment(cnf: CNF)->Option<TruthAssignment>{
+    check_sat_dpll_and_find_assignment_recursive(TruthAssignment::new(),cnf).ok()
+}
+
 fn check_sat_dpll_and_find_assignment_recursive(mut assignment:TruthAssignment,formula: CNF)->Result<TruthAssignment,TruthAssignment>{
     if formula.is_true(){
         return Ok(assignment);
@@ -415,12 +492,139 @@ fn check_sat_dpll_and_find_assignment_recursive(mut assignment:TruthAssignment,f
 }
 
 
-fn check_validity_dpll(formula: &BooleanFormula)->bool{
+pub fn check_validity_dpll(formula: &BooleanFormula)->bool{
     let not_formula=formula.not();
     return !check_sat_dpll(&not_formula);
 }
 
-fn check_entailment_dpll(f1: &BooleanFormula, f2: &BooleanFormula)->bool{
+pub fn check_entailment_dpll(f1: &BooleanFormula, f2: &BooleanFormula)->bool{
     let entailment=f1.entail(f2);
     return check_validity_dpll(&entailment);
-}
\ No newline at end of file
+}
+// a counterexample is a model of the negation, restricted to the variables
+// of the checked formula so that no Tseitin label leaks out of it
+pub fn is_valid(formula: &BooleanFormula)->Result<(),TruthAssignment>{
+    match check_sat_dpll_and_find_assignment(&formula.not()){
+        None=>Ok(()),
+        Some(model)=>{
+            let mut counterexample=TruthAssignment::new();
+            for variable in formula.get_variables().iter(){
+                counterexample.add_assignment(*variable,model.get_assignment_or_default(*variable));
+            }
+            Err(counterexample)
+        }
+    }
+}
+
+// the two formulas are matched by variable name, the counterexample is given
+// twice, over the ids of f1 and over the ids of f2, since both may use one id
+// for different variables
+pub fn entails(f1: &BooleanFormula, f2: &BooleanFormula)->Result<(),(TruthAssignment,TruthAssignment)>{
+    let (formula,first,second)=f1.entail_with_renamings(f2);
+    is_valid(&formula).map_err(|counterexample|split_counterexample(&counterexample,&first,&second))
+}
+
+pub fn equivalent(f1: &BooleanFormula, f2: &BooleanFormula)->Result<(),(TruthAssignment,TruthAssignment)>{
+    let (formula,first,second)=f1.iff_with_renamings(f2);
+    is_valid(&formula).map_err(|counterexample|split_counterexample(&counterexample,&first,&second))
+}
+
+// the renamings map the ids of each input to the ids of the combined formula
+fn split_counterexample(counterexample:&TruthAssignment, first:&HashMap<i32,i32>, second:&HashMap<i32,i32>)->(TruthAssignment,TruthAssignment){
+    let restrict=|renaming:&HashMap<i32,i32>|{
+        let mut res=TruthAssignment::new();
+        for (own,merged) in renaming.iter(){
+            res.add_assignment(*own,counterexample.get_assignment_or_default(*merged));
+        }
+        res
+    };
+    (restrict(first),restrict(second))
+}
+
+#[cfg(test)]
+mod test_validity{
+    use super::*;
+
+    fn parse(s:&str,symbols:&mut SymbolTable)->BooleanFormula{
+        BooleanFormula::from_conventional_string_with_symbols(s.to_string(),symbols).unwrap()
+    }
+
+    #[test]
+    fn valid_formulas_have_no_counterexample(){
+        let mut symbols=SymbolTable::new();
+        assert!(is_valid(&parse("a | !a",&mut symbols)).is_ok());
+        assert!(is_valid(&parse("(a -> b) & a -> b",&mut symbols)).is_ok());
+    }
+
+    #[test]
+    fn counterexample_falsifies_the_formula(){
+        let mut symbols=SymbolTable::new();
+        let formula=parse("(a -> b) -> (b -> a)",&mut symbols);
+        let counterexample=is_valid(&formula).unwrap_err();
+        assert!(!formula.evaluate(&counterexample));
+        let a=symbols.get_id("a").unwrap();
+        let b=symbols.get_id("b").unwrap();
+        assert_eq!(counterexample.get_assignment(a),Some(false));
+        assert_eq!(counterexample.get_assignment(b),Some(true));
+    }
+
+    #[test]
+    fn counterexample_has_only_original_variables(){
+        let mut symbols=SymbolTable::new();
+        let formula=parse("(a & b) | (c & d) | (a ^ c)",&mut symbols);
+        let counterexample=is_valid(&formula).unwrap_err();
+        assert!(!formula.evaluate(&counterexample));
+        for variable in counterexample.assignment.keys(){
+            assert!(formula.get_variables().contains(variable));
+        }
+        assert_eq!(counterexample.assignment.len(),formula.get_variables().len());
+    }
+
+    #[test]
+    fn entailment(){
+        let mut symbols=SymbolTable::new();
+        let f1=parse("a & b",&mut symbols);
+        let f2=parse("a | c",&mut symbols);
+        assert!(entails(&f1,&f2).is_ok());
+        let (first,second)=entails(&f2,&f1).unwrap_err();
+        assert!(f2.evaluate(&first));
+        assert!(!f1.evaluate(&second));
+    }
+
+    #[test]
+    fn equivalence(){
+        let mut symbols=SymbolTable::new();
+        let f1=parse("!(a & b)",&mut symbols);
+        let f2=parse("!a | !b",&mut symbols);
+        let f3=parse("!a & !b",&mut symbols);
+        assert!(equivalent(&f1,&f2).is_ok());
+        let (first,second)=equivalent(&f1,&f3).unwrap_err();
+        assert_ne!(f1.evaluate(&first),f3.evaluate(&second));
+    }
+
+    #[test]
+    fn named_against_numeric(){
+        // a has id 1 but is not the numeric variable 1
+        let a=BooleanFormula::from_conventional_str("a").unwrap();
+        let one=BooleanFormula::var(1);
+        let (first,second)=equivalent(&a,&one).unwrap_err();
+        assert_ne!(a.evaluate(&first),one.evaluate(&second));
+        assert!(entails(&a,&one).is_err());
+        assert!(entails(&(a.clone()&one.clone()),&one).is_ok());
+        let (first,second)=entails(&(a.clone()|one.clone()),&a).unwrap_err();
+        assert_eq!(first.get_assignment(1),second.get_assignment(1).map(|x|!x));
+    }
+
+    #[test]
+    fn separately_parsed_formulas(){
+        // b is 1 in f1 and 2 in f2, the counterexample follows each numbering
+        let f1=BooleanFormula::from_conventional_str("b").unwrap();
+        let f2=BooleanFormula::from_conventional_str("a & b").unwrap();
+        let (first,second)=entails(&f1,&f2).unwrap_err();
+        assert!(f1.evaluate(&first));
+        assert!(!f2.evaluate(&second));
+        assert_eq!(first.get_assignment(1),Some(true));
+        assert_eq!(second.get_assignment(2),Some(true));
+        assert_eq!(second.get_assignment(1),Some(false));
+    }
+}
@@ -0,0 +1,367 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use crate::formula::BooleanFormula;
+use crate::sat::{check_sat_dpll_and_find_assignment, TruthAssignment};
+use crate::symbols::{SymbolTable, is_valid_name};
+use crate::operators::Syntax;
+use crate::parse_error::{ParseError, Span, render_report};
+
+pub const COMMENT_SYMBOL:char='#';
+
+// a problem file holds one command per line, formulas use the native syntax:
+//
+// # comments run to the end of the line
+// let safe = -(door_open+-locked)
+// assert safe*alarm
+// check-sat
+// get-model
+//
+// defined names stand for their formula in every later line
+#[derive(Debug,Clone)]
+pub enum Command{
+    Let(String,BooleanFormula),
+    Assert(BooleanFormula),
+    CheckSat,
+    GetModel
+}
+
+#[derive(Debug,Clone,PartialEq,Eq)]
+pub enum Response{
+    Sat,
+    Unsat,
+    // the value of each variable of the problem, ordered by id
+    Model(Vec<(String,bool)>),
+    NoModel
+}
+
+impl std::fmt::Display for Response{
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self{
+            Response::Sat=>write!(f, "sat"),
+            Response::Unsat=>write!(f, "unsat"),
+            Response::Model(values)=>{
+                let lines:Vec<String>=values.iter().map(|(name,value)|format!("{}: {}",name,value)).collect();
+                write!(f, "{}", lines.join("\n"))
+            },
+            Response::NoModel=>write!(f, "no model, the last check-sat did not answer sat")
+        }
+    }
+}
+
+// spans are byte offsets into the whole file
+#[derive(Debug,Clone,PartialEq,Eq)]
+pub enum ScriptError{
+    Formula(ParseError),
+    UnknownCommand{ command: String, span: Span },
+    MissingDefinition{ span: Span },
+    InvalidName{ name: String, span: Span },
+    // defined twice, or used as a variable before its definition
+    NameInUse{ name: String, span: Span },
+    UnexpectedArgument{ span: Span }
+}
+
+impl ScriptError{
+    pub fn span(&self)->Span{
+        match self{
+            ScriptError::Formula(e)=>e.span(),
+            ScriptError::UnknownCommand{ span, .. }|
+            ScriptError::MissingDefinition{ span }|
+            ScriptError::InvalidName{ span, .. }|
+            ScriptError::NameInUse{ span, .. }|
+            ScriptError::UnexpectedArgument{ span }=>*span
+        }
+    }
+
+    pub fn message(&self)->String{
+        match self{
+            ScriptError::Formula(e)=>e.message(),
+            ScriptError::UnknownCommand{ command, .. }=>format!("unknown command \"{}\"",command),
+            ScriptError::MissingDefinition{ .. }=>"missing \"=\" in definition".to_string(),
+            ScriptError::InvalidName{ name, .. }=>format!("invalid name \"{}\"",name),
+            ScriptError::NameInUse{ name, .. }=>format!("\"{}\" is already in use",name),
+            ScriptError::UnexpectedArgument{ .. }=>"unexpected argument".to_string()
+        }
+    }
+
+    pub fn label(&self)->String{
+        match self{
+            ScriptError::Formula(e)=>e.label(),
+            ScriptError::UnknownCommand{ .. }=>"expected let, assert, check-sat or get-model".to_string(),
+            ScriptError::MissingDefinition{ .. }=>"expected let name = formula".to_string(),
+            ScriptError::InvalidName{ .. }=>"expected a name such as door_open or x[3]".to_string(),
+            ScriptError::NameInUse{ .. }=>"names must be defined once, before they are used".to_string(),
+            ScriptError::UnexpectedArgument{ .. }=>"this command takes no argument".to_string()
+        }
+    }
+
+    pub fn render(&self,source:&str)->String{
+        render_report(source,self.span(),&self.message(),&self.label())
+    }
+}
+
+impl std::fmt::Display for ScriptError{
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let span=self.span();
+        write!(f, "{}, {} (at {}..{})", self.message(), self.label(), span.start, span.end)
+    }
+}
+
+impl std::error::Error for ScriptError{}
+
+#[derive(Debug,Clone,Default)]
+pub struct Script{
+    commands: Vec<Command>,
+    symbols: SymbolTable,
+    // the highest id met so far, defined names get ids above it
+    highest_id: i32
+}
+
+impl FromStr for Script{
+    type Err=ScriptError;
+
+    fn from_str(source:&str)->Result<Self,ScriptError>{
+        Self::from_str_with_syntax(source,&Syntax::default())
+    }
+}
+
+impl Script{
+    pub fn from_str_with_syntax(source:&str, syntax:&Syntax)->Result<Self,ScriptError>{
+        let mut script=Script::default();
+        let mut definitions:HashMap<i32,BooleanFormula>=HashMap::new();
+        let mut line_start=0;
+        for line in source.split('\n'){
+            let offset=line_start;
+            line_start+=line.len()+1;
+            let code=match line.find(COMMENT_SYMBOL){
+                Some(position)=>&line[..position],
+                None=>line
+            };
+            let trimmed=code.trim_start();
+            if trimmed.trim_end().is_empty(){
+                continue;
+            }
+            let start=offset+code.len()-trimmed.len();
+            let keyword_length=trimmed.find(char::is_whitespace).unwrap_or(trimmed.len());
+            let keyword=&trimmed[..keyword_length];
+            let rest=&trimmed[keyword_length..];
+            let rest_start=start+keyword_length;
+            let command=match keyword{
+                "let"=>{
+                    let equals=match rest.find('='){
+                        Some(position)=>position,
+                        None=>{
+                            return Err(ScriptError::MissingDefinition { span: Span::new(start,start+trimmed.trim_end().len()) });
+                        }
+                    };
+                    let name=rest[..equals].trim();
+                    let name_start=rest_start+rest[..equals].find(|c:char|!c.is_whitespace()).unwrap_or(0);
+                    let name_span=Span::new(name_start,name_start+name.len());
+                    if !is_valid_name(name,syntax){
+                        return Err(ScriptError::InvalidName { name: name.to_string(), span: name_span });
+                    }
+                    if script.symbols.get_id(name).is_some(){
+                        return Err(ScriptError::NameInUse { name: name.to_string(), span: name_span });
+                    }
+                    let formula=match script.read_formula(&rest[equals+1..],rest_start+equals+1,syntax,&mut definitions){
+                        Ok(formula)=>formula,
+                        Err(e)=>{return Err(e);}
+                    };
+                    // the name gets an id of its own, so that later formulas can refer to it
+                    let id=script.highest_id.max(script.symbols.max_id())+1;
+                    script.highest_id=id;
+                    script.symbols.insert(name,id);
+                    definitions.insert(id,formula.clone());
+                    Command::Let(name.to_string(),formula)
+                },
+                "assert"=>{
+                    match script.read_formula(rest,rest_start,syntax,&mut definitions){
+                        Ok(formula)=>Command::Assert(formula),
+                        Err(e)=>{return Err(e);}
+                    }
+                },
+                "check-sat"|"get-model"=>{
+                    if !rest.trim().is_empty(){
+                        let argument_start=rest_start+rest.len()-rest.trim_start().len();
+                        return Err(ScriptError::UnexpectedArgument { span: Span::new(argument_start,rest_start+rest.trim_end().len()) });
+                    }
+                    if keyword=="check-sat"{
+                        Command::CheckSat
+                    }else{
+                        Command::GetModel
+                    }
+                },
+                _=>{
+                    return Err(ScriptError::UnknownCommand { command: keyword.to_string(), span: Span::new(start,start+keyword_length) });
+                }
+            };
+            script.commands.push(command);
+        }
+        Ok(script)
+    }
+
+    fn read_formula(&mut self, text:&str, offset:usize, syntax:&Syntax, definitions:&mut HashMap<i32,BooleanFormula>)->Result<BooleanFormula,ScriptError>{
+        let before=self.symbols.clone();
+        match BooleanFormula::from_string_with_symbols_and_syntax(text.to_string(),&mut self.symbols,syntax){
+            Ok(formula)=>{
+                self.highest_id=formula.get_variables().iter().copied().fold(self.highest_id,i32::max);
+                // a number that was the id of a name, a defined one included, moved that name
+                let moved:HashMap<i32,i32>=before.iter().filter_map(|(name,id)|match self.symbols.get_id(name){
+                    Some(new) if new!=*id=>Some((*id,new)),
+                    _=>None
+                }).collect();
+                if !moved.is_empty(){
+                    self.renumber(&moved,definitions);
+                }
+                Ok(formula.expand_definitions(definitions))
+            },
+            Err(e)=>Err(ScriptError::Formula(e.shifted(offset)))
+        }
+    }
+
+    // carries a move of names over to what was read before
+    fn renumber(&mut self, moved:&HashMap<i32,i32>, definitions:&mut HashMap<i32,BooleanFormula>){
+        self.highest_id=moved.values().copied().fold(self.highest_id,i32::max);
+        *definitions=definitions.iter().map(|(x,f)|(*moved.get(x).unwrap_or(x),f.renumber(moved,&self.symbols))).collect();
+        for command in self.commands.iter_mut(){
+            match command{
+                Command::Let(_,formula)|
+                Command::Assert(formula)=>{
+                    *formula=formula.renumber(moved,&self.symbols);
+                },
+                Command::CheckSat|Command::GetModel=>{}
+            }
+        }
+    }
+
+    pub fn get_commands(&self)->&Vec<Command>{
+        &self.commands
+    }
+
+    pub fn get_symbols(&self)->&SymbolTable{
+        &self.symbols
+    }
+
+    // the conjunction of all the assertions, with the definitions expanded
+    pub fn problem(&self)->BooleanFormula{
+        let assertions:Vec<BooleanFormula>=self.commands.iter().filter_map(|command|match command{
+            Command::Assert(formula)=>Some(formula.clone()),
+            _=>None
+        }).collect();
+        BooleanFormula::conjunction(&assertions,&self.symbols)
+    }
+
+    // check-sat answers for the assertions made up to it
+    pub fn run(&self)->Vec<Response>{
+        let mut responses:Vec<Response>=vec![];
+        let mut assertions:Vec<BooleanFormula>=vec![];
+        let mut model:Option<Vec<(String,bool)>>=None;
+        for command in self.commands.iter(){
+            match command{
+                Command::Let(_,_)=>{},
+                Command::Assert(formula)=>{
+                    assertions.push(formula.clone());
+                },
+                Command::CheckSat=>{
+                    let problem=BooleanFormula::conjunction(&assertions,&self.symbols);
+                    match check_sat_dpll_and_find_assignment(&problem){
+                        Some(assignment)=>{
+                            model=Some(self.model_of(&problem,&assignment));
+                            responses.push(Response::Sat);
+                        },
+                        None=>{
+                            model=None;
+                            responses.push(Response::Unsat);
+                        }
+                    }
+                },
+                Command::GetModel=>{
+                    match &model{
+                        Some(values)=>responses.push(Response::Model(values.clone())),
+                        None=>responses.push(Response::NoModel)
+                    }
+                }
+            }
+        }
+        responses
+    }
+
+    fn model_of(&self, problem:&BooleanFormula, assignment:&TruthAssignment)->Vec<(String,bool)>{
+        let mut variables:Vec<i32>=problem.get_variables().iter().copied().collect();
+        variables.sort();
+        variables.into_iter().map(|x|(self.symbols.variable_to_string(x),assignment.get_assignment_or_default(x))).collect()
+    }
+}
+
+#[cfg(test)]
+mod test_script{
+    use super::*;
+    use crate::operators::*;
+
+    #[test]
+    fn definitions_are_expanded(){
+        let source=format!("let both = a{}b\nassert both{}c\n",AND_OPERATOR_SYMBOL,OR_OPERATOR_SYMBOL);
+        let script=Script::from_str(&source).unwrap();
+        assert_eq!(script.get_commands().len(),2);
+        assert_eq!(script.problem().to_string(),format!("(a{}b){}c",AND_OPERATOR_SYMBOL,OR_OPERATOR_SYMBOL));
+    }
+
+    #[test]
+    fn check_sat_and_model(){
+        let source=format!("# a small problem\nassert a{}{}b  # both\ncheck-sat\nget-model\nassert {}a\ncheck-sat\nget-model",
+            AND_OPERATOR_SYMBOL,NEGATION_OPERATOR_SYMBOL,NEGATION_OPERATOR_SYMBOL);
+        let responses=Script::from_str(&source).unwrap().run();
+        let model=Response::Model(vec![("a".to_string(),true),("b".to_string(),false)]);
+        assert_eq!(responses,vec![Response::Sat,model,Response::Unsat,Response::NoModel]);
+        assert_eq!(responses[1].to_string(),"a: true\nb: false");
+    }
+
+    #[test]
+    fn numbers_after_definitions(){
+        // x gets id 1 first, the 1 asserted later is a variable of its own
+        let source=format!("let x = {}\nassert 1\ncheck-sat\nget-model",FALSE_ATOM_SYMBOL);
+        let responses=source.parse::<Script>().unwrap().run();
+        assert_eq!(responses,vec![Response::Sat,Response::Model(vec![("1".to_string(),true)])]);
+        // a moves away from 1 in the assertion and the definition made before
+        let source=format!("assert a\nlet y = a\nassert 1{}y\ncheck-sat\nget-model",AND_OPERATOR_SYMBOL);
+        let script=source.parse::<Script>().unwrap();
+        let model=Response::Model(vec![("1".to_string(),true),("a".to_string(),true)]);
+        assert_eq!(script.run(),vec![Response::Sat,model]);
+        assert_eq!(script.problem().get_variables().len(),2);
+        let source=format!("assert a\nlet y = a\nassert 1{}{}y\ncheck-sat",AND_OPERATOR_SYMBOL,NEGATION_OPERATOR_SYMBOL);
+        assert_eq!(source.parse::<Script>().unwrap().run(),vec![Response::Unsat]);
+    }
+
+    #[test]
+    fn definitions_of_definitions(){
+        let source=format!("let x = 1{o}2\nlet y = {n}x\nassert x\nassert y\ncheck-sat",o=OR_OPERATOR_SYMBOL,n=NEGATION_OPERATOR_SYMBOL);
+        let script=Script::from_str(&source).unwrap();
+        assert_eq!(script.run(),vec![Response::Unsat]);
+        assert_eq!(script.problem().get_variables().len(),2);
+    }
+
+    #[test]
+    fn quantifiers_hide_definitions(){
+        let source=format!("let x = {}\nassert {}x{}x\ncheck-sat",FALSE_ATOM_SYMBOL,EXISTENTIAL_QUANTIFIER_SYMBOL,QUANTIFIER_SEPARATOR_SYMBOL);
+        assert_eq!(Script::from_str(&source).unwrap().run(),vec![Response::Sat]);
+    }
+
+    #[test]
+    fn formula_errors_point_into_the_file(){
+        let source=format!("assert a\nassert b{}$",AND_OPERATOR_SYMBOL);
+        let error=Script::from_str(&source).unwrap_err();
+        assert_eq!(error.span(),Span::new(18,19));
+        assert!(error.render(&source).contains("--> 2:10"));
+    }
+
+    #[test]
+    fn command_errors(){
+        assert!(matches!(Script::from_str("solve"),Err(ScriptError::UnknownCommand{ .. })));
+        assert!(matches!(Script::from_str("let a"),Err(ScriptError::MissingDefinition{ .. })));
+        assert!(matches!(Script::from_str("let 3 = a"),Err(ScriptError::InvalidName{ .. })));
+        assert!(matches!(Script::from_str("check-sat now"),Err(ScriptError::UnexpectedArgument{ .. })));
+        let redefined=Script::from_str("let a = b\nlet a = c").unwrap_err();
+        assert_eq!(redefined,ScriptError::NameInUse { name: "a".to_string(), span: Span::new(14,15) });
+        assert!(matches!(Script::from_str("assert a\nlet a = b"),Err(ScriptError::NameInUse{ .. })));
+    }
+}
@@ -0,0 +1,742 @@
+use std::collections::HashMap;
+
+use crate::formula::{BooleanFormula, Expression};
+use crate::sat::{check_sat_dpll_and_find_assignment, TruthAssignment};
+use crate::symbols::SymbolTable;
+use crate::parse_error::{Span, render_report};
+
+// the Boolean fragment of SMT-LIB 2: constants of sort Bool, the core
+// connectives, let, named terms, the assertion stack and the usual queries
+#[derive(Debug,Clone,PartialEq,Eq)]
+pub enum SExpr{
+    Symbol(String,Span),
+    Keyword(String,Span),
+    Numeral(String,Span),
+    StringLiteral(String,Span),
+    List(Vec<SExpr>,Span)
+}
+
+impl SExpr{
+    pub fn span(&self)->Span{
+        match self{
+            SExpr::Symbol(_,span)|
+            SExpr::Keyword(_,span)|
+            SExpr::Numeral(_,span)|
+            SExpr::StringLiteral(_,span)|
+            SExpr::List(_,span)=>*span
+        }
+    }
+
+    fn as_symbol(&self)->Option<&str>{
+        match self{
+            SExpr::Symbol(name,_)=>Some(name),
+            _=>None
+        }
+    }
+}
+
+// input that is not a sequence of well formed s-expressions, spans are byte offsets
+#[derive(Debug,Clone,PartialEq,Eq)]
+pub struct SmtError{
+    pub message: String,
+    pub label: String,
+    pub span: Span
+}
+
+impl SmtError{
+    fn new(message:&str,label:&str,span:Span)->Self{
+        SmtError { message: message.to_string(), label: label.to_string(), span }
+    }
+
+    pub fn render(&self,source:&str)->String{
+        render_report(source,self.span,&self.message,&self.label)
+    }
+}
+
+impl std::fmt::Display for SmtError{
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}, {} (at {}..{})", self.message, self.label, self.span.start, self.span.end)
+    }
+}
+
+impl std::error::Error for SmtError{}
+
+fn is_symbol_char(c:char)->bool{
+    c.is_ascii_alphanumeric() || "~!@$%^&*_-+=<>.?/".contains(c)
+}
+
+pub fn read_sexprs(source:&str)->Result<Vec<SExpr>,SmtError>{
+    let chars:Vec<(usize,char)>=source.char_indices().collect();
+    let offset=|i:usize|if i<chars.len(){chars[i].0}else{source.len()};
+    // the open lists with the offset of their bracket
+    let mut stack:Vec<(Vec<SExpr>,usize)>=vec![];
+    let mut done:Vec<SExpr>=vec![];
+    let mut index:usize=0;
+    while index<chars.len(){
+        let start=index;
+        let item=match chars[index].1{
+            c if c.is_whitespace()=>{
+                index+=1;
+                continue;
+            },
+            ';'=>{
+                while index<chars.len() && chars[index].1!='\n'{
+                    index+=1;
+                }
+                continue;
+            },
+            '('=>{
+                stack.push((vec![],offset(index)));
+                index+=1;
+                continue;
+            },
+            ')'=>{
+                index+=1;
+                match stack.pop(){
+                    Some((items,open))=>SExpr::List(items,Span::new(open,offset(index))),
+                    None=>{
+                        return Err(SmtError::new("unexpected \")\"","this closes no \"(\"",Span::new(offset(start),offset(index))));
+                    }
+                }
+            },
+            '"'=>{
+                // a doubled quote stands for a quote
+                let mut text="".to_string();
+                index+=1;
+                loop{
+                    if index>=chars.len(){
+                        return Err(SmtError::new("unterminated string literal","expected a closing \"",Span::new(offset(start),source.len())));
+                    }
+                    if chars[index].1=='"'{
+                        if index+1<chars.len() && chars[index+1].1=='"'{
+                            text.push('"');
+                            index+=2;
+                            continue;
+                        }
+                        index+=1;
+                        break;
+                    }
+                    text.push(chars[index].1);
+                    index+=1;
+                }
+                SExpr::StringLiteral(text,Span::new(offset(start),offset(index)))
+            },
+            '|'=>{
+                let mut name="".to_string();
+                index+=1;
+                while index<chars.len() && chars[index].1!='|'{
+                    name.push(chars[index].1);
+                    index+=1;
+                }
+                if index>=chars.len(){
+                    return Err(SmtError::new("unterminated quoted symbol","expected a closing |",Span::new(offset(start),source.len())));
+                }
+                index+=1;
+                SExpr::Symbol(name,Span::new(offset(start),offset(index)))
+            },
+            c if c==':' || is_symbol_char(c)=>{
+                let mut text="".to_string();
+                text.push(c);
+                index+=1;
+                while index<chars.len() && is_symbol_char(chars[index].1){
+                    text.push(chars[index].1);
+                    index+=1;
+                }
+                let span=Span::new(offset(start),offset(index));
+                if let Some(keyword)=text.strip_prefix(':'){
+                    SExpr::Keyword(keyword.to_string(),span)
+                }else if text.chars().all(|c|c.is_ascii_digit()){
+                    SExpr::Numeral(text,span)
+                }else{
+                    SExpr::Symbol(text,span)
+                }
+            },
+            c=>{
+                return Err(SmtError::new(&format!("unexpected character \"{}\"",c),"expected a symbol, a literal or a bracket",
+                    Span::new(offset(index),offset(index+1))));
+            }
+        };
+        match stack.last_mut(){
+            Some((items,_))=>items.push(item),
+            None=>done.push(item)
+        }
+    }
+    if let Some((_,open))=stack.pop(){
+        return Err(SmtError::new("missing closing bracket","this \"(\" is never closed",Span::new(open,open+1)));
+    }
+    Ok(done)
+}
+
+// symbols that are not simple symbols are printed between bars
+fn quote_symbol(name:&str)->String{
+    let simple=!name.is_empty() && name.chars().all(is_symbol_char) && !name.chars().next().unwrap().is_ascii_digit();
+    if simple{
+        name.to_string()
+    }else{
+        format!("|{}|",name)
+    }
+}
+
+struct Assertion{
+    expression: Expression,
+    // set by (assert (! term :named name)), used by get-unsat-core
+    name: Option<String>
+}
+
+#[derive(Default)]
+struct Level{
+    assertions: Vec<Assertion>,
+    declared: Vec<(String,i32)>,
+    defined: Vec<String>
+}
+
+enum CheckResult{
+    Sat(TruthAssignment),
+    Unsat
+}
+
+const SORT_ERROR:&str="only the sort Bool is supported";
+
+// what a command answers, None when it prints nothing
+type Outcome=Result<Option<String>,String>;
+
+pub struct SmtSession{
+    symbols: SymbolTable,
+    definitions: HashMap<String,Expression>,
+    levels: Vec<Level>,
+    next_id: i32,
+    last_result: Option<CheckResult>,
+    print_success: bool,
+    exited: bool
+}
+
+impl Default for SmtSession{
+    fn default()->Self{
+        SmtSession {
+            symbols: SymbolTable::new(),
+            definitions: HashMap::new(),
+            levels: vec![Level::default()],
+            next_id: 1,
+            last_result: None,
+            print_success: false,
+            exited: false
+        }
+    }
+}
+
+impl SmtSession{
+    pub fn new()->Self{
+        Self::default()
+    }
+
+    // the responses of the commands in source, in SMT-LIB syntax; errors in
+    // a command are answered with (error "...") and the following commands run
+    pub fn run(&mut self,source:&str)->Result<Vec<String>,SmtError>{
+        let commands=match read_sexprs(source){
+            Ok(commands)=>commands,
+            Err(e)=>{return Err(e);}
+        };
+        let mut responses:Vec<String>=vec![];
+        for command in commands.iter(){
+            if self.exited{
+                break;
+            }
+            match self.execute(command){
+                Ok(Some(response))=>responses.push(response),
+                Ok(None)=>{
+                    if self.print_success{
+                        responses.push("success".to_string());
+                    }
+                },
+                Err(message)=>responses.push(format!("(error \"{}\")",message.replace('"',"\"\"")))
+            }
+        }
+        Ok(responses)
+    }
+
+    pub fn execute(&mut self,command:&SExpr)->Outcome{
+        let items=match command{
+            SExpr::List(items,_) if !items.is_empty()=>items,
+            _=>{return Err("expected a command".to_string());}
+        };
+        let name=match items[0].as_symbol(){
+            Some(name)=>name,
+            None=>{return Err("expected a command name".to_string());}
+        };
+        let arguments=&items[1..];
+        match name{
+            "set-logic"=>{
+                match arguments{
+                    [SExpr::Symbol(logic,_)] if logic=="QF_BOOL" || logic=="ALL"=>Ok(None),
+                    [SExpr::Symbol(_,_)]=>Ok(Some("unsupported".to_string())),
+                    _=>Err("set-logic expects a logic".to_string())
+                }
+            },
+            "set-option"=>self.set_option(arguments),
+            "set-info"=>Ok(None),
+            "declare-const"=>{
+                match arguments{
+                    [SExpr::Symbol(name,_),sort]=>self.declare(name,sort),
+                    _=>Err("declare-const expects a name and a sort".to_string())
+                }
+            },
+            "declare-fun"=>{
+                match arguments{
+                    [SExpr::Symbol(name,_),SExpr::List(parameters,_),sort] if parameters.is_empty()=>self.declare(name,sort),
+                    [SExpr::Symbol(_,_),SExpr::List(_,_),_]=>Ok(Some("unsupported".to_string())),
+                    _=>Err("declare-fun expects a name, a list of sorts and a sort".to_string())
+                }
+            },
+            "define-fun"=>{
+                match arguments{
+                    [SExpr::Symbol(name,_),SExpr::List(parameters,_),sort,body] if parameters.is_empty()=>{
+                        if !Self::is_bool_sort(sort){
+                            return Err(SORT_ERROR.to_string());
+                        }
+                        if self.is_defined(name){
+                            return Err(format!("{} is already declared",name));
+                        }
+                        let expression=match self.term(body,&mut vec![]){
+                            Ok(expression)=>expression,
+                            Err(e)=>{return Err(e);}
+                        };
+                        self.define(name,expression);
+                        Ok(None)
+                    },
+                    [SExpr::Symbol(_,_),SExpr::List(_,_),_,_]=>Ok(Some("unsupported".to_string())),
+                    _=>Err("define-fun expects a name, parameters, a sort and a body".to_string())
+                }
+            },
+            "assert"=>{
+                let term=match arguments{
+                    [term]=>term,
+                    _=>{return Err("assert expects one term".to_string());}
+                };
+                let name=Self::assertion_name(term);
+                let expression=match self.term(term,&mut vec![]){
+                    Ok(expression)=>expression,
+                    Err(e)=>{return Err(e);}
+                };
+                self.last_result=None;
+                self.levels.last_mut().unwrap().assertions.push(Assertion { expression, name });
+                Ok(None)
+            },
+            "push"|"pop"=>{
+                let count=match arguments{
+                    []=>1,
+                    [SExpr::Numeral(n,_)]=>match n.parse::<usize>(){
+                        Ok(n)=>n,
+                        Err(_)=>{return Err(format!("{} expects a small numeral",name));}
+                    },
+                    _=>{return Err(format!("{} expects a numeral",name));}
+                };
+                self.last_result=None;
+                if name=="push"{
+                    for _ in 0..count{
+                        self.levels.push(Level::default());
+                    }
+                    return Ok(None);
+                }
+                if count>=self.levels.len(){
+                    return Err(format!("cannot pop {} levels, only {} were pushed",count,self.levels.len()-1));
+                }
+                for _ in 0..count{
+                    let level=self.levels.pop().unwrap();
+                    for name in level.defined{
+                        self.definitions.remove(&name);
+                    }
+                }
+                Ok(None)
+            },
+            "check-sat"=>{
+                if !arguments.is_empty(){
+                    return Err("check-sat expects no argument".to_string());
+                }
+                let all:Vec<&Expression>=self.levels.iter().flat_map(|level|level.assertions.iter().map(|a|&a.expression)).collect();
+                match self.check(&all){
+                    Some(assignment)=>{
+                        self.last_result=Some(CheckResult::Sat(assignment));
+                        Ok(Some("sat".to_string()))
+                    },
+                    None=>{
+                        self.last_result=Some(CheckResult::Unsat);
+                        Ok(Some("unsat".to_string()))
+                    }
+                }
+            },
+            "get-model"=>{
+                let assignment=match &self.last_result{
+                    Some(CheckResult::Sat(assignment))=>assignment,
+                    _=>{return Err("model is not available".to_string());}
+                };
+                let mut res="(".to_string();
+                for level in self.levels.iter(){
+                    for (name,id) in level.declared.iter(){
+                        res.push_str(&format!("\n  (define-fun {} () Bool {})",quote_symbol(name),assignment.get_assignment_or_default(*id)));
+                    }
+                }
+                res.push_str("\n)");
+                Ok(Some(res))
+            },
+            "get-unsat-core"=>{
+                match self.last_result{
+                    Some(CheckResult::Unsat)=>{},
+                    _=>{return Err("unsat core is not available".to_string());}
+                }
+                let core:Vec<String>=self.unsat_core().iter().map(|name|quote_symbol(name)).collect();
+                Ok(Some(format!("({})",core.join(" "))))
+            },
+            "echo"=>{
+                match arguments{
+                    [SExpr::StringLiteral(text,_)]=>Ok(Some(format!("\"{}\"",text.replace('"',"\"\"")))),
+                    _=>Err("echo expects a string".to_string())
+                }
+            },
+            "exit"=>{
+                self.exited=true;
+                Ok(None)
+            },
+            _=>Ok(Some("unsupported".to_string()))
+        }
+    }
+
+    fn set_option(&mut self,arguments:&[SExpr])->Outcome{
+        match arguments{
+            [SExpr::Keyword(option,_),SExpr::Symbol(value,_)] if option=="print-success"=>{
+                match value.as_str(){
+                    "true"=>self.print_success=true,
+                    "false"=>self.print_success=false,
+                    _=>{return Err("print-success expects true or false".to_string());}
+                }
+                Ok(None)
+            },
+            [SExpr::Keyword(option,_),_] if option=="produce-models" || option=="produce-unsat-cores"=>Ok(None),
+            [SExpr::Keyword(_,_),_]=>Ok(Some("unsupported".to_string())),
+            _=>Err("set-option expects an option and a value".to_string())
+        }
+    }
+
+    fn is_bool_sort(sort:&SExpr)->bool{
+        sort.as_symbol()==Some("Bool")
+    }
+
+    fn is_defined(&self,name:&str)->bool{
+        self.definitions.contains_key(name) || self.constant(name).is_some()
+    }
+
+    fn constant(&self,name:&str)->Option<i32>{
+        self.levels.iter().rev().flat_map(|level|level.declared.iter()).find(|(declared,_)|declared==name).map(|(_,id)|*id)
+    }
+
+    fn declare(&mut self,name:&str,sort:&SExpr)->Outcome{
+        if !Self::is_bool_sort(sort){
+            return Err(SORT_ERROR.to_string());
+        }
+        if self.is_defined(name){
+            return Err(format!("{} is already declared",name));
+        }
+        // ids are never reused, a constant declared again after a pop is a new one
+        let id=self.next_id;
+        self.next_id+=1;
+        self.symbols.insert(name,id);
+        self.last_result=None;
+        self.levels.last_mut().unwrap().declared.push((name.to_string(),id));
+        Ok(None)
+    }
+
+    fn define(&mut self,name:&str,expression:Expression){
+        self.definitions.insert(name.to_string(),expression);
+        self.levels.last_mut().unwrap().defined.push(name.to_string());
+    }
+
+    fn assertion_name(term:&SExpr)->Option<String>{
+        let items=match term{
+            SExpr::List(items,_)=>items,
+            _=>{return None;}
+        };
+        if items.first().and_then(|x|x.as_symbol())!=Some("!"){
+            return None;
+        }
+        let mut i=2;
+        while i+1<items.len(){
+            if let (SExpr::Keyword(keyword,_),SExpr::Symbol(name,_))=(&items[i],&items[i+1]){
+                if keyword=="named"{
+                    return Some(name.clone());
+                }
+            }
+            i+=2;
+        }
+        None
+    }
+
+    // the bindings of the enclosing lets are searched innermost first
+    fn term(&mut self,term:&SExpr,scopes:&mut Vec<HashMap<String,Expression>>)->Result<Expression,String>{
+        let items=match term{
+            SExpr::Symbol(name,_)=>{
+                if let Some(expression)=scopes.iter().rev().find_map(|scope|scope.get(name)){
+                    return Ok(expression.clone());
+                }
+                if let Some(expression)=self.definitions.get(name){
+                    return Ok(expression.clone());
+                }
+                if let Some(id)=self.constant(name){
+                    return Ok(Expression::Variable(id));
+                }
+                return match name.as_str(){
+                    "true"=>Ok(Expression::Constant(true)),
+                    "false"=>Ok(Expression::Constant(false)),
+                    _=>Err(format!("unknown constant {}",name))
+                };
+            },
+            SExpr::List(items,_) if !items.is_empty()=>items,
+            _=>{return Err("expected a Bool term".to_string());}
+        };
+        let operator=match items[0].as_symbol(){
+            Some(operator)=>operator,
+            None=>{return Err("expected an operator".to_string());}
+        };
+        if operator=="let"{
+            return self.let_term(&items[1..],scopes);
+        }
+        if operator=="!"{
+            return self.annotated_term(&items[1..],scopes);
+        }
+        let mut arguments:Vec<Expression>=vec![];
+        for argument in items[1..].iter(){
+            match self.term(argument,scopes){
+                Ok(expression)=>arguments.push(expression),
+                Err(e)=>{return Err(e);}
+            }
+        }
+        let count=arguments.len();
+        let arity_error=|expected:&str|Err(format!("{} expects {}, got {}",operator,expected,count));
+        match operator{
+            "not"=>{
+                if count!=1{
+                    return arity_error("one argument");
+                }
+                Ok(Expression::Not(Box::new(arguments.remove(0))))
+            },
+            "and"=>Ok(Expression::And(arguments)),
+            "or"=>Ok(Expression::Or(arguments)),
+            "xor"|"=>"|"="|"distinct" if count<2=>arity_error("at least two arguments"),
+            "xor"=>{
+                // left associative
+                let mut arguments=arguments.into_iter();
+                let first=arguments.next().unwrap();
+                Ok(arguments.fold(first,|left,right|Expression::Xor(Box::new(left),Box::new(right))))
+            },
+            "=>"=>{
+                // right associative
+                let mut arguments=arguments.into_iter().rev();
+                let last=arguments.next().unwrap();
+                Ok(arguments.fold(last,|right,left|Expression::Implies(Box::new(left),Box::new(right))))
+            },
+            "="=>{
+                // chainable: every neighbouring pair is equal
+                let pairs=arguments.windows(2).map(|pair|Expression::Iff(Box::new(pair[0].clone()),Box::new(pair[1].clone()))).collect();
+                Ok(Expression::And(pairs))
+            },
+            "distinct"=>{
+                let mut pairs:Vec<Expression>=vec![];
+                for i in 0..count{
+                    for j in i+1..count{
+                        pairs.push(Expression::Xor(Box::new(arguments[i].clone()),Box::new(arguments[j].clone())));
+                    }
+                }
+                Ok(Expression::And(pairs))
+            },
+            "ite"=>{
+                if count!=3{
+                    return arity_error("three arguments");
+                }
+                let otherwise=arguments.pop().unwrap();
+                let then=arguments.pop().unwrap();
+                let condition=arguments.pop().unwrap();
+                Ok(Expression::Ite(Box::new(condition),Box::new(then),Box::new(otherwise)))
+            },
+            _=>Err(format!("unknown operator {}",operator))
+        }
+    }
+
+    // (let ((x t) (y u)) body), the bound terms are read in the enclosing scope
+    fn let_term(&mut self,items:&[SExpr],scopes:&mut Vec<HashMap<String,Expression>>)->Result<Expression,String>{
+        let (bindings,body)=match items{
+            [SExpr::List(bindings,_),body] if !bindings.is_empty()=>(bindings,body),
+            _=>{return Err("let expects a list of bindings and a body".to_string());}
+        };
+        let mut scope:HashMap<String,Expression>=HashMap::new();
+        for binding in bindings.iter(){
+            match binding{
+                SExpr::List(pair,_) if pair.len()==2 && pair[0].as_symbol().is_some()=>{
+                    let expression=match self.term(&pair[1],scopes){
+                        Ok(expression)=>expression,
+                        Err(e)=>{return Err(e);}
+                    };
+                    scope.insert(pair[0].as_symbol().unwrap().to_string(),expression);
+                },
+                _=>{return Err("let expects bindings of the form (name term)".to_string());}
+            }
+        }
+        scopes.push(scope);
+        let res=self.term(body,scopes);
+        scopes.pop();
+        res
+    }
+
+    // (! term :named name) also defines name as the term
+    fn annotated_term(&mut self,items:&[SExpr],scopes:&mut Vec<HashMap<String,Expression>>)->Result<Expression,String>{
+        if items.is_empty(){
+            return Err("! expects a term and attributes".to_string());
+        }
+        let expression=match self.term(&items[0],scopes){
+            Ok(expression)=>expression,
+            Err(e)=>{return Err(e);}
+        };
+        let mut i=1;
+        while i<items.len(){
+            match (&items[i],items.get(i+1)){
+                (SExpr::Keyword(keyword,_),Some(SExpr::Symbol(name,_))) if keyword=="named"=>{
+                    if self.is_defined(name){
+                        return Err(format!("{} is already declared",name));
+                    }
+                    self.define(name,expression.clone());
+                    i+=2;
+                },
+                (SExpr::Keyword(_,_),Some(SExpr::Keyword(_,_)))|(SExpr::Keyword(_,_),None)=>{
+                    i+=1;
+                },
+                (SExpr::Keyword(_,_),Some(_))=>{
+                    i+=2;
+                },
+                _=>{return Err("expected an attribute".to_string());}
+            }
+        }
+        Ok(expression)
+    }
+
+    fn check(&self,expressions:&[&Expression])->Option<TruthAssignment>{
+        let conjunction=Expression::And(expressions.iter().map(|e|(*e).clone()).collect());
+        let formula=BooleanFormula::from_expression(&conjunction,self.symbols.clone());
+        check_sat_dpll_and_find_assignment(&formula)
+    }
+
+    // drops the named assertions one at a time while what is left stays
+    // unsatisfiable, the unnamed ones are always kept
+    fn unsat_core(&self)->Vec<String>{
+        let assertions:Vec<&Assertion>=self.levels.iter().flat_map(|level|level.assertions.iter()).collect();
+        let unnamed:Vec<&Expression>=assertions.iter().filter(|a|a.name.is_none()).map(|a|&a.expression).collect();
+        let mut core:Vec<&Assertion>=assertions.iter().filter(|a|a.name.is_some()).copied().collect();
+        let mut i=0;
+        while i<core.len(){
+            let mut rest=unnamed.clone();
+            rest.extend(core.iter().enumerate().filter(|(j,_)|*j!=i).map(|(_,a)|&a.expression));
+            if self.check(&rest).is_none(){
+                core.remove(i);
+            }else{
+                i+=1;
+            }
+        }
+        core.iter().map(|a|a.name.clone().unwrap()).collect()
+    }
+}
+
+#[cfg(test)]
+mod test_smtlib{
+    use super::*;
+
+    fn run(source:&str)->Vec<String>{
+        SmtSession::new().run(source).unwrap()
+    }
+
+    #[test]
+    fn sat_with_model(){
+        let source="(set-logic QF_BOOL)
+            (declare-const a Bool)
+            (declare-fun b () Bool)
+            (assert (and a (not b)))
+            (check-sat)
+            (get-model)";
+        assert_eq!(run(source),vec!["sat","(\n  (define-fun a () Bool true)\n  (define-fun b () Bool false)\n)"]);
+    }
+
+    #[test]
+    fn connectives(){
+        let source="(declare-const a Bool) (declare-const b Bool) (declare-const c Bool)
+            (assert (xor a b c))
+            (assert (=> a b c))
+            (assert (= a (ite b c (not c))))
+            (assert (distinct a b))
+            (check-sat)
+            (assert (not (= a b c)))
+            (assert a)
+            (assert b)
+            (check-sat)";
+        assert_eq!(run(source),vec!["sat","unsat"]);
+    }
+
+    #[test]
+    fn let_bindings_are_parallel(){
+        let source="(declare-const a Bool)
+            (assert (let ((x a) (y (not a))) (let ((x y) (y x)) (and x (not y)))))
+            (check-sat)
+            (get-model)";
+        assert_eq!(run(source),vec!["sat","(\n  (define-fun a () Bool false)\n)"]);
+    }
+
+    #[test]
+    fn push_and_pop(){
+        let source="(declare-const a Bool)
+            (push 1)
+            (declare-const b Bool)
+            (assert (and a (not a)))
+            (check-sat)
+            (pop 1)
+            (check-sat)
+            (assert b)
+            (pop)";
+        let responses=run(source);
+        assert_eq!(responses[0..2],["unsat","sat"]);
+        assert!(responses[2].starts_with("(error \"unknown constant b"));
+        assert!(responses[3].starts_with("(error \"cannot pop"));
+    }
+
+    #[test]
+    fn unsat_core_of_named_assertions(){
+        let source="(set-option :produce-unsat-cores true)
+            (declare-const a Bool) (declare-const b Bool)
+            (assert (! a :named first))
+            (assert (! b :named second))
+            (assert (! (not a) :named third))
+            (check-sat)
+            (get-unsat-core)";
+        assert_eq!(run(source),vec!["unsat","(first third)"]);
+    }
+
+    #[test]
+    fn responses_to_unsupported_and_wrong_commands(){
+        let source="(set-option :print-success true)
+            (declare-const x Int)
+            (set-logic QF_LIA)
+            (get-model)
+            (echo \"done\")
+            (exit)
+            (check-sat)";
+        assert_eq!(run(source),vec!["success","(error \"only the sort Bool is supported\")","unsupported",
+            "(error \"model is not available\")","\"done\"","success"]);
+    }
+
+    #[test]
+    fn quoted_symbols(){
+        let source="(declare-const |door open| Bool) (assert |door open|) (check-sat) (get-model)";
+        assert_eq!(run(source)[1],"(\n  (define-fun |door open| () Bool true)\n)");
+    }
+
+    #[test]
+    fn malformed_input(){
+        let error=SmtSession::new().run("(assert (and a b)\n(check-sat)").unwrap_err();
+        assert_eq!(error.span,Span::new(0,1));
+        assert!(SmtSession::new().run("(check-sat))").is_err());
+        assert!(SmtSession::new().run("(echo \"unterminated)").is_err());
+    }
+}
@@ -0,0 +1,240 @@
+use std::collections::{BTreeMap,HashMap};
+
+use crate::operators::*;
+
+// maps variable names such as door_open or x[3] to the ids used inside formulas
+#[derive(Debug,Clone,Default,PartialEq,Eq)]
+pub struct SymbolTable{
+    ids: BTreeMap<String,i32>,
+    names: HashMap<i32,String>
+}
+
+impl SymbolTable{
+    pub fn new()->Self{
+        Self::default()
+    }
+
+    pub fn insert(&mut self,name:&str,id:i32){
+        if let Some(old_name)=self.names.insert(id,name.to_string()){
+            self.ids.remove(&old_name);
+        }
+        if let Some(old_id)=self.ids.insert(name.to_string(),id){
+            if old_id!=id{
+                self.names.remove(&old_id);
+            }
+        }
+    }
+
+    pub fn get_id(&self,name:&str)->Option<i32>{
+        self.ids.get(name).copied()
+    }
+
+    pub fn get_name(&self,id:i32)->Option<&String>{
+        self.names.get(&id)
+    }
+
+    pub fn len(&self)->usize{
+        self.ids.len()
+    }
+
+    pub fn is_empty(&self)->bool{
+        self.ids.is_empty()
+    }
+
+    pub fn max_id(&self)->i32{
+        self.names.keys().copied().max().unwrap_or(0)
+    }
+
+    // names in alphabetical order
+    pub fn iter(&self)->impl Iterator<Item=(&String,&i32)>{
+        self.ids.iter()
+    }
+
+    // the name of the variable if it has one, its number otherwise
+    pub fn variable_to_string(&self,variable:i32)->String{
+        self.variable_to_string_with_syntax(variable,&Syntax::default())
+    }
+
+    pub fn variable_to_string_with_syntax(&self,variable:i32,syntax:&Syntax)->String{
+        if let Some(name)=self.names.get(&variable){
+            return name.clone();
+        }
+        if variable>=0{
+            variable.to_string()
+        }else{
+            format!("{}{}",syntax.fresh_variable(),-variable)
+        }
+    }
+}
+
+pub fn is_identifier_start(c:char)->bool{
+    c.is_ascii_alphabetic() || c=='_'
+}
+
+pub fn is_identifier_char(c:char)->bool{
+    c.is_ascii_alphanumeric() || c=='_'
+}
+
+// names that would be read back as something else are reserved
+pub fn is_valid_name(name:&str,syntax:&Syntax)->bool{
+    let chars:Vec<char>=name.chars().collect();
+    if chars.is_empty() || !is_identifier_start(chars[0]){
+        return false;
+    }
+    if chars.len()==1 && [syntax.true_atom(),syntax.false_atom(),syntax.exists(),syntax.for_all()].contains(&chars[0]){
+        return false;
+    }
+    if is_fresh_variable_name(name,syntax){
+        return false;
+    }
+    let mut i=1;
+    while i<chars.len() && is_identifier_char(chars[i]){
+        i+=1;
+    }
+    while i<chars.len(){
+        if chars[i]!='['{
+            return false;
+        }
+        i+=1;
+        let start=i;
+        while i<chars.len() && chars[i].is_ascii_digit(){
+            i+=1;
+        }
+        if i==start || i>=chars.len() || chars[i]!=']'{
+            return false;
+        }
+        i+=1;
+    }
+    true
+}
+
+pub fn is_fresh_variable_name(name:&str,syntax:&Syntax)->bool{
+    let mut chars=name.chars();
+    if chars.next()!=Some(syntax.fresh_variable()){
+        return false;
+    }
+    let digits:Vec<char>=chars.collect();
+    !digits.is_empty() && digits[0]!='0' && digits.iter().all(|c|c.is_ascii_digit())
+}
+
+#[cfg(test)]
+mod test_symbols{
+    use super::*;
+    use crate::formula::BooleanFormula;
+    use crate::sat::TruthAssignment;
+
+    #[test]
+    fn valid_names(){
+        let syntax=Syntax::default();
+        assert!(is_valid_name("door_open",&syntax));
+        assert!(is_valid_name("x[3]",&syntax));
+        assert!(is_valid_name("grid[1][12]",&syntax));
+        assert!(is_valid_name("Enabled",&syntax));
+        assert!(!is_valid_name("x[]",&syntax));
+        assert!(!is_valid_name("x[3",&syntax));
+        assert!(!is_valid_name("3x",&syntax));
+        assert!(!is_valid_name(&TRUE_ATOM_SYMBOL.to_string(),&syntax));
+        assert!(!is_valid_name(&format!("{}12",FRESH_VARIABLE_SYMBOL),&syntax));
+    }
+
+    #[test]
+    fn names_are_printed_back(){
+        let string=format!("door_open{}{}x[3]",AND_OPERATOR_SYMBOL,NEGATION_OPERATOR_SYMBOL);
+        let formula=BooleanFormula::from_string(string.clone()).unwrap();
+        assert_eq!(formula.to_string(),string);
+        assert_eq!(formula.get_symbols().len(),2);
+        let id=formula.get_variable_id("x[3]").unwrap();
+        assert_eq!(formula.get_variable_name(id),"x[3]");
+    }
+
+    #[test]
+    fn names_do_not_collide_with_numbers(){
+        let string=format!("door{}1{}7",OR_OPERATOR_SYMBOL,OR_OPERATOR_SYMBOL);
+        let formula=BooleanFormula::from_string(string.clone()).unwrap();
+        assert_eq!(formula.get_variables().len(),3);
+        assert_eq!(formula.get_variable_id("door"),Some(8));
+        assert_eq!(formula.to_string(),string);
+    }
+
+    #[test]
+    fn names_do_not_collide_with_the_largest_numbers(){
+        for string in [format!("door{}2147483647",OR_OPERATOR_SYMBOL),format!("2147483646{}door{}2147483647{}window",OR_OPERATOR_SYMBOL,OR_OPERATOR_SYMBOL,OR_OPERATOR_SYMBOL)]{
+            let formula=BooleanFormula::from_string(string.clone()).unwrap();
+            assert_eq!(formula.to_string(),string);
+            assert_eq!(formula.get_variables().len(),string.split(OR_OPERATOR_SYMBOL).count());
+            assert!(formula.get_variable_id("door").unwrap()<i32::MAX-1);
+        }
+        let formula=BooleanFormula::from_conventional_str("alarm & !2147483647").unwrap();
+        assert_eq!(formula.to_conventional_string(),"alarm & !2147483647");
+        assert_eq!(formula.get_variable_id("alarm"),Some(1));
+    }
+
+    #[test]
+    fn reserved_letters_inside_names(){
+        // names may start with the symbols of constants, quantifiers and fresh variables
+        let string=format!("{e}Tick{s}(Tick{a}Fault{a}foo{a}{e}lse)",
+            e=EXISTENTIAL_QUANTIFIER_SYMBOL,s=QUANTIFIER_SEPARATOR_SYMBOL,a=AND_OPERATOR_SYMBOL);
+        let formula=BooleanFormula::from_string(string.clone()).unwrap();
+        assert_eq!(formula.get_symbols().len(),4);
+        assert_eq!(formula.to_string(),string);
+        let constants=format!("{}{}{}",TRUE_ATOM_SYMBOL,OR_OPERATOR_SYMBOL,FALSE_ATOM_SYMBOL);
+        assert!(BooleanFormula::from_string(constants).unwrap().get_symbols().is_empty());
+    }
+
+    #[test]
+    fn quantifier_over_name(){
+        let string=format!("{}door{}(door{}window)",UNIVERSAL_QUANTIFIER_SYMBOL,QUANTIFIER_SEPARATOR_SYMBOL,OR_OPERATOR_SYMBOL);
+        let formula=BooleanFormula::from_string(string.clone()).unwrap();
+        assert_eq!(formula.to_string(),string);
+        let without=formula.without_quantifiers();
+        assert_eq!(without.to_string(),"window");
+    }
+
+    #[test]
+    fn shared_table_between_formulas(){
+        let mut table=SymbolTable::new();
+        let first=BooleanFormula::from_string_with_symbols("a".to_string(),&mut table).unwrap();
+        let second=BooleanFormula::from_string_with_symbols(format!("b{}a",AND_OPERATOR_SYMBOL),&mut table).unwrap();
+        assert_eq!(first.get_variable_id("a"),second.get_variable_id("a"));
+        assert_ne!(second.get_variable_id("a"),second.get_variable_id("b"));
+    }
+
+    #[test]
+    fn shared_table_numbers_take_over_ids(){
+        let mut table=SymbolTable::new();
+        let first=BooleanFormula::from_string_with_symbols("a".to_string(),&mut table).unwrap();
+        assert_eq!(first.get_variable_id("a"),Some(1));
+        // 1 is a plain number here, so a moves to a new id
+        let string=format!("a{}{}1",AND_OPERATOR_SYMBOL,NEGATION_OPERATOR_SYMBOL);
+        let second=BooleanFormula::from_string_with_symbols(string.clone(),&mut table).unwrap();
+        assert_eq!(second.to_string(),string);
+        assert_eq!(second.get_variables().len(),2);
+        assert!(crate::sat::check_sat_dpll(&second));
+        assert_eq!(table.get_id("a"),Some(2));
+        let third=BooleanFormula::from_string_with_symbols("2".to_string(),&mut table).unwrap();
+        assert_eq!(third.to_string(),"2");
+        assert_ne!(table.get_id("a"),Some(2));
+        assert!(crate::sat::check_sat_dpll(&(first&second)));
+    }
+
+    #[test]
+    fn entailment_aligns_names(){
+        // parsed separately, a and b both get id 1
+        let left=BooleanFormula::from_string(format!("a{}b",AND_OPERATOR_SYMBOL)).unwrap();
+        let right="b".parse::<BooleanFormula>().unwrap();
+        let entailment=left.entail(&right);
+        assert_eq!(entailment.to_string(),format!("(a{}b){}b",AND_OPERATOR_SYMBOL,IMPL_OPERATOR_SYMBOL));
+        let mut assignment=TruthAssignment::new();
+        assignment.add_assignment(entailment.get_variable_id("a").unwrap(),true);
+        assert!(entailment.evaluate(&assignment));
+    }
+
+    #[test]
+    fn cnf_printing_uses_names(){
+        let string=format!("door{}window",OR_OPERATOR_SYMBOL);
+        let formula=BooleanFormula::from_string(string).unwrap();
+        let cnf=formula.to_cnf_representation();
+        let printed=cnf.to_string();
+        assert!(printed.contains("door") && printed.contains("window"));
+    }
+}
@@ -0,0 +1,187 @@
+use crate::formula::{BooleanFormula, Term};
+use crate::sat::TruthAssignment;
+use crate::symbols::SymbolTable;
+
+pub const DEFAULT_TRUTH_TABLE_MAX_VARIABLES:usize=16;
+// the row numbers have to fit in a usize, any larger limit is lowered to this one
+pub const MAX_TRUTH_TABLE_VARIABLES:usize=usize::BITS as usize-1;
+
+// the value of a formula under every assignment of its variables, the variables
+// are ordered by id and row r assigns the first one the highest bit of r, so the
+// rows run from all false to all true
+#[derive(Debug,Clone,PartialEq,Eq)]
+pub struct TruthTable{
+    variables: Vec<i32>,
+    values: Vec<bool>,
+    symbols: SymbolTable
+}
+
+impl TruthTable{
+    pub(crate) fn from_formula(formula:&BooleanFormula, max_variables:usize)->Result<Self,String>{
+        let mut variables:Vec<i32>=formula.get_variables().iter().copied().collect();
+        variables.sort();
+        let max_variables=max_variables.min(MAX_TRUTH_TABLE_VARIABLES);
+        if variables.len()>max_variables{
+            return Err(truth_table_limit_error(variables.len(),max_variables));
+        }
+        let mut table=TruthTable { variables, values: vec![], symbols: formula.get_symbols().clone() };
+        for row in 0..table.row_count(){
+            let value=formula.evaluate(&table.get_assignment(row));
+            table.values.push(value);
+        }
+        Ok(table)
+    }
+
+    pub fn get_variables(&self)->&Vec<i32>{
+        &self.variables
+    }
+
+    pub fn get_symbols(&self)->&SymbolTable{
+        &self.symbols
+    }
+
+    pub fn row_count(&self)->usize{
+        1<<self.variables.len()
+    }
+
+    pub fn get_value(&self, row:usize)->bool{
+        self.values[row]
+    }
+
+    pub fn get_assignment(&self, row:usize)->TruthAssignment{
+        let mut assignment=TruthAssignment::new();
+        for (variable,value) in self.variables.iter().zip(self.row_bits(row)){
+            assignment.add_assignment(*variable,value);
+        }
+        assignment
+    }
+
+    fn row_bits(&self, row:usize)->Vec<bool>{
+        let n=self.variables.len();
+        (0..n).map(|i|row>>(n-1-i)&1==1).collect()
+    }
+
+    // a brute force answer, to check the solvers against
+    pub fn is_satisfiable(&self)->bool{
+        self.values.iter().any(|value|*value)
+    }
+
+    pub fn is_valid(&self)->bool{
+        self.values.iter().all(|value|*value)
+    }
+
+    pub fn find_row(&self, value:bool)->Option<usize>{
+        self.values.iter().position(|x|*x==value)
+    }
+
+    // one minterm per true row
+    pub(crate) fn minterms(&self)->Vec<Term>{
+        (0..self.row_count()).filter(|row|self.values[*row]).map(|row|self.term(row,true)).collect()
+    }
+
+    // one clause per false row, as the term of its literals
+    pub(crate) fn maxterms(&self)->Vec<Term>{
+        (0..self.row_count()).filter(|row|!self.values[*row]).map(|row|self.term(row,false)).collect()
+    }
+
+    fn term(&self, row:usize, polarity:bool)->Term{
+        self.variables.iter().copied().zip(self.row_bits(row).into_iter().map(|bit|bit==polarity)).collect()
+    }
+
+    fn header(&self)->Vec<String>{
+        self.variables.iter().map(|x|self.symbols.variable_to_string(*x)).collect()
+    }
+
+    pub fn to_csv(&self)->String{
+        let mut header=self.header();
+        header.push("value".to_string());
+        let mut res=format!("{}\n",header.join(","));
+        for row in 0..self.row_count(){
+            let mut cells:Vec<String>=self.row_bits(row).into_iter().map(|bit|(bit as u8).to_string()).collect();
+            cells.push((self.values[row] as u8).to_string());
+            res.push_str(&format!("{}\n",cells.join(",")));
+        }
+        res
+    }
+}
+
+impl std::fmt::Display for TruthTable{
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let header=self.header();
+        writeln!(f,"{} | value",header.join(" "))?;
+        for row in 0..self.row_count(){
+            let cells:Vec<String>=header.iter().zip(self.row_bits(row)).map(|(name,bit)|{
+                format!("{:<width$}",bit as u8,width=name.len())
+            }).collect();
+            writeln!(f,"{} | {}",cells.join(" "),self.values[row] as u8)?;
+        }
+        Ok(())
+    }
+}
+
+fn truth_table_limit_error(variables:usize, max_variables:usize)->String{
+    format!("truth table aborted: {} variables exceed the limit of {}",variables,max_variables)
+}
+
+#[cfg(test)]
+mod test_truth_table{
+    use super::*;
+    use crate::sat::{check_sat_dpll, check_validity_dpll, equivalent};
+
+    fn parse(s:&str)->BooleanFormula{
+        BooleanFormula::from_conventional_str(s).unwrap()
+    }
+
+    #[test]
+    fn rows_are_in_counting_order(){
+        let table=parse("a -> b").truth_table().unwrap();
+        assert_eq!(table.row_count(),4);
+        let values:Vec<bool>=(0..4).map(|row|table.get_value(row)).collect();
+        assert_eq!(values,vec![true,true,false,true]);
+        assert_eq!(table.to_string(),"a b | value\n0 0 | 1\n0 1 | 1\n1 0 | 0\n1 1 | 1\n");
+        assert_eq!(table.to_csv(),"a,b,value\n0,0,1\n0,1,1\n1,0,0\n1,1,1\n");
+    }
+
+    #[test]
+    fn columns_are_as_wide_as_the_names(){
+        let table=parse("door & !alarm").truth_table().unwrap();
+        assert_eq!(table.to_string().lines().nth(2).unwrap(),"0    1     | 0");
+    }
+
+    #[test]
+    fn canonical_forms(){
+        let formula=parse("(a ^ b) | c");
+        let table=formula.truth_table().unwrap();
+        let dnf=BooleanFormula::canonical_dnf(&table);
+        let cnf=BooleanFormula::canonical_cnf(&table);
+        assert!(dnf.is_dnf());
+        assert!(cnf.is_cnf());
+        assert!(equivalent(&formula,&dnf).is_ok());
+        assert!(equivalent(&formula,&cnf).is_ok());
+        assert_eq!(dnf.truth_table().unwrap(),table);
+        assert_eq!(BooleanFormula::canonical_dnf(&parse("a & !a").truth_table().unwrap()).to_conventional_string(),"false");
+        assert_eq!(BooleanFormula::canonical_cnf(&parse("a | !a").truth_table().unwrap()).to_conventional_string(),"true");
+    }
+
+    #[test]
+    fn limit(){
+        let formula=parse("a & b & c");
+        assert!(formula.truth_table_with_limit(2).is_err());
+        assert!(formula.truth_table_with_limit(3).is_ok());
+        // no shift past the width of the row numbers, whatever the limit
+        let wide=(0..70).map(|i|BooleanFormula::var(i+1)).reduce(|a,b|a|b).unwrap();
+        let error=wide.truth_table_with_limit(usize::MAX).unwrap_err();
+        assert_eq!(error,truth_table_limit_error(70,MAX_TRUTH_TABLE_VARIABLES));
+    }
+
+    #[test]
+    fn agrees_with_the_solver(){
+        let inputs=["a & !a","a | !a","(a -> b) & a & !b","exactly(2, a, b, c) & !a","(a ? b : c) <-> (a & b | !a & c)"];
+        for input in inputs{
+            let formula=parse(input);
+            let table=formula.truth_table().unwrap();
+            assert_eq!(table.is_satisfiable(),check_sat_dpll(&formula),"{}",input);
+            assert_eq!(table.is_valid(),check_validity_dpll(&formula),"{}",input);
+        }
+    }
+}